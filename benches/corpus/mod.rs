@@ -0,0 +1,229 @@
+//! Benchmark corpora.
+//!
+//! The canonical documents from nativejson-benchmark (`twitter.json`,
+//! `citm_catalog.json`, `canada.json`) are read from `benches/data/` when
+//! present, see `benches/README.md`. Otherwise a synthetic document of the
+//! same shape and roughly the same size is generated, so the suite always
+//! runs.
+
+use std::{fmt::Write, fs, path::Path};
+
+pub struct Corpus {
+    pub name: &'static str,
+    pub data: String,
+    pub synthetic: bool,
+}
+
+pub fn all() -> Vec<Corpus> {
+    vec![
+        load("twitter", twitter),
+        load("citm_catalog", citm_catalog),
+        load("canada", canada),
+    ]
+}
+
+fn load(name: &'static str, generate: fn() -> String) -> Corpus {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/data")
+        .join(format!("{}.json", name));
+    match fs::read_to_string(path) {
+        Ok(data) => Corpus {
+            name,
+            data,
+            synthetic: false,
+        },
+        Err(_) => Corpus {
+            name,
+            data: generate(),
+            synthetic: true,
+        },
+    }
+}
+
+/// xorshift, deterministic across runs
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn float(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn words(&mut self, out: &mut String, n: u64) {
+        const WORDS: [&str; 12] = [
+            "lorem",
+            "ipsum",
+            "dolor",
+            "Arrière-scène",
+            "\\u3042\\u3044",
+            "sit",
+            "amet",
+            "\\\"quoted\\\"",
+            "日本語",
+            "consectetur",
+            "http:\\/\\/t.co\\/abc",
+            "\\n",
+        ];
+        for i in 0..n {
+            if i > 0 {
+                out.push(' ');
+            }
+            out.push_str(WORDS[self.below(WORDS.len() as u64) as usize]);
+        }
+    }
+}
+
+/// string-heavy objects with moderate nesting
+fn twitter() -> String {
+    let mut rng = Rng(0x7717_7e12);
+    let mut out = String::from("{\"statuses\": [\n");
+    for i in 0..240 {
+        if i > 0 {
+            out.push_str(",\n");
+        }
+        let id = 505_874_924_095_815_700u64 + rng.below(1 << 20);
+        write!(
+            out,
+            "  {{\"id\": {}, \"id_str\": \"{}\", \"text\": \"",
+            id, id
+        )
+        .unwrap();
+        rng.words(&mut out, 12);
+        write!(
+            out,
+            "\", \"user\": {{\"id\": {}, \"name\": \"",
+            rng.below(1 << 31)
+        )
+        .unwrap();
+        rng.words(&mut out, 2);
+        out.push_str("\", \"description\": \"");
+        rng.words(&mut out, 20);
+        write!(
+            out,
+            "\", \"followers_count\": {}, \"verified\": false, \"profile_image_url\": null}}, \
+             \"entities\": {{\"hashtags\": [], \"urls\": [], \"user_mentions\": \
+             [{{\"screen_name\": \"a{}\", \"indices\": [0, {}]}}]}}, \
+             \"retweet_count\": {}, \"favorited\": false, \"lang\": \"ja\"}}",
+            rng.below(10_000),
+            rng.below(1000),
+            rng.below(140),
+            rng.below(100)
+        )
+        .unwrap();
+    }
+    out.push_str("\n], \"search_metadata\": {\"count\": 240, \"query\": \"%23lorem\"}}\n");
+    out
+}
+
+/// integer-heavy objects keyed by numeric ids
+fn citm_catalog() -> String {
+    let mut rng = Rng(0xc17a_ca7a);
+    let mut out = String::from("{\n\"areaNames\": {");
+    for i in 0..400 {
+        if i > 0 {
+            out.push(',');
+        }
+        write!(out, "\n  \"{}\": \"", 205_705_993 + i).unwrap();
+        rng.words(&mut out, 2);
+        out.push('"');
+    }
+    out.push_str("\n},\n\"events\": {");
+    for i in 0..1800 {
+        if i > 0 {
+            out.push(',');
+        }
+        let id = 138_586_341 + i;
+        write!(
+            out,
+            "\n  \"{}\": {{\"description\": null, \"id\": {}, \"logo\": null, \"name\": \"",
+            id, id
+        )
+        .unwrap();
+        rng.words(&mut out, 3);
+        write!(
+            out,
+            "\", \"subTopicIds\": [{}, {}], \"topicIds\": [{}]}}",
+            337_184_269 + rng.below(100),
+            337_184_283 + rng.below(100),
+            324_846_099 + rng.below(100)
+        )
+        .unwrap();
+    }
+    out.push_str("\n},\n\"performances\": [");
+    for i in 0..2400 {
+        if i > 0 {
+            out.push(',');
+        }
+        write!(
+            out,
+            "\n  {{\"eventId\": {}, \"id\": {}, \"prices\": [",
+            138_586_341 + rng.below(1800),
+            339_887_544 + i
+        )
+        .unwrap();
+        for j in 0..3 {
+            if j > 0 {
+                out.push_str(", ");
+            }
+            write!(
+                out,
+                "{{\"amount\": {}, \"audienceSubCategoryId\": 337100890, \"seatCategoryId\": {}}}",
+                rng.below(100_000),
+                338_937_295 + rng.below(10)
+            )
+            .unwrap();
+        }
+        write!(
+            out,
+            "], \"seatCategories\": [{{\"areas\": [{{\"areaId\": {}, \"blockIds\": []}}], \
+             \"seatCategoryId\": 338937295}}], \"seatMapImage\": null, \"start\": {}, \
+             \"venueCode\": \"PLEYEL_PLEYEL\"}}",
+            205_705_993 + rng.below(400),
+            1_372_701_600_000u64 + rng.below(1 << 30)
+        )
+        .unwrap();
+    }
+    out.push_str("\n]\n}\n");
+    out
+}
+
+/// deeply nested arrays of floating point coordinates
+fn canada() -> String {
+    let mut rng = Rng(0xca7a_dae5);
+    let mut out = String::from(
+        "{\"type\": \"FeatureCollection\", \"features\": [{\"type\": \"Feature\", \
+         \"properties\": {\"name\": \"Canada\"}, \"geometry\": {\"type\": \"Polygon\", \
+         \"coordinates\": [",
+    );
+    for i in 0..480 {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("\n[");
+        for j in 0..115 {
+            if j > 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                "[{:.15},{:.15}]",
+                -141.0 + rng.float() * 88.0,
+                41.0 + rng.float() * 42.0
+            )
+            .unwrap();
+        }
+        out.push(']');
+    }
+    out.push_str("\n]}}]}\n");
+    out
+}
@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+mod corpus;
+
+fn bench_parse(c: &mut Criterion) {
+    for corpus in corpus::all() {
+        let data = corpus.data.as_str();
+        if let Err(err) = nom_json_parser::parse(data) {
+            panic!("{} doesn't parse:\n{}", corpus.name, err);
+        }
+        let name = if corpus.synthetic {
+            format!("parse/{} (synthetic)", corpus.name)
+        } else {
+            format!("parse/{}", corpus.name)
+        };
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function("nom_json_parser", |b| {
+            b.iter(|| nom_json_parser::parse(black_box(data)))
+        });
+        group.bench_function("nom_json_parser/fast", |b| {
+            b.iter(|| nom_json_parser::parse_fast(black_box(data)))
+        });
+        group.bench_function("nom_json_parser/borrowed", |b| {
+            let options = nom_json_parser::ParserOptions::default();
+            b.iter(|| nom_json_parser::parse_borrowed(black_box(data), &options))
+        });
+        group.bench_function("serde_json", |b| {
+            b.iter(|| serde_json::from_str::<serde_json::Value>(black_box(data)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
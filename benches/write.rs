@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use nom_json_parser::WriteOptions;
+
+mod corpus;
+
+fn bench_write(c: &mut Criterion) {
+    for corpus in corpus::all() {
+        let value = nom_json_parser::parse(&corpus.data).unwrap();
+        let serde_value: serde_json::Value = serde_json::from_str(&corpus.data).unwrap();
+        let name = if corpus.synthetic {
+            format!("write/{} (synthetic)", corpus.name)
+        } else {
+            format!("write/{}", corpus.name)
+        };
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(value.to_string().len() as u64));
+        group.bench_function("nom_json_parser", |b| {
+            b.iter(|| black_box(&value).to_string_with(&WriteOptions::compact()))
+        });
+        group.bench_function("nom_json_parser/pretty", |b| {
+            b.iter(|| black_box(&value).to_string_with(&WriteOptions::pretty()))
+        });
+        group.bench_function("serde_json", |b| {
+            b.iter(|| serde_json::to_string(black_box(&serde_value)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_write);
+criterion_main!(benches);
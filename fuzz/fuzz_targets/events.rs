@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_json_parser::{parse, JsonEvents};
+
+fuzz_target!(|data: &str| {
+    let mut events = JsonEvents::new(data);
+    let mut ok = true;
+    for event in events.by_ref() {
+        ok = event.is_ok();
+    }
+    // the events check no more than the parser does
+    if parse(data).is_ok() {
+        assert!(ok, "events of a valid document failed");
+        assert_eq!(events.depth(), 0);
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_json_parser::{parse, parse_with, ParserOptions};
+
+fuzz_target!(|data: &str| {
+    let _ = parse(data);
+    let _ = parse_with(data, &ParserOptions::strict());
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_json_parser::fuzzing;
+
+fuzz_target!(|data: &str| {
+    if let Some((n, rest)) = fuzzing::parse_number(data) {
+        // whatever was accepted must agree with the standard library
+        let text = &data[..data.len() - rest.len()];
+        assert_eq!(text.parse::<f64>().ok(), n.as_f64());
+    }
+});
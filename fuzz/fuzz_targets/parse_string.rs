@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_json_parser::fuzzing;
+
+fuzz_target!(|data: &str| {
+    let _ = fuzzing::parse_string(data);
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nom_json_parser::{parse_with, ParserOptions, WriteOptions};
+
+fuzz_target!(|data: &str| {
+    // strict, so that every value parsed can be written as it is
+    let options = ParserOptions::strict();
+    let Ok(value) = parse_with(data, &options) else {
+        return;
+    };
+    for write in [
+        WriteOptions::compact(),
+        WriteOptions::pretty().width(Some(20)),
+        WriteOptions::relaxed().js_safe(true).html_safe(true),
+    ] {
+        let text = value.to_string_with(&write);
+        match parse_with(&text, &options) {
+            Ok(reparsed) => assert_eq!(reparsed, value, "{}", text),
+            Err(err) => panic!("{} doesn't parse: {}", text, err),
+        }
+    }
+});
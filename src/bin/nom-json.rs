@@ -0,0 +1,130 @@
+//! Command-line tools for JSON documents.
+//!
+//! `nom-json explain [--at POINTER] FILE...` merges the files as layers of
+//! merge patches, later files taking precedence, and prints every value of
+//! the result with the file that set it.
+//!
+//! `nom-json codegen [--lang rust|typescript|json-schema] [--name NAME] FILE...`
+//! prints type definitions for documents like the sample files.
+
+use std::{env, fmt::Write, fs, process::ExitCode};
+
+use nom_json_parser::{merge_layers, parse, JsonPointer, JsonValue, Shape};
+
+const USAGE: &str = "usage: nom-json explain [--at POINTER] FILE...
+       nom-json codegen [--lang rust|typescript|json-schema] [--name NAME] FILE...";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let output = match args.split_first() {
+        Some((command, args)) if command == "explain" => run_explain(args),
+        Some((command, args)) if command == "codegen" => run_codegen(args),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match output {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("nom-json: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// the documents of `files`, in order
+fn read_files(files: &[String]) -> Result<Vec<JsonValue>, String> {
+    if files.is_empty() {
+        return Err(USAGE.to_owned());
+    }
+    let mut docs = Vec::with_capacity(files.len());
+    for file in files {
+        let text = fs::read_to_string(file).map_err(|err| format!("{}: {}", file, err))?;
+        let doc =
+            parse(&text).map_err(|err| format!("{}: {}", file, err.render(&text).trim_end()))?;
+        docs.push(doc);
+    }
+    Ok(docs)
+}
+
+fn run_explain(args: &[String]) -> Result<String, String> {
+    let (at, files) = match args {
+        [flag, pointer, files @ ..] if flag == "--at" => {
+            let pointer = JsonPointer::parse_any(pointer).map_err(|err| err.to_string())?;
+            (pointer, files)
+        }
+        files => (JsonPointer::root(), files),
+    };
+    let layers = read_files(files)?;
+    let sources: Vec<&str> = files.iter().map(String::as_str).collect();
+    Ok(explain(&layers, &sources, &at))
+}
+
+fn run_codegen(mut args: &[String]) -> Result<String, String> {
+    let (mut lang, mut name) = ("rust", "Root");
+    loop {
+        match args {
+            [flag, value, ..] if flag == "--lang" => lang = value,
+            [flag, value, ..] if flag == "--name" => name = value,
+            _ => break,
+        }
+        args = &args[2..];
+    }
+    let shape = Shape::infer(&read_files(args)?);
+    match lang {
+        "rust" => Ok(shape.to_rust(name)),
+        "typescript" => Ok(shape.to_typescript(name)),
+        "json-schema" => {
+            let schema = shape.to_json_schema();
+            Ok(schema.to_string() + "\n")
+        }
+        lang => Err(format!("unknown language {:?}", lang)),
+    }
+}
+
+/// One `pointer = value  (source)` line per leaf at or inside `at`.
+fn explain(layers: &[JsonValue], sources: &[&str], at: &JsonPointer) -> String {
+    let (merged, provenance) = merge_layers(layers);
+    let mut output = String::new();
+    for (pointer, layer) in provenance.within(at) {
+        let value = merged
+            .pointer(pointer.as_str())
+            .map(|leaf| leaf.to_string())
+            .unwrap_or_default();
+        let _ = writeln!(output, "{} = {}  ({})", pointer, value, sources[layer]);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_json_parser::{parse, JsonPointer};
+
+    use super::explain;
+
+    #[test]
+    fn test_explain() {
+        let layers = [
+            r#"{"server": {"host": "localhost", "port": 80}, "tags": ["a"]}"#,
+            r#"{"server": {"port": 8080, "name": "x\"y"}}"#,
+        ]
+        .map(|layer| parse(layer).unwrap());
+        let sources = ["default.json", "local.json"];
+        assert_eq!(
+            explain(&layers, &sources, &JsonPointer::root()),
+            "/server/host = \"localhost\"  (default.json)\n\
+             /server/name = \"x\\\"y\"  (local.json)\n\
+             /server/port = 8080  (local.json)\n\
+             /tags = [\"a\"]  (default.json)\n"
+        );
+        let at = JsonPointer::parse_any("server.port").unwrap();
+        assert_eq!(
+            explain(&layers, &sources, &at),
+            "/server/port = 8080  (local.json)\n"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Parsing the bodies of HTTP requests and responses.
+
+use crate::{decode_utf8, parse_with, JsonValue, LineIndex, ParseError, ParserOptions};
+
+/// Parse an HTTP body with the default options, checking its `Content-Type`
+/// header first.
+///
+/// The media type must be `application/json` or `application/*+json`, in any
+/// case, and a `charset` parameter, if any, `utf-8`: RFC 8259 requires JSON
+/// exchanged between systems to be UTF-8. A UTF-8 byte order mark is skipped
+/// while other byte order marks are rejected; positions in errors are still
+/// those in `body`, so that [`ParseError::render`] and
+/// [`ParseError::to_json`] can show the body decoded with
+/// [`String::from_utf8_lossy`]. Media types and charsets that aren't
+/// supported fail with [`ErrorKind::UnsupportedMediaType`].
+///
+/// [`ErrorKind::UnsupportedMediaType`]: crate::ErrorKind::UnsupportedMediaType
+pub fn from_http_body(content_type: &str, body: &[u8]) -> Result<JsonValue, ParseError> {
+    from_http_body_with(content_type, body, &ParserOptions::default())
+}
+
+/// Like [`from_http_body`], with `options`.
+pub fn from_http_body_with(
+    content_type: &str,
+    body: &[u8],
+    options: &ParserOptions,
+) -> Result<JsonValue, ParseError> {
+    check_media_type(content_type)?;
+    let (bom, text) = match body {
+        [0xef, 0xbb, 0xbf, rest @ ..] => ("\u{feff}", rest),
+        [0, 0, 0xfe, 0xff, ..] | [0xff, 0xfe, 0, 0, ..] => {
+            return Err(ParseError::media_type("UTF-32 byte order mark".to_owned()))
+        }
+        [0xfe, 0xff, ..] | [0xff, 0xfe, ..] => {
+            return Err(ParseError::media_type("UTF-16 byte order mark".to_owned()))
+        }
+        _ => ("", body),
+    };
+    let parsed = decode_utf8(text, options).and_then(|text| parse_with(&text, options));
+    parsed.map_err(|err| err.starting_at(LineIndex::new(bom).position(bom.len())))
+}
+
+/// `Ok` for a JSON media type in UTF-8, the reason it isn't one otherwise
+pub(crate) fn check_media_type(content_type: &str) -> Result<(), ParseError> {
+    let mut parts = content_type.split(';');
+    let mime = parts.next().unwrap_or_default().trim();
+    let is_json = mime.split_once('/').is_some_and(|(kind, subtype)| {
+        let subtype = subtype.to_ascii_lowercase();
+        kind.eq_ignore_ascii_case("application")
+            && (subtype == "json" || subtype.ends_with("+json"))
+    });
+    if !is_json {
+        return Err(ParseError::media_type(format!("{:?} isn't JSON", mime)));
+    }
+    for param in parts {
+        let Some((name, value)) = param.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        if name.trim().eq_ignore_ascii_case("charset")
+            && !value.eq_ignore_ascii_case("utf-8")
+            && !value.eq_ignore_ascii_case("utf8")
+        {
+            return Err(ParseError::media_type(format!("charset {:?}", value)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, ErrorKind};
+
+    use super::from_http_body;
+
+    #[test]
+    fn test_from_http_body() {
+        let expect = parse(r#"{"a": "é"}"#).unwrap();
+        for content_type in [
+            "application/json",
+            "Application/JSON; charset=UTF-8",
+            "application/problem+json;charset=\"utf-8\"",
+            "application/json; charset=utf8; q=1",
+        ] {
+            let body = r#"{"a": "é"}"#.as_bytes();
+            assert_eq!(from_http_body(content_type, body).unwrap(), expect);
+        }
+        let body = b"\xef\xbb\xbf{\"a\": \"\xc3\xa9\"}";
+        assert_eq!(from_http_body("application/json", body).unwrap(), expect);
+
+        // errors are located in the body, byte order mark included
+        let err = from_http_body("application/json", b"\xef\xbb\xbf[1 x]").unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::UnexpectedToken, 6));
+        let err = from_http_body("application/json", b"\xef\xbb\xbf[\"\xff\"]").unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::InvalidUtf8, 5));
+
+        for (content_type, body, message) in [
+            ("text/plain", &b"[]"[..], "\"text/plain\" isn't JSON"),
+            ("", b"[]", "\"\" isn't JSON"),
+            (
+                "application/json; charset=latin1",
+                b"[]",
+                "charset \"latin1\"",
+            ),
+            (
+                "application/json",
+                b"\xff\xfe[\x00]\x00",
+                "UTF-16 byte order mark",
+            ),
+            (
+                "application/json",
+                b"\x00\x00\xfe\xff",
+                "UTF-32 byte order mark",
+            ),
+        ] {
+            let err = from_http_body(content_type, body).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnsupportedMediaType);
+            assert_eq!(
+                err.to_string(),
+                format!("unsupported media type: {}", message)
+            );
+        }
+    }
+}
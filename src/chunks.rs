@@ -0,0 +1,439 @@
+//! JSON objects embedded in a stream of text chunks, such as server-sent
+//! events or the chunked responses of LLM APIs.
+
+use std::fmt;
+
+use crate::{parse_with, ErrorKind, JsonValue, LineIndex, ParseError, ParserOptions, Position};
+
+/// Extracts the JSON objects of a text stream as its chunks arrive.
+///
+/// Text outside objects is skipped: SSE field names like `data:`, comments,
+/// `[DONE]` markers, line breaks; the objects of a top-level array are
+/// yielded one by one. An object may be split across any number of chunks,
+/// even in the middle of a string or escape; it's parsed as soon as its
+/// closing `}` arrives, and a fragment left at the end of the stream is kept
+/// in [`remainder`](ChunkDecoder::remainder) rather than reported as an
+/// error. Line breaks within an object are whitespace, so pretty-printed
+/// objects are read whole, but an object can't span SSE events: a blank line
+/// or a line starting a new `data:` field ends it, so one cut short, e.g. by
+/// a stray `{`, is reported as an error and decoding resumes there. So is an
+/// object longer than [`max_buffered`](ChunkDecoder::max_buffered), the rest
+/// of which is skipped.
+///
+/// Used as an iterator, the decoder yields the objects complete so far,
+/// parse errors positioned in the whole stream included, and `None` when it
+/// needs more input:
+///
+/// ```
+/// use nom_json_parser::ChunkDecoder;
+///
+/// let mut decoder = ChunkDecoder::new();
+/// decoder.push("data: {\"delta\": \"Hel");
+/// assert!(decoder.next().is_none());
+/// decoder.push("lo\"}\n\ndata: {\"delta\"");
+/// let value = decoder.next().unwrap().unwrap();
+/// assert_eq!(value.pointer("/delta"), Some(&"Hello".into()));
+/// assert!(decoder.next().is_none());
+/// assert_eq!(decoder.remainder(), "{\"delta\"");
+/// ```
+pub struct ChunkDecoder {
+    options: ParserOptions,
+    max_buffered: Option<usize>,
+    buf: String,
+    /// position of `buf` in the stream
+    origin: Position,
+    /// start of the object being scanned, if any
+    start: Option<usize>,
+    /// offset in `buf` scanned so far
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// whether the rest of a too large object is being skipped
+    skipping: bool,
+    /// the offset in `buf` of the last line break in the object, and the
+    /// bytes of `data:` the line starts with so far
+    line: Option<(usize, usize)>,
+}
+
+/// how the object being scanned ended
+enum End {
+    /// at the offset in `buf` after its closing `}`, or of the line break
+    /// before the SSE event boundary that cut it short
+    At(usize),
+    TooLarge,
+}
+
+impl ChunkDecoder {
+    pub fn new() -> Self {
+        ChunkDecoder {
+            options: ParserOptions::default(),
+            max_buffered: None,
+            buf: String::new(),
+            origin: Position {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
+            start: None,
+            scanned: 0,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            skipping: false,
+            line: None,
+        }
+    }
+
+    /// Options objects are parsed with.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Most bytes an object may take, `None` for no limit, the default.
+    ///
+    /// The start of an object is kept until the object is complete, so
+    /// without a limit a stream that never completes one is kept whole.
+    pub fn max_buffered(mut self, max: Option<usize>) -> Self {
+        self.max_buffered = max;
+        self
+    }
+
+    /// Append the next chunk of the stream.
+    pub fn push(&mut self, chunk: &str) {
+        self.buf.push_str(chunk);
+    }
+
+    /// The start of an object not complete yet, or `""`.
+    pub fn remainder(&self) -> &str {
+        match self.start {
+            Some(start) => &self.buf[start..],
+            None => "",
+        }
+    }
+
+    /// The objects of `chunks`, parsed as the chunks are read.
+    pub fn decode<I>(self, chunks: I) -> Decoded<I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Decoded {
+            decoder: self,
+            chunks: chunks.into_iter(),
+        }
+    }
+
+    /// The end of the next object, scanning on from where the previous call
+    /// stopped.
+    fn scan(&mut self) -> Option<End> {
+        while self.scanned < self.buf.len() {
+            let i = self.scanned;
+            self.scanned += 1;
+            let b = self.buf.as_bytes()[i];
+            if self.start.is_none() && !self.skipping {
+                if b == b'{' {
+                    self.start = Some(i);
+                    self.depth = 1;
+                    self.in_string = false;
+                    self.escaped = false;
+                    self.line = None;
+                }
+                continue;
+            }
+            if let Some(at) = self.event_boundary(i, b) {
+                if self.skipping {
+                    self.skipping = false;
+                    continue;
+                }
+                return Some(End::At(at));
+            }
+            let mut closed = false;
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+            } else {
+                match b {
+                    b'"' => self.in_string = true,
+                    b'{' | b'[' => self.depth += 1,
+                    b'}' | b']' => {
+                        self.depth -= 1;
+                        closed = self.depth == 0;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(start) = self.start {
+                if self.max_buffered.is_some_and(|max| i - start >= max) {
+                    self.skipping = !closed;
+                    return Some(End::TooLarge);
+                }
+            }
+            if closed {
+                if self.skipping {
+                    self.skipping = false;
+                    continue;
+                }
+                return Some(End::At(i + 1));
+            }
+        }
+        None
+    }
+
+    /// The offset of the line break before `b`, at `i` in the object, if `b`
+    /// completes an SSE event boundary: a blank line, or a line starting with
+    /// `data:`.
+    fn event_boundary(&mut self, i: usize, b: u8) -> Option<usize> {
+        const FIELD: &[u8] = b"data:";
+        if let Some((at, matched)) = self.line {
+            match b {
+                b'\n' if matched == 0 => return Some(at),
+                b'\r' if matched == 0 => return None,
+                _ if FIELD[matched] == b => {
+                    if matched + 1 == FIELD.len() {
+                        return Some(at);
+                    }
+                    self.line = Some((at, matched + 1));
+                    return None;
+                }
+                _ => self.line = None,
+            }
+        }
+        if b == b'\n' {
+            self.line = Some((i, 0));
+        }
+        None
+    }
+
+    /// Drop the first `len` bytes of `buf`, moving `origin` past them.
+    fn consume(&mut self, len: usize) {
+        let consumed = &self.buf[..len];
+        match consumed.rfind('\n') {
+            Some(i) => {
+                self.origin.line += consumed.matches('\n').count();
+                self.origin.column = consumed[i + 1..].chars().count() + 1;
+            }
+            None => self.origin.column += consumed.chars().count(),
+        }
+        self.origin.offset += len;
+        self.buf.drain(..len);
+        self.scanned -= len;
+        self.start = self.start.map(|start| start - len);
+        // only dropped while skipping, where it isn't used
+        self.line = self
+            .line
+            .map(|(at, matched)| (at.saturating_sub(len), matched));
+    }
+}
+
+impl Default for ChunkDecoder {
+    fn default() -> Self {
+        ChunkDecoder::new()
+    }
+}
+
+impl fmt::Debug for ChunkDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkDecoder")
+            .field("options", &self.options)
+            .field("max_buffered", &self.max_buffered)
+            .field("position", &self.origin)
+            .field("remainder", &self.remainder())
+            .finish()
+    }
+}
+
+impl Iterator for ChunkDecoder {
+    type Item = Result<JsonValue, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(end) = self.scan() else {
+            // keep only the object in progress
+            let len = self.start.unwrap_or(self.buf.len());
+            self.consume(len);
+            return None;
+        };
+        let start = self.start.take().unwrap_or_default();
+        self.consume(start);
+        let (rst, len) = match end {
+            End::At(end) => {
+                let rst = parse_with(&self.buf[..end - start], &self.options);
+                (rst, end - start)
+            }
+            End::TooLarge => {
+                let position = LineIndex::new("").position(0);
+                // the scanned part, but for a char it ends in the middle of
+                let mut len = self.scanned;
+                while !self.buf.is_char_boundary(len) {
+                    len -= 1;
+                }
+                (Err(ParseError::new(ErrorKind::TooLarge, position)), len)
+            }
+        };
+        let rst = rst.map_err(|err| err.starting_at(self.origin));
+        self.consume(len);
+        Some(rst)
+    }
+}
+
+/// Iterator over the objects of a stream of chunks, see
+/// [`ChunkDecoder::decode`].
+pub struct Decoded<I> {
+    decoder: ChunkDecoder,
+    chunks: I,
+}
+
+impl<I> Decoded<I> {
+    /// The start of an object the stream ended in the middle of, or `""`.
+    pub fn remainder(&self) -> &str {
+        self.decoder.remainder()
+    }
+}
+
+impl<I> Iterator for Decoded<I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Result<JsonValue, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(rst) = self.decoder.next() {
+                return Some(rst);
+            }
+            self.decoder.push(self.chunks.next()?.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, ErrorKind, JsonValue};
+
+    use super::ChunkDecoder;
+
+    #[test]
+    fn test_decode() {
+        let stream = concat!(
+            ": keep-alive\n\n",
+            "event: delta\ndata: {\"text\": \"a}\\\"\", \"n\": [1, {}]}\n\n",
+            "data: {\"text\": \"é\"}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"text\": \"cut",
+        );
+        let expect = [r#"{"text": "a}\"", "n": [1, {}]}"#, r#"{"text": "é"}"#]
+            .map(|doc| parse(doc).unwrap());
+        // the same objects however the stream is split
+        let chars: Vec<char> = stream.chars().collect();
+        for size in [1, 2, 7, stream.len()] {
+            let chunks: Vec<String> = chars.chunks(size).map(String::from_iter).collect();
+            let mut decoded = ChunkDecoder::new().decode(&chunks);
+            let values: Vec<JsonValue> = decoded.by_ref().map(Result::unwrap).collect();
+            assert_eq!(values, expect);
+            assert_eq!(decoded.remainder(), "{\"text\": \"cut");
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut decoder = ChunkDecoder::new();
+        decoder.push("data: {\"a\": 1}\n\ndata: {\"a\" 2}\n");
+        assert!(decoder.next().unwrap().is_ok());
+        let err = decoder.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingColon);
+        assert_eq!((err.line(), err.column(), err.offset()), (3, 12, 27));
+        // decoding goes on after an error
+        decoder.push("data: {\"b\": 3}");
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            parse(r#"{"b": 3}"#).unwrap()
+        );
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.remainder(), "");
+    }
+
+    #[test]
+    fn test_resync() {
+        // a stray `{` is cut short at the end of its line
+        let stream = "data: {oops\ndata: {\"a\": 1}\n\ndata: {\"b\": \"x\ndata: {\"c\": 3}\n";
+        for size in [1, 5, stream.len()] {
+            let chars: Vec<char> = stream.chars().collect();
+            let chunks: Vec<String> = chars.chunks(size).map(String::from_iter).collect();
+            let rsts: Vec<_> = ChunkDecoder::new().decode(&chunks).collect();
+            assert_eq!(rsts.len(), 4);
+            let err = rsts[0].as_ref().unwrap_err();
+            assert_eq!((err.kind(), err.line()), (ErrorKind::UnexpectedToken, 1));
+            assert_eq!(rsts[1].as_ref().unwrap(), &parse(r#"{"a": 1}"#).unwrap());
+            let err = rsts[2].as_ref().unwrap_err();
+            assert_eq!((err.kind(), err.line()), (ErrorKind::UnterminatedString, 4));
+            assert_eq!(rsts[3].as_ref().unwrap(), &parse(r#"{"c": 3}"#).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_pretty() {
+        let stream = "{\n  \"a\": [\n    1\n  ]\n}\n{\r\n  \"b\": 2\r\n}\r\n";
+        for size in [1, 3, stream.len()] {
+            let chars: Vec<char> = stream.chars().collect();
+            let chunks: Vec<String> = chars.chunks(size).map(String::from_iter).collect();
+            let values: Vec<JsonValue> = ChunkDecoder::new()
+                .decode(&chunks)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(
+                values,
+                [r#"{"a": [1]}"#, r#"{"b": 2}"#].map(|doc| parse(doc).unwrap())
+            );
+        }
+
+        // but a blank line ends an event, and the object in it
+        let mut decoder = ChunkDecoder::new();
+        decoder.push("data: {\n  \"a\":\r\n\r\ndata: {\"b\": 2}\n");
+        let err = decoder.next().unwrap().unwrap_err();
+        assert_eq!((err.kind(), err.line()), (ErrorKind::UnexpectedEof, 2));
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            parse(r#"{"b": 2}"#).unwrap()
+        );
+
+        // the rest of a too large object is skipped, however it's broken
+        let mut decoder = ChunkDecoder::new().max_buffered(Some(8));
+        decoder.push("{\"é\": \"😀\"}\n{\n  \"a\": {\"x\": 1},\n");
+        for _ in 0..2 {
+            let err = decoder.next().unwrap().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::TooLarge);
+        }
+        assert!(decoder.next().is_none());
+        decoder.push("  \"b\": {\"y\": \"}\"}\n}\n{\"c\": 3}\n");
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            parse(r#"{"c": 3}"#).unwrap()
+        );
+        assert!(decoder.next().is_none());
+    }
+
+    #[test]
+    fn test_max_buffered() {
+        let mut decoder = ChunkDecoder::new().max_buffered(Some(8));
+        decoder.push("data: {\"a\": 1}\ndata: {\"a\": 123");
+        assert!(decoder.next().unwrap().is_ok());
+        let err = decoder.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TooLarge);
+        assert_eq!((err.line(), err.column(), err.offset()), (2, 7, 21));
+        assert!(decoder.next().is_none());
+        // the rest of the object is skipped, however long
+        decoder.push(&"3".repeat(100));
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.remainder(), "");
+        decoder.push("}\ndata: {\"a\": 1}\n");
+        let value = decoder.next().unwrap().unwrap();
+        assert_eq!(value, parse(r#"{"a": 1}"#).unwrap());
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,589 @@
+//! Type definitions inferred from sample documents.
+
+use std::{borrow::Borrow, collections::HashSet, fmt::Write};
+
+use crate::{
+    map::{Key, Map, MapKind, Object},
+    JsonValue,
+};
+
+/// The shape of sample values: their types, and the members of objects.
+///
+/// Built with [`Shape::infer`], then written out as Rust, TypeScript or
+/// JSON Schema type definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Shape {
+    /// No value was seen, e.g. the elements of empty arrays.
+    Unknown,
+    Null,
+    Bool,
+    /// Numbers that are all integers in the range of `i64`.
+    Integer,
+    Float,
+    String,
+    Array(Box<Shape>),
+    /// Members sorted by name, so that the output doesn't depend on the
+    /// order of the samples' members.
+    Object(Vec<Field>),
+    /// `null` or the shape.
+    Nullable(Box<Shape>),
+    /// Values of different types.
+    Any,
+}
+
+/// A member of an object [`Shape`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub shape: Shape,
+    /// Whether every sample object has the member.
+    pub required: bool,
+}
+
+impl Shape {
+    /// The shape of all of `samples`, [`Shape::Unknown`] if there are none.
+    pub fn infer<'v, S, M, I>(samples: I) -> Shape
+    where
+        S: Key + Borrow<str> + 'v,
+        M: MapKind + 'v,
+        I: IntoIterator<Item = &'v JsonValue<S, M>>,
+    {
+        samples
+            .into_iter()
+            .fold(Shape::Unknown, |shape, value| shape.merge(Shape::of(value)))
+    }
+
+    pub fn of<S: Key + Borrow<str>, M: MapKind>(value: &JsonValue<S, M>) -> Shape {
+        match value {
+            JsonValue::Null => Shape::Null,
+            JsonValue::Bool(_) => Shape::Bool,
+            JsonValue::Number(n) if n.is_i64() => Shape::Integer,
+            JsonValue::Number(_) => Shape::Float,
+            JsonValue::String(_) => Shape::String,
+            JsonValue::Array(items) => Shape::Array(Box::new(Shape::infer(items))),
+            JsonValue::Object(map) => {
+                let mut fields: Vec<_> = map
+                    .iter()
+                    .map(|(key, value)| Field {
+                        name: key.borrow().to_owned(),
+                        shape: Shape::of(value),
+                        required: true,
+                    })
+                    .collect();
+                fields.sort_by(|a, b| a.name.cmp(&b.name));
+                Shape::Object(fields)
+            }
+        }
+    }
+
+    /// The shape of the values of both shapes.
+    pub fn merge(self, other: Shape) -> Shape {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Shape::Unknown, shape) | (shape, Shape::Unknown) => shape,
+            (Shape::Any, _) | (_, Shape::Any) => Shape::Any,
+            (Shape::Null, shape) | (shape, Shape::Null) => {
+                Shape::Nullable(Box::new(shape)).flatten_nullable()
+            }
+            (Shape::Nullable(a), b) | (b, Shape::Nullable(a)) => {
+                Shape::Nullable(Box::new(a.merge(b))).flatten_nullable()
+            }
+            (Shape::Integer, Shape::Float) | (Shape::Float, Shape::Integer) => Shape::Float,
+            (Shape::Array(a), Shape::Array(b)) => Shape::Array(Box::new(a.merge(*b))),
+            (Shape::Object(a), Shape::Object(b)) => Shape::Object(merge_fields(a, b)),
+            _ => Shape::Any,
+        }
+    }
+
+    /// `Nullable(Nullable(_))` and `Nullable(Any)` are redundant
+    fn flatten_nullable(self) -> Shape {
+        match self {
+            Shape::Nullable(shape) => match *shape {
+                Shape::Nullable(shape) => Shape::Nullable(shape).flatten_nullable(),
+                Shape::Any => Shape::Any,
+                shape => Shape::Nullable(Box::new(shape)),
+            },
+            shape => shape,
+        }
+    }
+
+    /// Rust definitions of types for values of this shape, with serde
+    /// derives: structs for the objects, the outermost type named `name`.
+    ///
+    /// Integers are `i64`, other numbers `f64`, and values whose type varies
+    /// or isn't known `serde_json::Value`. Members missing from some samples
+    /// or sometimes `null` are `Option`s; names that aren't Rust identifiers
+    /// in snake case are renamed.
+    pub fn to_rust(&self, name: &str) -> String {
+        let mut writer = RustWriter::default();
+        let ty = writer.rust_type(self, &pascal_case(name));
+        if !matches!(self, Shape::Object(_)) {
+            writer
+                .defs
+                .insert(0, format!("pub type {} = {};\n", pascal_case(name), ty));
+        }
+        writer.defs.join("\n")
+    }
+
+    /// TypeScript definitions of types for values of this shape: interfaces
+    /// for the objects, the outermost type named `name`.
+    ///
+    /// Values whose type varies or isn't known are `unknown`, and members
+    /// missing from some samples are optional.
+    pub fn to_typescript(&self, name: &str) -> String {
+        let mut writer = TsWriter::default();
+        let ty = writer.ts_type(self, &pascal_case(name));
+        if !matches!(self, Shape::Object(_)) {
+            writer
+                .defs
+                .insert(0, format!("export type {} = {};\n", pascal_case(name), ty));
+        }
+        writer.defs.join("\n")
+    }
+
+    /// A JSON Schema (draft 2020-12) for values of this shape, with every
+    /// member that all the samples have required.
+    ///
+    /// Values whose type varies or isn't known are unconstrained.
+    pub fn to_json_schema(&self) -> JsonValue {
+        let mut schema = match self.json_schema() {
+            JsonValue::Object(schema) => schema,
+            _ => Map::new(),
+        };
+        schema.insert(
+            "$schema".to_owned(),
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        );
+        JsonValue::Object(schema)
+    }
+
+    fn json_schema(&self) -> JsonValue {
+        let mut schema = Map::new();
+        let simple = |ty: &str| {
+            let mut schema = Map::new();
+            schema.insert("type".to_owned(), ty.into());
+            JsonValue::Object(schema)
+        };
+        match self {
+            Shape::Unknown | Shape::Any => {}
+            Shape::Null => return simple("null"),
+            Shape::Bool => return simple("boolean"),
+            Shape::Integer => return simple("integer"),
+            Shape::Float => return simple("number"),
+            Shape::String => return simple("string"),
+            Shape::Array(items) => {
+                schema.insert("type".to_owned(), "array".into());
+                schema.insert("items".to_owned(), items.json_schema());
+            }
+            Shape::Object(fields) => {
+                let mut properties = Map::new();
+                for field in fields {
+                    properties.insert(field.name.clone(), field.shape.json_schema());
+                }
+                let required: Vec<JsonValue> = fields
+                    .iter()
+                    .filter(|field| field.required)
+                    .map(|field| field.name.as_str().into())
+                    .collect();
+                schema.insert("type".to_owned(), "object".into());
+                schema.insert("properties".to_owned(), JsonValue::Object(properties));
+                if !required.is_empty() {
+                    schema.insert("required".to_owned(), required.into());
+                }
+            }
+            Shape::Nullable(shape) => match shape.json_schema() {
+                // `{"type": ["string", "null"]}` for simple types
+                JsonValue::Object(mut inner) => match Object::remove(&mut inner, "type") {
+                    Some(JsonValue::String(ty)) if inner.is_empty() => {
+                        let types = vec![ty.into(), "null".into()];
+                        schema.insert("type".to_owned(), types.into());
+                    }
+                    ty => {
+                        if let Some(ty) = ty {
+                            inner.insert("type".to_owned(), ty);
+                        }
+                        let any_of = vec![JsonValue::Object(inner), simple("null")];
+                        schema.insert("anyOf".to_owned(), any_of.into());
+                    }
+                },
+                inner => {
+                    let any_of = vec![inner, simple("null")];
+                    schema.insert("anyOf".to_owned(), any_of.into());
+                }
+            },
+        }
+        JsonValue::Object(schema)
+    }
+}
+
+/// members of both, sorted by name
+fn merge_fields(a: Vec<Field>, b: Vec<Field>) -> Vec<Field> {
+    let mut fields = Vec::with_capacity(a.len().max(b.len()));
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    loop {
+        let field = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if x.name == y.name => {
+                let (x, y) = (a.next().unwrap(), b.next().unwrap());
+                fields.push(Field {
+                    name: x.name,
+                    shape: x.shape.merge(y.shape),
+                    required: x.required && y.required,
+                });
+                continue;
+            }
+            (Some(x), Some(y)) if x.name < y.name => a.next().unwrap(),
+            (_, Some(_)) => b.next().unwrap(),
+            (Some(_), None) => a.next().unwrap(),
+            (None, None) => return fields,
+        };
+        // members only one side has
+        fields.push(Field {
+            required: false,
+            ..field
+        });
+    }
+}
+
+#[derive(Default)]
+struct RustWriter {
+    /// definitions, parents before their children
+    defs: Vec<String>,
+    names: HashSet<String>,
+}
+
+impl RustWriter {
+    /// `hint` names the struct of an object
+    fn rust_type(&mut self, shape: &Shape, hint: &str) -> String {
+        match shape {
+            Shape::Unknown | Shape::Null | Shape::Any => "serde_json::Value".to_owned(),
+            Shape::Bool => "bool".to_owned(),
+            Shape::Integer => "i64".to_owned(),
+            Shape::Float => "f64".to_owned(),
+            Shape::String => "String".to_owned(),
+            Shape::Nullable(shape) => format!("Option<{}>", self.rust_type(shape, hint)),
+            Shape::Array(shape) => format!("Vec<{}>", self.rust_type(shape, &singular(hint))),
+            Shape::Object(fields) => self.rust_struct(fields, hint),
+        }
+    }
+
+    fn rust_struct(&mut self, fields: &[Field], hint: &str) -> String {
+        let name = unique(&mut self.names, pascal_case(hint));
+        let index = self.defs.len();
+        self.defs.push(String::new());
+        let mut def = String::new();
+        let _ = writeln!(
+            def,
+            "#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]"
+        );
+        let _ = writeln!(def, "pub struct {} {{", name);
+        let mut idents = HashSet::new();
+        for field in fields {
+            let ident = unique(&mut idents, snake_case(&field.name));
+            let mut ty = self.rust_type(&field.shape, &pascal_case(&field.name));
+            if !field.required && !matches!(field.shape, Shape::Nullable(_)) {
+                ty = format!("Option<{}>", ty);
+            }
+            if ident.trim_start_matches("r#") != field.name {
+                let _ = writeln!(def, "    #[serde(rename = {:?})]", field.name);
+            }
+            if !field.required {
+                let _ = writeln!(def, "    #[serde(default)]");
+            }
+            let _ = writeln!(def, "    pub {}: {},", ident, ty);
+        }
+        def.push_str("}\n");
+        self.defs[index] = def;
+        name
+    }
+}
+
+#[derive(Default)]
+struct TsWriter {
+    /// definitions, parents before their children
+    defs: Vec<String>,
+    names: HashSet<String>,
+}
+
+impl TsWriter {
+    /// `hint` names the interface of an object
+    fn ts_type(&mut self, shape: &Shape, hint: &str) -> String {
+        match shape {
+            Shape::Unknown | Shape::Any => "unknown".to_owned(),
+            Shape::Null => "null".to_owned(),
+            Shape::Bool => "boolean".to_owned(),
+            Shape::Integer | Shape::Float => "number".to_owned(),
+            Shape::String => "string".to_owned(),
+            Shape::Nullable(shape) => format!("{} | null", self.ts_type(shape, hint)),
+            Shape::Array(shape) => match self.ts_type(shape, &singular(hint)) {
+                ty if ty.contains(' ') => format!("({})[]", ty),
+                ty => format!("{}[]", ty),
+            },
+            Shape::Object(fields) => self.ts_interface(fields, hint),
+        }
+    }
+
+    fn ts_interface(&mut self, fields: &[Field], hint: &str) -> String {
+        let name = unique(&mut self.names, pascal_case(hint));
+        let index = self.defs.len();
+        self.defs.push(String::new());
+        let mut def = format!("export interface {} {{\n", name);
+        for field in fields {
+            let ty = self.ts_type(&field.shape, &pascal_case(&field.name));
+            let optional = if field.required { "" } else { "?" };
+            let _ = writeln!(def, "  {}{}: {};", ts_property(&field.name), optional, ty);
+        }
+        def.push_str("}\n");
+        self.defs[index] = def;
+        name
+    }
+}
+
+/// `name` as a property name, quoted unless it's an identifier
+fn ts_property(name: &str) -> String {
+    let identifier = name
+        .starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_' || ch == '$')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$');
+    if identifier {
+        name.to_owned()
+    } else {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// `name`, or `name` followed by the first number making it unique
+fn unique(names: &mut HashSet<String>, name: String) -> String {
+    let name = (1..)
+        .map(|i| match i {
+            1 => name.clone(),
+            i => format!("{}{}", name, i),
+        })
+        .find(|name| !names.contains(name))
+        .unwrap_or(name);
+    names.insert(name.clone());
+    name
+}
+
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for ch in name.chars() {
+        if !ch.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            prev_lower = false;
+            continue;
+        }
+        // a camelCase boundary
+        if ch.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+        word.push(ch);
+    }
+    words.push(word);
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut pascal: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if !pascal.starts_with(|ch: char| ch.is_alphabetic()) {
+        pascal.insert_str(0, "Type");
+    }
+    pascal
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = words(name).join("_").to_lowercase();
+    if !snake.starts_with(|ch: char| ch.is_alphabetic()) {
+        snake.insert(0, '_');
+    }
+    if snake == "_" {
+        return "field".to_owned();
+    }
+    match snake.as_str() {
+        "self" | "super" | "crate" => snake.push('_'),
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum"
+        | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match"
+        | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct" | "trait"
+        | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract" | "become"
+        | "box" | "do" | "final" | "macro" | "override" | "priv" | "try" | "typeof" | "unsized"
+        | "virtual" | "yield" | "gen" => snake.insert_str(0, "r#"),
+        _ => {}
+    }
+    snake
+}
+
+/// the name of the elements of an array named `name`
+fn singular(name: &str) -> String {
+    match name.strip_suffix('s') {
+        Some(singular) if !singular.is_empty() && !singular.ends_with('s') => singular.to_owned(),
+        _ => format!("{}Item", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::{Field, Shape};
+
+    #[test]
+    fn test_infer() {
+        let samples = [
+            r#"{"id": 1, "name": "a", "tags": [], "score": 1, "parent": null}"#,
+            r#"{"id": 2, "tags": ["x"], "score": 2.5, "parent": 1, "extra": [1, "a"]}"#,
+        ]
+        .map(|sample| parse(sample).unwrap());
+        let field = |name: &str, shape, required| Field {
+            name: name.to_owned(),
+            shape,
+            required,
+        };
+        assert_eq!(
+            Shape::infer(&samples),
+            Shape::Object(vec![
+                field("extra", Shape::Array(Box::new(Shape::Any)), false),
+                field("id", Shape::Integer, true),
+                field("name", Shape::String, false),
+                field("parent", Shape::Nullable(Box::new(Shape::Integer)), true),
+                field("score", Shape::Float, true),
+                field("tags", Shape::Array(Box::new(Shape::String)), true),
+            ])
+        );
+        assert_eq!(
+            Shape::infer::<String, crate::DefaultMapKind, _>([]),
+            Shape::Unknown
+        );
+        assert_eq!(Shape::Null.merge(Shape::Any), Shape::Any,);
+        assert_eq!(
+            Shape::Nullable(Box::new(Shape::Bool)).merge(Shape::Bool),
+            Shape::Nullable(Box::new(Shape::Bool))
+        );
+    }
+
+    #[test]
+    fn test_to_rust() {
+        let samples = [
+            r#"{"userId": 1, "type": "a", "1st": true, "items": [{"price": 1.5}], "meta": {}}"#,
+            r#"{"userId": 2, "type": "b", "items": [{"price": 2, "note": null}], "meta": {"x": 1}}"#,
+        ]
+        .map(|sample| parse(sample).unwrap());
+        let expect = r#"#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Order {
+    #[serde(rename = "1st")]
+    #[serde(default)]
+    pub _1st: Option<bool>,
+    pub items: Vec<Item>,
+    pub meta: Meta,
+    pub r#type: String,
+    #[serde(rename = "userId")]
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Item {
+    #[serde(default)]
+    pub note: Option<serde_json::Value>,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Meta {
+    #[serde(default)]
+    pub x: Option<i64>,
+}
+"#;
+        assert_eq!(Shape::infer(&samples).to_rust("order"), expect);
+
+        let samples = [parse(r#"[{"a": 1}]"#).unwrap()];
+        assert_eq!(
+            Shape::infer(&samples).to_rust("rows"),
+            "pub type Rows = Vec<Row>;\n\n\
+             #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n\
+             pub struct Row {\n    pub a: i64,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_typescript() {
+        let samples = [
+            r#"{"id": 1, "a-b": [null, "x"], "items": [{"price": 1.5}], "x": null}"#,
+            r#"{"id": 2, "a-b": [], "items": [{"price": 2, "note": "n"}], "x": true}"#,
+        ]
+        .map(|sample| parse(sample).unwrap());
+        let expect = r#"export interface Order {
+  "a-b": (string | null)[];
+  id: number;
+  items: Item[];
+  x: boolean | null;
+}
+
+export interface Item {
+  note?: string;
+  price: number;
+}
+"#;
+        assert_eq!(Shape::infer(&samples).to_typescript("order"), expect);
+        assert_eq!(
+            Shape::infer(&[parse("[1, []]").unwrap()]).to_typescript("t"),
+            "export type T = unknown[];\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json_schema() {
+        let samples = [
+            r#"{"id": 1, "tags": ["a"], "parent": null, "meta": {"v": [1]}}"#,
+            r#"{"id": 2, "tags": [], "parent": {"id": 1}, "extra": 1.5}"#,
+        ]
+        .map(|sample| parse(sample).unwrap());
+        let expect = r#"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "extra": {"type": "number"},
+                "id": {"type": "integer"},
+                "meta": {
+                    "type": "object",
+                    "properties": {"v": {"type": "array", "items": {"type": "integer"}}},
+                    "required": ["v"]
+                },
+                "parent": {
+                    "anyOf": [
+                        {"type": "object", "properties": {"id": {"type": "integer"}}, "required": ["id"]},
+                        {"type": "null"}
+                    ]
+                },
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["id", "parent", "tags"]
+        }"#;
+        assert_eq!(
+            Shape::infer(&samples).to_json_schema(),
+            parse(expect).unwrap()
+        );
+
+        let shape = Shape::infer(&[parse(r#"[1, null, "a"]"#).unwrap()]);
+        let expect = r#"{"$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "array", "items": {}}"#;
+        assert_eq!(shape.to_json_schema(), parse(expect).unwrap());
+        let shape = Shape::infer(&[parse(r#"[1, null]"#).unwrap()]);
+        assert_eq!(
+            shape.to_json_schema().pointer("/items/type"),
+            Some(&parse(r#"["integer", "null"]"#).unwrap())
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Structural comparison with exceptions.
+
+use std::borrow::Borrow;
+
+use crate::{
+    map::{DefaultMapKind, Key, MapKind, Object},
+    JsonPointer, JsonValue,
+};
+
+/// How [`EqOptions::eq`] and [`EqOptions::diff`] compare documents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EqOptions {
+    ignore_paths: Vec<String>,
+    array_key: Option<String>,
+}
+
+impl EqOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip the values at these JSON Pointers, and everything inside them.
+    pub fn ignore_paths<I>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.ignore_paths.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Compare arrays of objects that all have a `key` member as sets,
+    /// matching elements by the value of that member instead of their
+    /// position.
+    ///
+    /// Matched elements are reported at their index in the first document,
+    /// unmatched ones at their index in their own document.
+    pub fn array_key(mut self, key: impl Into<String>) -> Self {
+        self.array_key = Some(key.into());
+        self
+    }
+
+    pub fn eq<S: Key + Borrow<str>, M: MapKind>(
+        &self,
+        a: &JsonValue<S, M>,
+        b: &JsonValue<S, M>,
+    ) -> bool {
+        self.diff(a, b).is_empty()
+    }
+
+    /// JSON Pointers of the values that differ, members in the iteration
+    /// order of the objects, elements in index order.
+    ///
+    /// Objects are compared member by member and arrays of the same length
+    /// element by element; other differing values, including arrays of
+    /// different lengths, are reported as a whole.
+    pub fn diff<S: Key + Borrow<str>, M: MapKind>(
+        &self,
+        a: &JsonValue<S, M>,
+        b: &JsonValue<S, M>,
+    ) -> Vec<JsonPointer> {
+        let mut diff = Vec::new();
+        self.diff_at(Some(a), Some(b), &mut JsonPointer::root(), &mut diff);
+        diff
+    }
+
+    /// whether `items` are compared by `array_key`
+    fn keyed<S: Key + Borrow<str>, M: MapKind>(&self, items: &[JsonValue<S, M>]) -> bool {
+        match &self.array_key {
+            Some(key) => items.iter().all(|item| match item {
+                JsonValue::Object(map) => map.get(key.as_str()).is_some(),
+                _ => false,
+            }),
+            None => false,
+        }
+    }
+
+    /// `None` for a missing member.
+    fn diff_at<'v, S: Key + Borrow<str>, M: MapKind>(
+        &self,
+        a: Option<&'v JsonValue<S, M>>,
+        b: Option<&'v JsonValue<S, M>>,
+        pointer: &mut JsonPointer,
+        diff: &mut Vec<JsonPointer>,
+    ) {
+        if self
+            .ignore_paths
+            .iter()
+            .any(|path| path == pointer.as_str())
+        {
+            return;
+        }
+        let len = pointer.len();
+        match (a, b) {
+            (Some(JsonValue::Object(a)), Some(JsonValue::Object(b))) => {
+                let keys = a.iter().map(|(key, _)| key).chain(
+                    b.iter()
+                        .map(|(key, _)| key)
+                        .filter(|key| a.get::<S>(key).is_none()),
+                );
+                for key in keys {
+                    pointer.push(key.borrow());
+                    self.diff_at(a.get::<S>(key), b.get::<S>(key), pointer, diff);
+                    pointer.truncate(len);
+                }
+            }
+            (Some(JsonValue::Array(a)), Some(JsonValue::Array(b)))
+                if self.keyed(a) && self.keyed(b) =>
+            {
+                let key = |value: &'v JsonValue<S, M>| match value {
+                    JsonValue::Object(map) => map.get(self.array_key.as_deref().unwrap_or("")),
+                    _ => None,
+                };
+                for (i, a) in a.iter().enumerate() {
+                    pointer.push(&i.to_string());
+                    let b = b.iter().find(|b| key(b) == key(a));
+                    self.diff_at(Some(a), b, pointer, diff);
+                    pointer.truncate(len);
+                }
+                for (i, b) in b.iter().enumerate() {
+                    if !a.iter().any(|a| key(a) == key(b)) {
+                        pointer.push(&i.to_string());
+                        self.diff_at(None, Some(b), pointer, diff);
+                        pointer.truncate(len);
+                    }
+                }
+            }
+            (Some(JsonValue::Array(a)), Some(JsonValue::Array(b))) if a.len() == b.len() => {
+                for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                    pointer.push(&i.to_string());
+                    self.diff_at(Some(a), Some(b), pointer, diff);
+                    pointer.truncate(len);
+                }
+            }
+            (a, b) if a != b => diff.push(pointer.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// The latest version of a document, such as a configuration file re-read
+/// when it changes, reporting what each new version changed.
+#[derive(Debug, Clone)]
+pub struct ChangeTracker<S: Key = String, M: MapKind = DefaultMapKind> {
+    current: JsonValue<S, M>,
+    options: EqOptions,
+}
+
+impl<S: Key + Borrow<str>, M: MapKind> ChangeTracker<S, M> {
+    pub fn new(value: JsonValue<S, M>) -> Self {
+        Self::with_options(value, EqOptions::new())
+    }
+
+    /// Compare versions with `options`, e.g. to ignore a timestamp.
+    pub fn with_options(value: JsonValue<S, M>, options: EqOptions) -> Self {
+        ChangeTracker {
+            current: value,
+            options,
+        }
+    }
+
+    pub fn current(&self) -> &JsonValue<S, M> {
+        &self.current
+    }
+
+    /// Make `value` the current version, returning the pointers of what
+    /// changed since the previous one; see [`EqOptions::diff`].
+    pub fn update(&mut self, value: JsonValue<S, M>) -> Changes {
+        let pointers = self.options.diff(&self.current, &value);
+        self.current = value;
+        Changes(pointers)
+    }
+}
+
+/// The pointers of the values changed by a new version, see
+/// [`ChangeTracker::update`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes(Vec<JsonPointer>);
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn pointers(&self) -> &[JsonPointer] {
+        &self.0
+    }
+
+    /// Whether the value at `pointer` changed: a value inside it did, or one
+    /// holding it was replaced as a whole.
+    pub fn affects(&self, pointer: &JsonPointer) -> bool {
+        self.0
+            .iter()
+            .any(|changed| changed.is_within(pointer) || pointer.is_within(changed))
+    }
+
+    pub fn into_vec(self) -> Vec<JsonPointer> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonPointer};
+
+    use super::{ChangeTracker, EqOptions};
+
+    // sorted, as member order depends on the map kind
+    fn diff(options: &EqOptions, a: &str, b: &str) -> Vec<JsonPointer> {
+        let mut diff = options.diff(&parse(a).unwrap(), &parse(b).unwrap());
+        diff.sort();
+        diff
+    }
+
+    #[test]
+    fn test_ignore_paths() {
+        let a = r#"{"id": 1, "meta": {"timestamp": 10, "v": 1}, "items": [1, 2]}"#;
+        let b = r#"{"id": 2, "meta": {"timestamp": 20, "v": 1}, "items": [1, 3]}"#;
+        let options = EqOptions::new();
+        assert_eq!(diff(&options, a, b), ["/id", "/items/1", "/meta/timestamp"]);
+
+        let options = EqOptions::new().ignore_paths(["/meta/timestamp", "/id"]);
+        assert_eq!(diff(&options, a, b), ["/items/1"]);
+        let (a, b) = (parse(a).unwrap(), parse(b).unwrap());
+        assert!(!options.eq(&a, &b));
+        assert!(options.ignore_paths(["/items"]).eq(&a, &b));
+    }
+
+    #[test]
+    fn test_diff() {
+        let (a, b) = (r#"{"a": [1], "b": {}}"#, r#"{"a": [1, 2], "c": null}"#);
+        assert_eq!(diff(&EqOptions::new(), a, b), ["/a", "/b", "/c"]);
+        assert!(EqOptions::new().eq(&parse(a).unwrap(), &parse(a).unwrap()));
+    }
+
+    #[test]
+    fn test_array_key() {
+        let a = r#"{"items": [{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3}]}"#;
+        let b = r#"{"items": [{"id": 4}, {"id": 2, "v": "c"}, {"id": 1, "v": "a"}]}"#;
+        let options = EqOptions::new().array_key("id");
+        assert_eq!(
+            options.diff(&parse(a).unwrap(), &parse(b).unwrap()),
+            ["/items/1/v", "/items/2", "/items/0"]
+        );
+        let b = r#"{"items": [{"id": 3}, {"id": 2, "v": "b"}, {"id": 1, "v": "a"}]}"#;
+        assert!(options.eq(&parse(a).unwrap(), &parse(b).unwrap()));
+        // arrays with unkeyed elements are compared by position
+        assert_eq!(
+            diff(&options, r#"[{"id": 1}, 2]"#, r#"[2, {"id": 1}]"#),
+            ["/0", "/1"]
+        );
+    }
+
+    #[test]
+    fn test_change_tracker() {
+        let config = |s: &str| parse(s).unwrap();
+        let mut tracker = ChangeTracker::with_options(
+            config(r#"{"db": {"host": "a", "port": 1}, "log": "info", "reloaded": 1}"#),
+            EqOptions::new().ignore_paths(["/reloaded"]),
+        );
+        let pointer = |s: &str| s.parse::<JsonPointer>().unwrap();
+
+        let changes = tracker.update(config(
+            r#"{"db": {"host": "b", "port": 1}, "log": "info", "reloaded": 2}"#,
+        ));
+        assert_eq!(changes.pointers(), ["/db/host"]);
+        assert!(changes.affects(&pointer("/db")) && changes.affects(&pointer("")));
+        assert!(changes.affects(&pointer("/db/host/x")));
+        assert!(!changes.affects(&pointer("/db/port")) && !changes.affects(&pointer("/log")));
+
+        let unchanged = tracker.current().clone();
+        assert!(tracker.update(unchanged).is_empty());
+        let changes = tracker.update(config(r#"{"db": null, "log": "debug"}"#));
+        let mut pointers = changes.into_vec();
+        pointers.sort();
+        assert_eq!(pointers, ["/db", "/log"]);
+        assert_eq!(
+            tracker.current(),
+            &config(r#"{"db": null, "log": "debug"}"#)
+        );
+    }
+}
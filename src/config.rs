@@ -0,0 +1,393 @@
+//! Configuration files: loaded, checked against the settings they must
+//! have, and reloaded when they change.
+
+#[cfg(feature = "notify")]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    parse_bytes, ChangeTracker, Changes, InvalidPointer, JsonKind, JsonPointer, JsonValue,
+    ParseError,
+};
+
+/// The settings a configuration must or may have, with their types, e.g.
+/// `ConfigSchema::new().required("/db/host", JsonKind::String)?`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSchema {
+    /// `(pointer, type, required)`
+    settings: Vec<(JsonPointer, JsonKind, bool)>,
+}
+
+impl ConfigSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a value of type `ty` at `pointer`.
+    pub fn required(self, pointer: &str, ty: JsonKind) -> Result<Self, InvalidPointer> {
+        self.push(pointer, ty, true)
+    }
+
+    /// Check the type of the value at `pointer`, if there is one.
+    pub fn optional(self, pointer: &str, ty: JsonKind) -> Result<Self, InvalidPointer> {
+        self.push(pointer, ty, false)
+    }
+
+    /// The first setting of `value` that is missing or has the wrong type.
+    pub fn validate(&self, value: &JsonValue) -> Result<(), ConfigError> {
+        for (pointer, ty, required) in &self.settings {
+            match value.pointer(pointer.as_str()) {
+                Some(setting) if setting.kind() != *ty => {
+                    return Err(ConfigError::WrongType {
+                        pointer: pointer.clone(),
+                        expected: *ty,
+                    })
+                }
+                None if *required => return Err(ConfigError::Missing(pointer.clone())),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn push(mut self, pointer: &str, ty: JsonKind, required: bool) -> Result<Self, InvalidPointer> {
+        self.settings
+            .push((JsonPointer::parse(pointer)?, ty, required));
+        Ok(self)
+    }
+}
+
+/// A JSON configuration file, checked against a [`ConfigSchema`] whenever
+/// it is read.
+#[derive(Debug, Clone)]
+pub struct Config {
+    path: PathBuf,
+    schema: ConfigSchema,
+    tracker: ChangeTracker,
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>, schema: ConfigSchema) -> Result<Self, ConfigError> {
+        let path = path.as_ref().to_owned();
+        let value = read(&path, &schema)?;
+        Ok(Config {
+            path,
+            schema,
+            tracker: ChangeTracker::new(value),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The whole configuration.
+    pub fn value(&self) -> &JsonValue {
+        self.tracker.current()
+    }
+
+    /// Read the file again, returning what changed. If it can't be parsed or
+    /// doesn't match the schema, the error is returned and the configuration
+    /// stays as it was.
+    pub fn reload(&mut self) -> Result<Changes, ConfigError> {
+        let value = read(&self.path, &self.schema)?;
+        Ok(self.tracker.update(value))
+    }
+
+    /// The setting at `pointer`; see [`JsonValue::pointer`].
+    pub fn get(&self, pointer: &str) -> Option<&JsonValue> {
+        self.value().pointer(pointer)
+    }
+
+    pub fn get_str(&self, pointer: &str) -> Result<&str, ConfigError> {
+        self.get_as(pointer, JsonKind::String, |value| match value {
+            JsonValue::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_bool(&self, pointer: &str) -> Result<bool, ConfigError> {
+        self.get_as(pointer, JsonKind::Bool, |value| match value {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        })
+    }
+
+    /// Integers out of the range of `i64` have the wrong type.
+    pub fn get_i64(&self, pointer: &str) -> Result<i64, ConfigError> {
+        self.get_as(pointer, JsonKind::Number, |value| match value {
+            JsonValue::Number(n) => n.as_i64(),
+            _ => None,
+        })
+    }
+
+    /// Integers out of the range of `u64` have the wrong type.
+    pub fn get_u64(&self, pointer: &str) -> Result<u64, ConfigError> {
+        self.get_as(pointer, JsonKind::Number, |value| match value {
+            JsonValue::Number(n) => n.as_u64(),
+            _ => None,
+        })
+    }
+
+    pub fn get_f64(&self, pointer: &str) -> Result<f64, ConfigError> {
+        self.get_as(pointer, JsonKind::Number, |value| match value {
+            JsonValue::Number(n) => n.as_f64(),
+            _ => None,
+        })
+    }
+
+    fn get_as<'c, T>(
+        &'c self,
+        pointer: &str,
+        ty: JsonKind,
+        get: impl FnOnce(&'c JsonValue) -> Option<T>,
+    ) -> Result<T, ConfigError> {
+        let pointer = JsonPointer::parse(pointer).map_err(ConfigError::InvalidPointer)?;
+        let value = self
+            .value()
+            .pointer(pointer.as_str())
+            .ok_or_else(|| ConfigError::Missing(pointer.clone()))?;
+        get(value).ok_or(ConfigError::WrongType {
+            pointer,
+            expected: ty,
+        })
+    }
+
+    /// Reload the configuration whenever its file changes, calling
+    /// `on_change` with the pointers of the changed settings.
+    ///
+    /// The directory of the file is watched, so that editors replacing the
+    /// file are seen. A new version that can't be parsed or doesn't match
+    /// the schema is logged and ignored.
+    #[cfg(feature = "notify")]
+    pub fn watch<F>(self, mut on_change: F) -> Result<ConfigWatcher, ConfigError>
+    where
+        F: FnMut(&Config, &Changes) + Send + 'static,
+    {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = self.path.file_name().map(ToOwned::to_owned);
+        let config = Arc::new(Mutex::new(self));
+        let shared = Arc::clone(&config);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let ours = event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == file_name.as_deref());
+                if !ours || !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    return;
+                }
+                let mut config = shared.lock().unwrap_or_else(PoisonError::into_inner);
+                match config.reload() {
+                    Ok(changes) if !changes.is_empty() => on_change(&config, &changes),
+                    Ok(_) => {}
+                    Err(err) => warn!("not reloading {}: {}", config.path.display(), err),
+                }
+            })
+            .map_err(ConfigError::Watch)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(ConfigError::Watch)?;
+        Ok(ConfigWatcher {
+            config,
+            _watcher: watcher,
+        })
+    }
+}
+
+fn read(path: &Path, schema: &ConfigSchema) -> Result<JsonValue, ConfigError> {
+    let bytes = fs::read(path).map_err(|err| ConfigError::Parse(ParseError::io(err)))?;
+    let value = parse_bytes(&bytes).map_err(ConfigError::Parse)?;
+    schema.validate(&value)?;
+    Ok(value)
+}
+
+/// A [`Config`] reloaded as its file changes, see [`Config::watch`]; the
+/// file stops being watched when this is dropped.
+#[cfg(feature = "notify")]
+pub struct ConfigWatcher {
+    config: Arc<Mutex<Config>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "notify")]
+impl ConfigWatcher {
+    /// The current configuration, which isn't reloaded while it's borrowed.
+    pub fn config(&self) -> MutexGuard<'_, Config> {
+        self.config.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Error returned when a [`Config`] can't be loaded, or by its getters.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The file couldn't be read, or isn't valid JSON.
+    Parse(ParseError),
+    /// A required setting, or the one asked for, is missing.
+    Missing(JsonPointer),
+    WrongType {
+        pointer: JsonPointer,
+        expected: JsonKind,
+    },
+    InvalidPointer(InvalidPointer),
+    /// The file couldn't be watched.
+    #[cfg(feature = "notify")]
+    Watch(notify::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Parse(err) => err.fmt(f),
+            ConfigError::Missing(pointer) => write!(f, "missing setting {:?}", pointer.as_str()),
+            ConfigError::WrongType { pointer, expected } => {
+                let article = match expected {
+                    JsonKind::Array | JsonKind::Object => "an",
+                    _ => "a",
+                };
+                write!(
+                    f,
+                    "setting {:?} isn't {} {}",
+                    pointer.as_str(),
+                    article,
+                    expected
+                )
+            }
+            ConfigError::InvalidPointer(err) => err.fmt(f),
+            #[cfg(feature = "notify")]
+            ConfigError::Watch(err) => write!(f, "can't watch the file: {}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Parse(err) => Some(err),
+            ConfigError::InvalidPointer(err) => Some(err),
+            #[cfg(feature = "notify")]
+            ConfigError::Watch(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use crate::JsonKind;
+
+    use super::{Config, ConfigError, ConfigSchema};
+
+    /// a file of the temporary directory, unique to the test
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("nom-json-parser-{}-{}.json", process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::new()
+            .required("/db/host", JsonKind::String)
+            .and_then(|schema| schema.optional("/db/port", JsonKind::Number))
+            .and_then(|schema| schema.optional("/db/replicas", JsonKind::Array))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_config() {
+        let path = temp_file(
+            "config",
+            r#"{"db": {"host": "a", "port": 5432}, "debug": true}"#,
+        );
+        let mut config = Config::load(&path, schema()).unwrap();
+        assert_eq!(config.get_str("/db/host").unwrap(), "a");
+        assert_eq!(config.get_u64("/db/port").unwrap(), 5432);
+        assert_eq!(config.get_f64("/db/port").unwrap(), 5432.0);
+        assert!(config.get_bool("/debug").unwrap());
+        assert!(matches!(
+            config.get_i64("/db/host"),
+            Err(ConfigError::WrongType { .. })
+        ));
+        assert!(matches!(
+            config.get_bool("/verbose"),
+            Err(ConfigError::Missing(_))
+        ));
+        assert!(matches!(
+            config.get_bool("debug"),
+            Err(ConfigError::InvalidPointer(_))
+        ));
+
+        // invalid versions are rejected, keeping the current one
+        fs::write(&path, r#"{"db": {"host": "b", "port": "5432"}}"#).unwrap();
+        let err = config.reload().unwrap_err();
+        assert_eq!(err.to_string(), r#"setting "/db/port" isn't a number"#);
+        fs::write(&path, r#"{"db": {"host": "b", "replicas": {}}}"#).unwrap();
+        let err = config.reload().unwrap_err();
+        assert_eq!(err.to_string(), r#"setting "/db/replicas" isn't an array"#);
+        fs::write(&path, r#"{"db": {"port": 1}}"#).unwrap();
+        assert_eq!(
+            config.reload().unwrap_err().to_string(),
+            r#"missing setting "/db/host""#
+        );
+        fs::write(&path, "{").unwrap();
+        assert!(matches!(config.reload(), Err(ConfigError::Parse(_))));
+        assert_eq!(config.get_str("/db/host").unwrap(), "a");
+
+        fs::write(
+            &path,
+            r#"{"db": {"host": "b", "port": 5432, "replicas": []}, "debug": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.reload().unwrap().pointers(),
+            ["/db/host", "/db/replicas"]
+        );
+        assert_eq!(config.get_str("/db/host").unwrap(), "b");
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            Config::load(&path, schema()),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_watch() {
+        use std::{sync::mpsc, time::Duration};
+
+        let path = temp_file("watch", r#"{"db": {"host": "a"}}"#);
+        let (tx, rx) = mpsc::channel();
+        let watcher = Config::load(&path, schema())
+            .unwrap()
+            .watch(move |config, changes| {
+                let host = config.get_str("/db/host").unwrap().to_owned();
+                tx.send((changes.clone().into_vec(), host)).unwrap();
+            })
+            .unwrap();
+        fs::write(&path, r#"{"db": {"host": "b"}}"#).unwrap();
+        let (pointers, host) = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            (pointers, host.as_str()),
+            (vec!["/db/host".parse().unwrap()], "b")
+        );
+        assert_eq!(watcher.config().get_str("/db/host").unwrap(), "b");
+        drop(watcher);
+        fs::remove_file(&path).unwrap();
+    }
+}
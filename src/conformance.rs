@@ -0,0 +1,201 @@
+//! Running the parser over a conformance corpus laid out like
+//! [JSONTestSuite](https://github.com/nst/JSONTestSuite)'s `test_parsing`
+//! directory.
+
+use std::{
+    fs, io,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use crate::{parse_bytes_with, ErrorKind, ParserOptions};
+
+/// What the name of a corpus file says about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expected {
+    /// `y_*.json`: valid JSON a parser must accept.
+    Accept,
+    /// `n_*.json`: invalid JSON a parser must reject.
+    Reject,
+    /// `i_*.json`: implementation-defined, see [`Case`](crate::Case).
+    Either,
+}
+
+/// What the parser did with a corpus file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    Accepted,
+    Rejected(ErrorKind),
+    /// The parser panicked, which it never should.
+    Panicked,
+}
+
+/// A corpus file and what the parser did with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFile {
+    /// The file name, e.g. `n_array_extra_comma.json`.
+    pub name: String,
+    pub expected: Expected,
+    pub verdict: Verdict,
+}
+
+impl TestFile {
+    /// Whether the verdict is the expected one.
+    pub fn passed(&self) -> bool {
+        !matches!(
+            (self.expected, self.verdict),
+            (_, Verdict::Panicked)
+                | (Expected::Accept, Verdict::Rejected(_))
+                | (Expected::Reject, Verdict::Accepted)
+        )
+    }
+}
+
+/// The results of [`run_conformance`], in file name order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub files: Vec<TestFile>,
+}
+
+impl ConformanceReport {
+    /// Whether every file passed.
+    pub fn is_conformant(&self) -> bool {
+        self.files.iter().all(TestFile::passed)
+    }
+
+    /// The files whose verdict isn't the expected one.
+    pub fn failures(&self) -> impl Iterator<Item = &TestFile> {
+        self.files.iter().filter(|file| !file.passed())
+    }
+
+    /// The implementation-defined files, with what the options made of them.
+    pub fn implementation_defined(&self) -> impl Iterator<Item = &TestFile> {
+        self.files
+            .iter()
+            .filter(|file| file.expected == Expected::Either)
+    }
+}
+
+/// Parse every `y_*.json`, `n_*.json` and `i_*.json` file of `dir` with
+/// `options`, as bytes read by [`parse_bytes_with`], and report what the
+/// parser did with each; other files are skipped.
+///
+/// A panic is caught and reported as [`Verdict::Panicked`]; the panic hook
+/// still runs.
+pub fn run_conformance(
+    dir: impl AsRef<Path>,
+    options: &ParserOptions,
+) -> io::Result<ConformanceReport> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let expected = match name.split_once('_') {
+            Some(("y", _)) => Expected::Accept,
+            Some(("n", _)) => Expected::Reject,
+            Some(("i", _)) => Expected::Either,
+            _ => continue,
+        };
+        if !name.ends_with(".json") {
+            continue;
+        }
+        let input = fs::read(entry.path())?;
+        let parsed = panic::catch_unwind(AssertUnwindSafe(|| {
+            parse_bytes_with(&input, options).map_err(|err| err.kind())
+        }));
+        let verdict = match parsed {
+            Ok(Ok(_)) => Verdict::Accepted,
+            Ok(Err(kind)) => Verdict::Rejected(kind),
+            Err(_) => Verdict::Panicked,
+        };
+        files.push(TestFile {
+            name,
+            expected,
+            verdict,
+        });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ConformanceReport { files })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{ErrorKind, InvalidUtf8, ParserOptions};
+
+    use super::{run_conformance, Expected, Verdict};
+
+    #[test]
+    fn test_run_conformance() {
+        let dir = std::env::temp_dir().join(format!("nom-json-conformance-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in [
+            ("y_array.json", &b"[1]"[..]),
+            ("y_lone_surrogate.json", br#"["\ud800"]"#),
+            ("n_extra_comma.json", b"[1,]"),
+            ("n_invalid_utf8.json", b"[\"\xff\"]"),
+            ("i_huge_exp.json", b"[1e400]"),
+            ("i_iso_latin_1.json", b"[\"\xe9\"]"),
+            ("i_utf16LE_no_BOM.json", b"[\0\"\0\xe9\0\"\0]\0"),
+            ("README.md", b"skipped"),
+            ("x_other.json", b"skipped"),
+        ] {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+
+        let report = run_conformance(&dir, &ParserOptions::default()).unwrap();
+        let names: Vec<&str> = report.files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "i_huge_exp.json",
+                "i_iso_latin_1.json",
+                "i_utf16LE_no_BOM.json",
+                "n_extra_comma.json",
+                "n_invalid_utf8.json",
+                "y_array.json",
+                "y_lone_surrogate.json"
+            ]
+        );
+        assert_eq!(
+            report.files[4].verdict,
+            Verdict::Rejected(ErrorKind::InvalidUtf8)
+        );
+        let failures: Vec<&str> = report.failures().map(|file| file.name.as_str()).collect();
+        assert_eq!(failures, ["y_lone_surrogate.json"]);
+        assert!(!report.is_conformant());
+
+        let verdicts: Vec<_> = report
+            .implementation_defined()
+            .map(|file| {
+                assert_eq!(file.expected, Expected::Either);
+                file.verdict
+            })
+            .collect();
+        assert_eq!(
+            verdicts,
+            [
+                Verdict::Accepted,
+                Verdict::Rejected(ErrorKind::InvalidUtf8),
+                Verdict::Rejected(ErrorKind::InvalidUtf8)
+            ]
+        );
+        let options = ParserOptions::default()
+            .invalid_utf8(InvalidUtf8::Replace)
+            .allow_utf16(true);
+        let report = run_conformance(&dir, &options).unwrap();
+        assert!(report
+            .implementation_defined()
+            .all(|file| file.verdict == Verdict::Accepted));
+        let report = run_conformance(&dir, &ParserOptions::strict()).unwrap();
+        assert_eq!(
+            report.files[0].verdict,
+            Verdict::Rejected(ErrorKind::NumberOutOfRange)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+//! Taking the content out of a value, without cloning it.
+
+use std::{collections::HashMap, convert::Infallible, error::Error, fmt, hash::BuildHasher, iter};
+
+use crate::{
+    map::{Key, MapKind},
+    JsonKind, JsonPointer, JsonValue,
+};
+
+/// Error of the consuming conversions of [`JsonValue`], e.g.
+/// [`JsonValue::into_vec`]: the value wasn't of the `expected` type, and is
+/// given back.
+pub struct ConversionError<S: Key = String, M: MapKind = crate::DefaultMapKind> {
+    expected: JsonKind,
+    value: JsonValue<S, M>,
+}
+
+impl<S: Key, M: MapKind> ConversionError<S, M> {
+    pub fn expected(&self) -> JsonKind {
+        self.expected
+    }
+
+    /// The type of the value.
+    pub fn found(&self) -> JsonKind {
+        self.value.kind()
+    }
+
+    /// The value, unchanged.
+    pub fn into_value(self) -> JsonValue<S, M> {
+        self.value
+    }
+}
+
+impl<S: Key + fmt::Debug, M: MapKind> fmt::Debug for ConversionError<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConversionError")
+            .field("expected", &self.expected)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<S: Key, M: MapKind> fmt::Display for ConversionError<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found())
+    }
+}
+
+impl<S: Key + fmt::Debug, M: MapKind> Error for ConversionError<S, M> {}
+
+impl<S: Key, M: MapKind> JsonValue<S, M> {
+    /// The members of an object, in the map it holds them in: a `HashMap`
+    /// with the default features, see [`Map`](crate::Map).
+    #[allow(clippy::type_complexity)]
+    pub fn into_map(self) -> Result<M::Map<S, JsonValue<S, M>>, ConversionError<S, M>> {
+        match self {
+            JsonValue::Object(map) => Ok(map),
+            value => Err(value.mismatch(JsonKind::Object)),
+        }
+    }
+
+    /// The elements of an array.
+    pub fn into_vec(self) -> Result<Vec<JsonValue<S, M>>, ConversionError<S, M>> {
+        match self {
+            JsonValue::Array(items) => Ok(items),
+            value => Err(value.mismatch(JsonKind::Array)),
+        }
+    }
+
+    /// The content of a string.
+    pub fn into_string(self) -> Result<S, ConversionError<S, M>> {
+        match self {
+            JsonValue::String(s) => Ok(s),
+            value => Err(value.mismatch(JsonKind::String)),
+        }
+    }
+
+    /// The nearest `f64` to a number, see [`Number::as_f64`](crate::Number::as_f64).
+    pub fn into_f64(self) -> Result<f64, ConversionError<S, M>> {
+        match &self {
+            JsonValue::Number(n) => n.as_f64().ok_or_else(|| self.mismatch(JsonKind::Number)),
+            _ => Err(self.mismatch(JsonKind::Number)),
+        }
+    }
+
+    fn mismatch(self, expected: JsonKind) -> ConversionError<S, M> {
+        ConversionError {
+            expected,
+            value: self,
+        }
+    }
+}
+
+/// Error of the `TryFrom<JsonValue>` conversions, e.g. into a `Vec<u8>`: the
+/// value at `pointer`, relative to the converted one, doesn't convert to the
+/// Rust type `expected`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromJsonError {
+    pointer: JsonPointer,
+    expected: &'static str,
+    found: JsonKind,
+}
+
+impl FromJsonError {
+    fn new<S: Key, M: MapKind>(expected: &'static str, value: &JsonValue<S, M>) -> Self {
+        FromJsonError {
+            pointer: JsonPointer::root(),
+            expected,
+            found: value.kind(),
+        }
+    }
+
+    /// the error of the member or element `token`, as one of its parent
+    fn within(self, token: &str) -> Self {
+        FromJsonError {
+            pointer: JsonPointer::from_tokens(
+                iter::once(token.to_owned()).chain(self.pointer.tokens()),
+            ),
+            ..self
+        }
+    }
+
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    /// The name of the Rust type, e.g. `u8` or `Vec`.
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    pub fn found(&self) -> JsonKind {
+        self.found
+    }
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't convert {}", self.found)?;
+        if !self.pointer.is_root() {
+            write!(f, " at {:?}", self.pointer.as_str())?;
+        }
+        write!(f, " to {}", self.expected)
+    }
+}
+
+impl Error for FromJsonError {}
+
+/// For `Vec<JsonValue>` and the like, whose elements always convert.
+impl From<Infallible> for FromJsonError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+impl<S: Key, M: MapKind> TryFrom<JsonValue<S, M>> for bool {
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        match value {
+            JsonValue::Bool(b) => Ok(b),
+            value => Err(FromJsonError::new("bool", &value)),
+        }
+    }
+}
+
+impl<S: Key + Into<String>, M: MapKind> TryFrom<JsonValue<S, M>> for String {
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        match value {
+            JsonValue::String(s) => Ok(s.into()),
+            value => Err(FromJsonError::new("String", &value)),
+        }
+    }
+}
+
+/// Integers in the range of the type; numbers with a fraction or exponent
+/// never convert.
+macro_rules! try_from_integer {
+    ($($as:ident => $($ty:ty)*;)*) => {$($(
+        impl<S: Key, M: MapKind> TryFrom<JsonValue<S, M>> for $ty {
+            type Error = FromJsonError;
+
+            fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+                match &value {
+                    JsonValue::Number(n) => n.$as().and_then(|n| n.try_into().ok()),
+                    _ => None,
+                }
+                .ok_or_else(|| FromJsonError::new(stringify!($ty), &value))
+            }
+        }
+    )*)*};
+}
+
+try_from_integer! {
+    as_u64 => u8 u16 u32 u64 usize;
+    as_i64 => i8 i16 i32 i64 isize;
+}
+#[cfg(feature = "i128")]
+try_from_integer! {
+    as_u128 => u128;
+    as_i128 => i128;
+}
+
+/// The nearest `f64`, see [`Number::as_f64`](crate::Number::as_f64).
+impl<S: Key, M: MapKind> TryFrom<JsonValue<S, M>> for f64 {
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        match &value {
+            JsonValue::Number(n) => n.as_f64(),
+            _ => None,
+        }
+        .ok_or_else(|| FromJsonError::new("f64", &value))
+    }
+}
+
+/// The nearest `f32`; numbers beyond its range become infinities.
+impl<S: Key, M: MapKind> TryFrom<JsonValue<S, M>> for f32 {
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        match &value {
+            JsonValue::Number(n) => n.as_f64().map(|n| n as f32),
+            _ => None,
+        }
+        .ok_or_else(|| FromJsonError::new("f32", &value))
+    }
+}
+
+impl<S: Key, M: MapKind, T> TryFrom<JsonValue<S, M>> for Vec<T>
+where
+    T: TryFrom<JsonValue<S, M>>,
+    T::Error: Into<FromJsonError>,
+{
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        let JsonValue::Array(items) = value else {
+            return Err(FromJsonError::new("Vec", &value));
+        };
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| T::try_from(item).map_err(|err| err.into().within(&i.to_string())))
+            .collect()
+    }
+}
+
+impl<S, M, T, H> TryFrom<JsonValue<S, M>> for HashMap<String, T, H>
+where
+    S: Key + Into<String>,
+    M: MapKind,
+    T: TryFrom<JsonValue<S, M>>,
+    T::Error: Into<FromJsonError>,
+    H: BuildHasher + Default,
+{
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<S, M>) -> Result<Self, FromJsonError> {
+        let JsonValue::Object(map) = value else {
+            return Err(FromJsonError::new("HashMap", &value));
+        };
+        map.into_iter()
+            .map(|(key, val)| {
+                let key: String = key.into();
+                match T::try_from(val) {
+                    Ok(val) => Ok((key, val)),
+                    Err(err) => Err(err.into().within(&key)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{parse, JsonKind, JsonValue, Object};
+
+    use super::FromJsonError;
+
+    #[test]
+    fn test_into() {
+        let value = parse(r#"{"a": ["x", 1.5]}"#).unwrap();
+        let mut map = value.into_map().unwrap();
+        let items = Object::remove(&mut map, "a").unwrap().into_vec().unwrap();
+        let [s, n]: [JsonValue; 2] = items.try_into().unwrap();
+        assert_eq!(n.into_f64().unwrap(), 1.5);
+
+        let err = s.into_f64().unwrap_err();
+        assert_eq!(
+            (err.expected(), err.found()),
+            (JsonKind::Number, JsonKind::String)
+        );
+        assert_eq!(err.to_string(), "expected number, found string");
+        assert_eq!(err.into_value().into_string().unwrap(), "x");
+
+        let null: JsonValue = JsonValue::Null;
+        let err = null.into_vec().unwrap_err();
+        assert_eq!(err.to_string(), "expected array, found null");
+        assert_eq!(err.into_value(), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_try_from() {
+        let value = parse(r#"{"a": [1, 2], "b": []}"#).unwrap();
+        let map: HashMap<String, Vec<u8>> = value.clone().try_into().unwrap();
+        assert_eq!(map["a"], [1, 2]);
+        assert!(map["b"].is_empty());
+        let map: HashMap<String, Vec<JsonValue>> = value.try_into().unwrap();
+        assert_eq!(map["a"].len(), 2);
+
+        let value = parse(r#"[["x", "y"], ["z"]]"#).unwrap();
+        let strings: Vec<Vec<String>> = value.try_into().unwrap();
+        assert_eq!(strings, [vec!["x", "y"], vec!["z"]]);
+
+        let value = parse(r#"[true, 1.5, -1, 300]"#).unwrap();
+        let items = value.clone().into_vec().unwrap();
+        assert_eq!(bool::try_from(items[0].clone()), Ok(true));
+        assert_eq!(f64::try_from(items[1].clone()), Ok(1.5));
+        assert_eq!(i8::try_from(items[2].clone()), Ok(-1));
+        assert!(u64::try_from(items[2].clone()).is_err());
+        assert!(i64::try_from(items[1].clone()).is_err());
+
+        let err: FromJsonError = Vec::<u8>::try_from(value).unwrap_err();
+        assert_eq!(err.pointer().as_str(), "/0");
+        assert_eq!(err.to_string(), "can't convert bool at \"/0\" to u8");
+        let err = Vec::<Vec<u8>>::try_from(parse("[[], [1, 256]]").unwrap()).unwrap_err();
+        assert_eq!((err.pointer().as_str(), err.expected()), ("/1/1", "u8"));
+        let err = String::try_from(parse("[]").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "can't convert array to String");
+    }
+}
@@ -0,0 +1,158 @@
+//! Moving through a document and editing it in place.
+
+use std::{borrow::Borrow, mem};
+
+use crate::{
+    map::{DefaultMapKind, Key, MapKind, Object},
+    JsonPointer, JsonValue,
+};
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// A cursor on the value itself.
+    pub fn cursor(&mut self) -> Cursor<'_, S, M> {
+        Cursor {
+            root: self,
+            path: JsonPointer::root(),
+        }
+    }
+}
+
+/// A position in a document borrowed mutably, moved with [`down`],
+/// [`up`] and [`next_sibling`], each returning whether it moved, and
+/// editing the value it's on with [`set`] or [`value_mut`].
+///
+/// [`down`]: Cursor::down
+/// [`up`]: Cursor::up
+/// [`next_sibling`]: Cursor::next_sibling
+/// [`set`]: Cursor::set
+/// [`value_mut`]: Cursor::value_mut
+pub struct Cursor<'d, S: Key = String, M: MapKind = DefaultMapKind> {
+    root: &'d mut JsonValue<S, M>,
+    /// always points at a value of `root`
+    path: JsonPointer,
+}
+
+impl<S: Key + Borrow<str>, M: MapKind> Cursor<'_, S, M> {
+    /// JSON Pointer of the value the cursor is on.
+    pub fn path(&self) -> &JsonPointer {
+        &self.path
+    }
+
+    pub fn value(&self) -> &JsonValue<S, M> {
+        self.root
+            .pointer(self.path.as_str())
+            .expect("cursor path points at a value")
+    }
+
+    pub fn value_mut(&mut self) -> &mut JsonValue<S, M> {
+        self.root
+            .pointer_mut(self.path.as_str())
+            .expect("cursor path points at a value")
+    }
+
+    /// Replace the value the cursor is on, returning the old one.
+    pub fn set(&mut self, value: JsonValue<S, M>) -> JsonValue<S, M> {
+        mem::replace(self.value_mut(), value)
+    }
+
+    /// Move to the member `token` of an object, or the element at index
+    /// `token` of an array.
+    pub fn down(&mut self, token: &str) -> bool {
+        let child = self.path.child(token);
+        let found = self.root.pointer(child.as_str()).is_some();
+        if found {
+            self.path = child;
+        }
+        found
+    }
+
+    /// Move to the element at `index` of an array.
+    pub fn down_index(&mut self, index: usize) -> bool {
+        matches!(self.value(), JsonValue::Array(_)) && self.down(&index.to_string())
+    }
+
+    /// Move to the parent, unless the cursor is on the root.
+    pub fn up(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Move to the next element of the parent array, or the next member of
+    /// the parent object in its iteration order.
+    pub fn next_sibling(&mut self) -> bool {
+        let (Some(parent), Some(token)) = (self.path.parent(), self.path.last()) else {
+            return false;
+        };
+        let next = match self.root.pointer(parent.as_str()) {
+            Some(JsonValue::Array(items)) => token
+                .parse::<usize>()
+                .ok()
+                .map(|i| i + 1)
+                .filter(|&i| i < items.len())
+                .map(|i| i.to_string()),
+            Some(JsonValue::Object(map)) => map
+                .iter()
+                .map(|(key, _)| key.borrow())
+                .skip_while(|&key| key != token)
+                .nth(1)
+                .map(str::to_owned),
+            _ => None,
+        };
+        match next {
+            Some(next) => {
+                self.path = parent.child(&next);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn test_cursor() {
+        let mut value = parse(r#"{"a": [1, {"b": 2}, 3], "c~/": null}"#).unwrap();
+        let mut cursor = value.cursor();
+        assert!(!cursor.up() && !cursor.next_sibling());
+        assert!(cursor.down("a") && cursor.down_index(1) && cursor.down("b"));
+        assert_eq!(cursor.path(), "/a/1/b");
+        assert_eq!(cursor.set(JsonValue::from("x")), JsonValue::from(2u64));
+        assert!(!cursor.down("b") && !cursor.down_index(0) && !cursor.next_sibling());
+
+        assert!(cursor.up() && cursor.next_sibling());
+        assert_eq!(
+            (cursor.path().as_str(), cursor.value()),
+            ("/a/2", &JsonValue::from(3u64))
+        );
+        assert!(!cursor.next_sibling() && !cursor.down_index(5));
+        *cursor.value_mut() = JsonValue::Null;
+        assert!(cursor.up() && cursor.up() && cursor.down("c~/"));
+        assert_eq!(cursor.path(), "/c~0~1");
+        assert!(cursor.up() && !cursor.down("d"));
+        assert_eq!(cursor.path(), "");
+
+        assert_eq!(
+            value,
+            parse(r#"{"a": [1, {"b": "x"}, null], "c~/": null}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_next_sibling_members() {
+        let mut value = parse(r#"{"a": 1, "b": 2, "c": 3}"#).unwrap();
+        // members in the iteration order of the map
+        let pointers: Vec<_> = match &value {
+            JsonValue::Object(map) => map.keys().map(|key| format!("/{}", key)).collect(),
+            _ => unreachable!(),
+        };
+        let mut cursor = value.cursor();
+        assert!(cursor.down(&pointers[0][1..]));
+        let mut seen = vec![cursor.path().to_string()];
+        while cursor.next_sibling() {
+            seen.push(cursor.path().to_string());
+        }
+        assert_eq!(seen, pointers);
+    }
+}
@@ -0,0 +1,62 @@
+//! Recognizing RFC 3339 timestamps in string values.
+
+use std::borrow::Borrow;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    map::{Key, MapKind},
+    JsonPointer, JsonValue,
+};
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// The RFC 3339 timestamp held by a string value, e.g.
+    /// `"2024-05-01T12:30:00+02:00"`, with its offset.
+    pub fn as_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            JsonValue::String(s) => DateTime::parse_from_rfc3339(s.borrow()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Every string value holding an RFC 3339 timestamp, with its JSON
+    /// Pointer, in the order of [`walk`](JsonValue::walk).
+    pub fn find_datetime_fields(&self) -> Vec<(JsonPointer, DateTime<FixedOffset>)> {
+        self.walk()
+            .filter_map(|(pointer, value)| Some((pointer, value.as_datetime()?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::parse;
+
+    #[test]
+    fn test_datetime() {
+        let value = parse(
+            r#"[{"ts": "2024-05-01T12:30:00+02:00"}, "2024-05-01", "2024-05-01T10:30:00.5Z", 1]"#,
+        )
+        .unwrap();
+        let at = |ms| Utc.timestamp_millis_opt(ms).unwrap();
+        assert_eq!(
+            value.pointer("/0/ts").and_then(|ts| ts.as_datetime()),
+            Some(at(1_714_559_400_000).fixed_offset())
+        );
+        assert_eq!(value.pointer("/1").and_then(|d| d.as_datetime()), None);
+        let fields: Vec<_> = value
+            .find_datetime_fields()
+            .into_iter()
+            .map(|(pointer, ts)| (pointer.to_string(), ts.with_timezone(&Utc)))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("/0/ts".to_owned(), at(1_714_559_400_000)),
+                ("/2".to_owned(), at(1_714_559_400_500)),
+            ]
+        );
+    }
+}
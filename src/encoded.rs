@@ -0,0 +1,129 @@
+//! Typed access to string values holding UUIDs and base64 data, and the
+//! convention for embedding binary data.
+
+use std::borrow::Borrow;
+
+#[cfg(feature = "base64")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+use crate::{
+    map::{Key, MapKind},
+    JsonValue,
+};
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// The UUID held by a string value, hyphenated like
+    /// `"67e55044-10b1-426f-9247-bb680e5fe0c8"` or in one of the other forms
+    /// [`Uuid::parse_str`] accepts.
+    #[cfg(feature = "uuid")]
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            JsonValue::String(s) => Uuid::parse_str(s.borrow()).ok(),
+            _ => None,
+        }
+    }
+
+    /// The bytes encoded by a string value in standard, padded base64
+    /// (RFC 4648, section 4).
+    #[cfg(feature = "base64")]
+    pub fn as_base64_bytes(&self) -> Option<Vec<u8>> {
+        BinaryEncoding::new().decode(self)
+    }
+}
+
+/// How binary data is embedded in string values: standard, padded base64,
+/// after an optional marker telling binary strings from text, e.g.
+/// `"base64:aGVsbG8="` with the marker `"base64:"`.
+#[cfg(feature = "base64")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BinaryEncoding {
+    marker: Option<String>,
+}
+
+#[cfg(feature = "base64")]
+impl BinaryEncoding {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefix written before the base64 data and required when decoding.
+    pub fn marker(mut self, marker: impl Into<String>) -> Self {
+        self.marker = Some(marker.into());
+        self
+    }
+
+    /// The string value holding `bytes`.
+    pub fn encode<S: Key + From<String>, M: MapKind>(&self, bytes: &[u8]) -> JsonValue<S, M> {
+        let marker = self.marker.as_deref().unwrap_or("");
+        JsonValue::String(S::from(format!("{}{}", marker, STANDARD.encode(bytes))))
+    }
+
+    /// The bytes held by a string value, `None` for other values, strings
+    /// without the marker and invalid base64.
+    pub fn decode<S: Key + Borrow<str>, M: MapKind>(
+        &self,
+        value: &JsonValue<S, M>,
+    ) -> Option<Vec<u8>> {
+        let s = match value {
+            JsonValue::String(s) => s.borrow(),
+            _ => return None,
+        };
+        let data = match &self.marker {
+            Some(marker) => s.strip_prefix(marker.as_str())?,
+            None => s,
+        };
+        STANDARD.decode(data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid() {
+        let value = parse(
+            r#"["67e55044-10b1-426f-9247-bb680e5fe0c8", "67e5504410b1426f9247bb680e5fe0c8",
+                "67e55044-10b1-426f-9247", 1]"#,
+        )
+        .unwrap();
+        let uuids: Vec<_> = (0..4)
+            .map(|i| value.pointer(&format!("/{}", i)).unwrap().as_uuid())
+            .collect();
+        let uuid = uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+        assert_eq!(uuids, [Some(uuid), Some(uuid), None, None]);
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_base64() {
+        let value = parse(r#"["aGVsbG8=", "aGVsbG8", "a$b=", ""]"#).unwrap();
+        let bytes: Vec<_> = (0..4)
+            .map(|i| value.pointer(&format!("/{}", i)).unwrap().as_base64_bytes())
+            .collect();
+        assert_eq!(
+            bytes,
+            [Some(b"hello".to_vec()), None, None, Some(Vec::new())]
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_binary_encoding() {
+        use super::BinaryEncoding;
+        use crate::JsonValue;
+
+        let plain = BinaryEncoding::new();
+        let marked = BinaryEncoding::new().marker("base64:");
+        let value: JsonValue = marked.encode(&[0, 255, 7]);
+        assert_eq!(value, JsonValue::from("base64:AP8H"));
+        assert_eq!(marked.decode(&value), Some(vec![0, 255, 7]));
+        assert_eq!(plain.decode(&value), None);
+        let value: JsonValue = plain.encode(b"x");
+        assert_eq!(marked.decode(&value), None);
+        assert_eq!(marked.decode(&JsonValue::<String>::Null), None);
+    }
+}
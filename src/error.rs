@@ -0,0 +1,1051 @@
+use std::{
+    error::Error,
+    fmt::{self, Write},
+    io,
+    str::Utf8Error,
+};
+
+use nom::error::{VerboseError, VerboseErrorKind};
+
+use crate::{
+    map::Map,
+    position::{LineIndex, Position},
+    JsonValue,
+};
+
+/// Category of a [`ParseError`], for matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A character that can't start or continue the current production.
+    UnexpectedToken,
+    /// The input ended before the document was complete.
+    UnexpectedEof,
+    /// A string without its closing quote.
+    UnterminatedString,
+    /// A raw control character inside a string.
+    ControlCharacter,
+    /// A backslash followed by something other than a JSON escape.
+    InvalidEscape,
+    /// A `\u` escape not followed by four hex digits.
+    InvalidUnicodeEscape,
+    /// A `\u` escape naming a lone UTF-16 surrogate.
+    LoneSurrogate,
+    /// A number too large or too small for `f64`.
+    NumberOutOfRange,
+    /// An integer `f64` can't represent exactly.
+    IntegerTooLarge,
+    /// An object key not followed by `:`.
+    MissingColon,
+    /// An object member followed by another without a `,` between them.
+    MissingComma,
+    /// A key occurring twice in the same object.
+    DuplicateKey,
+    /// Keys in the same object that only differ by Unicode normalization form.
+    KeyNormalizationConflict,
+    /// Arrays and objects nested deeper than the configured limit.
+    DepthLimitExceeded,
+    /// A string longer than the configured limit.
+    StringTooLong,
+    /// A document with more values than the configured limit.
+    TooManyValues,
+    /// A parse that took longer than the configured budget.
+    Timeout,
+    /// Non-whitespace characters after the document.
+    TrailingCharacters,
+    /// Bytes input that isn't valid UTF-8, or UTF-16 where
+    /// [`ParserOptions::allow_utf16`](crate::ParserOptions::allow_utf16)
+    /// reads it.
+    InvalidUtf8,
+    /// Reading the input failed.
+    Io,
+    /// An HTTP body whose content type isn't JSON or whose charset or byte
+    /// order mark isn't UTF-8.
+    UnsupportedMediaType,
+    /// A document in a stream longer than the configured limit.
+    TooLarge,
+}
+
+impl ErrorKind {
+    /// Context label the parser attaches to failures of this kind.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken => "unexpected token",
+            ErrorKind::UnexpectedEof => "unexpected end of input",
+            ErrorKind::UnterminatedString => "unterminated string",
+            ErrorKind::ControlCharacter => "control character in string",
+            ErrorKind::InvalidEscape => "invalid escape",
+            ErrorKind::InvalidUnicodeEscape => "invalid unicode escape",
+            ErrorKind::LoneSurrogate => "lone surrogate",
+            ErrorKind::NumberOutOfRange => "number out of range",
+            ErrorKind::IntegerTooLarge => "integer too large",
+            ErrorKind::MissingColon => "expected `:`",
+            ErrorKind::MissingComma => "expected `,`",
+            ErrorKind::DuplicateKey => "duplicate key",
+            ErrorKind::KeyNormalizationConflict => "keys differ only by normalization",
+            ErrorKind::DepthLimitExceeded => "depth limit exceeded",
+            ErrorKind::StringTooLong => "string too long",
+            ErrorKind::TooManyValues => "too many values",
+            ErrorKind::Timeout => "time budget exceeded",
+            ErrorKind::TrailingCharacters => "trailing characters",
+            ErrorKind::InvalidUtf8 => "invalid UTF-8",
+            ErrorKind::Io => "I/O error",
+            ErrorKind::UnsupportedMediaType => "unsupported media type",
+            ErrorKind::TooLarge => "document too large",
+        }
+    }
+
+    /// Stable diagnostic ID, e.g. `E0003` for [`ErrorKind::UnterminatedString`].
+    ///
+    /// Codes are never reused or renumbered, so tests and monitoring can match
+    /// on them instead of on message text.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken => "E0001",
+            ErrorKind::UnexpectedEof => "E0002",
+            ErrorKind::UnterminatedString => "E0003",
+            ErrorKind::ControlCharacter => "E0004",
+            ErrorKind::InvalidEscape => "E0005",
+            ErrorKind::LoneSurrogate => "E0006",
+            ErrorKind::NumberOutOfRange => "E0007",
+            ErrorKind::IntegerTooLarge => "E0008",
+            ErrorKind::DuplicateKey => "E0009",
+            ErrorKind::DepthLimitExceeded => "E0010",
+            ErrorKind::TrailingCharacters => "E0011",
+            ErrorKind::InvalidUtf8 => "E0012",
+            ErrorKind::Io => "E0013",
+            ErrorKind::KeyNormalizationConflict => "E0014",
+            ErrorKind::InvalidUnicodeEscape => "E0015",
+            ErrorKind::StringTooLong => "E0016",
+            ErrorKind::TooManyValues => "E0017",
+            ErrorKind::MissingColon => "E0018",
+            ErrorKind::MissingComma => "E0019",
+            ErrorKind::Timeout => "E0020",
+            ErrorKind::UnsupportedMediaType => "E0021",
+            ErrorKind::TooLarge => "E0022",
+        }
+    }
+
+    const LABELLED: [ErrorKind; 12] = [
+        ErrorKind::InvalidUnicodeEscape,
+        ErrorKind::LoneSurrogate,
+        ErrorKind::NumberOutOfRange,
+        ErrorKind::IntegerTooLarge,
+        ErrorKind::MissingColon,
+        ErrorKind::MissingComma,
+        ErrorKind::DuplicateKey,
+        ErrorKind::KeyNormalizationConflict,
+        ErrorKind::DepthLimitExceeded,
+        ErrorKind::StringTooLong,
+        ErrorKind::TooManyValues,
+        ErrorKind::Timeout,
+    ];
+
+    /// Context label marking the start of the key of the member a failure is
+    /// in, see [`ParseError::key`].
+    pub(crate) const KEY_LABEL: &'static str = "key";
+
+    fn from_label(label: &str) -> Option<ErrorKind> {
+        ErrorKind::LABELLED
+            .into_iter()
+            .find(|kind| kind.label() == label)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Error returned by [`parse`](crate::parse) and friends.
+#[derive(Debug)]
+pub struct ParseError {
+    kind: ErrorKind,
+    position: Position,
+    context: Option<String>,
+    key: Option<String>,
+    expected: Option<&'static str>,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ErrorKind, position: Position) -> Self {
+        ParseError {
+            kind,
+            position,
+            context: None,
+            key: None,
+            expected: None,
+            source: None,
+        }
+    }
+
+    pub(crate) fn io(err: io::Error) -> Self {
+        ParseError {
+            source: Some(Box::new(err)),
+            ..ParseError::new(ErrorKind::Io, LineIndex::new("").position(0))
+        }
+    }
+
+    pub(crate) fn utf8(input: &[u8], err: Utf8Error) -> Self {
+        let valid = std::str::from_utf8(&input[..err.valid_up_to()]).unwrap_or_default();
+        ParseError {
+            source: Some(Box::new(err)),
+            ..ParseError::new(
+                ErrorKind::InvalidUtf8,
+                LineIndex::new(valid).position(valid.len()),
+            )
+        }
+    }
+
+    pub(crate) fn media_type(reason: String) -> Self {
+        ParseError {
+            source: Some(reason.into()),
+            ..ParseError::new(
+                ErrorKind::UnsupportedMediaType,
+                LineIndex::new("").position(0),
+            )
+        }
+    }
+
+    /// Relocate an error in an input that is the part of a larger input
+    /// starting at `start`.
+    pub(crate) fn starting_at(mut self, start: Position) -> Self {
+        if self.position.line == 1 {
+            self.position.column += start.column - 1;
+        }
+        self.position.line += start.line - 1;
+        self.position.offset += start.offset;
+        self
+    }
+
+    /// Classify the failure recorded by a `VerboseError` on `input`.
+    pub(crate) fn from_verbose(input: &str, err: &VerboseError<&str>) -> Self {
+        let index = LineIndex::new(input);
+        let position = |rest: &str| index.position(input.len() - rest.len());
+        // innermost first, leaving out the arrays, objects and strings that
+        // were only tried as alternatives
+        let contexts: Vec<(&str, &str)> = err
+            .errors
+            .iter()
+            .filter_map(|(rest, kind)| match kind {
+                VerboseErrorKind::Context(label) => Some((*rest, *label)),
+                _ => None,
+            })
+            .filter(|&(rest, label)| match label {
+                "array" => rest.starts_with('['),
+                "object" => rest.starts_with('{'),
+                "string" => rest.starts_with('"'),
+                _ => true,
+            })
+            .collect();
+        let rest = err.errors.first().map_or("", |(rest, _)| rest);
+        let context = describe(&contexts, rest);
+        let contexts = || contexts.iter().copied();
+
+        if let Some((rest, kind)) =
+            contexts().find_map(|(rest, label)| Some((rest, ErrorKind::from_label(label)?)))
+        {
+            let key = contexts()
+                .find(|&(_, label)| label == ErrorKind::KEY_LABEL)
+                .map(|(key, _)| string_token(key).to_owned());
+            let (kind, expected) = match kind {
+                ErrorKind::MissingColon if rest.is_empty() => {
+                    (ErrorKind::UnexpectedEof, Some("`:`"))
+                }
+                kind => (kind, None),
+            };
+            return ParseError {
+                context,
+                key,
+                expected,
+                ..ParseError::new(kind, position(rest))
+            };
+        }
+
+        let in_context = |label| contexts().any(|(_, l)| l == label);
+        let kind = if in_context("escape") {
+            ErrorKind::InvalidEscape
+        } else if in_context("string") && rest.is_empty() {
+            ErrorKind::UnterminatedString
+        } else if in_context("string") && rest.starts_with(|ch: char| ch.is_ascii_control()) {
+            ErrorKind::ControlCharacter
+        } else if let Some(expected) = expected_at_end(
+            input,
+            rest,
+            contexts().find(|(_, label)| {
+                !matches!(*label, "object key" | "array element" | "member value")
+            }),
+        ) {
+            return ParseError {
+                context,
+                expected: Some(expected),
+                ..ParseError::new(ErrorKind::UnexpectedEof, position(""))
+            };
+        } else {
+            ErrorKind::UnexpectedToken
+        };
+        ParseError {
+            context,
+            ..ParseError::new(kind, position(rest))
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Stable diagnostic ID of the [`kind`](ParseError::kind).
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// The key, as written in the input, of the object member the error is
+    /// about, e.g. `"hello"` for a [`MissingColon`](ErrorKind::MissingColon)
+    /// after it.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// What the parser was waiting for when the input ended, for an
+    /// [`UnexpectedEof`](ErrorKind::UnexpectedEof) error, e.g. `` `,` or `]` ``
+    /// for `[1, 2`.
+    pub fn expected(&self) -> Option<&str> {
+        self.expected
+    }
+
+    /// Where in the input the error was detected.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Byte offset into the input where the error was detected.
+    pub fn offset(&self) -> usize {
+        self.position.offset
+    }
+
+    /// 1-based line of the [`position`](ParseError::position).
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    /// 1-based column, in `char`s, of the [`position`](ParseError::position).
+    pub fn column(&self) -> usize {
+        self.position.column
+    }
+
+    /// The error with the lines of `input` around it and a caret under its
+    /// column, as [`Snippet::default`] renders it.
+    pub fn render(&self, input: &str) -> String {
+        Snippet::default().render(self, input)
+    }
+
+    /// The error as a diagnostic document for e.g. an API error response,
+    /// with the lines of `input` around it:
+    ///
+    /// ```json
+    /// {
+    ///   "code": "E0019",
+    ///   "message": "expected `,` after key \"a\" at line 1, column 9, in object",
+    ///   "offset": 8, "line": 1, "column": 9,
+    ///   "key": "\"a\"",
+    ///   "context": "in object",
+    ///   "snippet": "  |\n1 | {\"a\": 1 \"b\": 2}\n  |         ^\n",
+    ///   "hints": ["separate object members with `,`"]
+    /// }
+    /// ```
+    ///
+    /// `key`, `expected` and `context` are only there when the error has
+    /// them.
+    pub fn to_json(&self, input: &str) -> JsonValue {
+        let mut object = Map::new();
+        object.insert("code".to_owned(), self.code().into());
+        object.insert("message".to_owned(), self.to_string().into());
+        object.insert("offset".to_owned(), self.offset().into());
+        object.insert("line".to_owned(), self.line().into());
+        object.insert("column".to_owned(), self.column().into());
+        let optional = [
+            ("key", self.key()),
+            ("expected", self.expected()),
+            ("context", self.context.as_deref()),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                object.insert(name.to_owned(), value.into());
+            }
+        }
+        let mut snippet = String::new();
+        Snippet::default().write_lines(&mut snippet, self, input);
+        object.insert("snippet".to_owned(), snippet.into());
+        let hints = self.hints().iter().map(|&hint| hint.into()).collect();
+        object.insert("hints".to_owned(), JsonValue::Array(hints));
+        JsonValue::Object(object)
+    }
+
+    /// how to fix the input, or the options that would accept it
+    fn hints(&self) -> &'static [&'static str] {
+        match self.kind {
+            ErrorKind::UnexpectedEof => &["the input may have been truncated"],
+            ErrorKind::UnterminatedString => &["close the string with `\"`"],
+            ErrorKind::ControlCharacter => {
+                &["escape control characters in strings, e.g. `\\n` for a line break"]
+            }
+            ErrorKind::InvalidEscape => &[
+                "the escapes are `\\\"`, `\\\\`, `\\/`, `\\b`, `\\f`, `\\n`, `\\r`, `\\t` and `\\u`",
+                "`ParserOptions::escape_decoder` accepts others",
+            ],
+            ErrorKind::InvalidUnicodeEscape => &["`\\u` takes four hex digits"],
+            ErrorKind::LoneSurrogate => &["`ParserOptions::lone_surrogates` accepts them"],
+            ErrorKind::NumberOutOfRange => &[
+                "`ParserOptions::number_overflow` and `number_underflow` accept such numbers",
+            ],
+            ErrorKind::IntegerTooLarge => &["`ParserOptions::large_integers` accepts them"],
+            ErrorKind::MissingColon => &["separate a key from its value with `:`"],
+            ErrorKind::MissingComma => &["separate object members with `,`"],
+            ErrorKind::DuplicateKey => &["`ParserOptions::duplicate_keys` accepts them"],
+            ErrorKind::DepthLimitExceeded => &["`ParserOptions::max_depth` sets the limit"],
+            ErrorKind::StringTooLong => &["`ParserOptions::max_string_len` sets the limit"],
+            ErrorKind::TooManyValues => &["`ParserOptions::max_values` sets the limit"],
+            ErrorKind::Timeout => &["`ParserOptions::max_duration` sets the budget"],
+            ErrorKind::TrailingCharacters => {
+                &["a document is a single value; NDJSON holds one per line"]
+            }
+            ErrorKind::InvalidUtf8 => &[
+                "the input must be UTF-8",
+                "`ParserOptions::invalid_utf8` replaces invalid sequences",
+            ],
+            ErrorKind::UnsupportedMediaType => {
+                &["JSON bodies are `application/json` or `application/*+json`, in UTF-8"]
+            }
+            ErrorKind::TooLarge => &["`ChunkDecoder::max_buffered` sets the limit"],
+            ErrorKind::UnexpectedToken
+            | ErrorKind::KeyNormalizationConflict
+            | ErrorKind::Io => &[],
+        }
+    }
+}
+
+/// How [`ParseError::render`] shows the input around an error: e.g.
+///
+/// ```text
+/// error[E0019]: expected `,` after key "a" at line 2, column 10, in object
+///   |
+/// 1 | {
+/// 2 |   "a": 1 "b": 2
+///   |          ^
+/// 3 | }
+/// ```
+///
+/// Lines wider than [`max_width`](Snippet::max_width) are cut to a window
+/// around the column, marked with `…`, so that documents on a single long
+/// line, such as minified ones, stay readable; the caret is then labelled
+/// with the column and byte offset of the error:
+///
+/// ```text
+/// error[E0001]: unexpected token at line 1, column 1048577, in array
+///   |
+/// 1 | …1, 1, 1, 1, x, 2, 2,…
+///   |            ^ column 1048577, byte 1048576
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snippet {
+    context_lines: usize,
+    max_width: usize,
+}
+
+impl Default for Snippet {
+    fn default() -> Self {
+        Snippet {
+            context_lines: 2,
+            max_width: 100,
+        }
+    }
+}
+
+impl Snippet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lines shown before and after the line of the error.
+    pub fn context_lines(mut self, lines: usize) -> Self {
+        self.context_lines = lines;
+        self
+    }
+
+    /// Characters of each line shown, at least 1.
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = width.max(1);
+        self
+    }
+
+    /// `err`, an error parsing `input`, with the lines around it.
+    pub fn render(&self, err: &ParseError, input: &str) -> String {
+        let mut out = format!("error[{}]: {}\n", err.code(), err);
+        self.write_lines(&mut out, err, input);
+        out
+    }
+
+    /// the lines of `input` around `err`, with the caret
+    fn write_lines(&self, out: &mut String, err: &ParseError, input: &str) {
+        let Position { line, column, .. } = err.position();
+        let first = line.saturating_sub(self.context_lines).max(1);
+        let mut lines: Vec<(usize, &str)> = input
+            .split('\n')
+            .enumerate()
+            .map(|(i, text)| (i + 1, text.strip_suffix('\r').unwrap_or(text)))
+            .skip(first - 1)
+            .take(line + self.context_lines + 1 - first)
+            .collect();
+        // nothing after the final newline, unless the error is there
+        let last = input.matches('\n').count() + 1;
+        if input.ends_with('\n') && lines.last().is_some_and(|&(n, _)| n == last && n > line) {
+            lines.pop();
+        }
+        if !lines.iter().any(|&(n, _)| n == line) {
+            return;
+        }
+        // the same columns of every line, centered on the error if its line
+        // is too wide
+        let width = lines
+            .iter()
+            .find(|&&(n, _)| n == line)
+            .map_or(0, |(_, text)| text.chars().count());
+        let start = if width > self.max_width {
+            (column - 1)
+                .saturating_sub(self.max_width / 2)
+                .min(width + 1 - self.max_width)
+        } else {
+            0
+        };
+        let gutter = lines.last().map_or(1, |(n, _)| n.to_string().len());
+        let _ = writeln!(out, "{:gutter$} |", "");
+        for (n, text) in lines {
+            let (shown, cut_before) = self.window(text, start);
+            let _ = writeln!(out, "{:>gutter$} | {}", n, shown);
+            if n == line {
+                let indent = column - 1 - start + usize::from(cut_before);
+                let _ = write!(out, "{:gutter$} | {:indent$}^", "", "");
+                // where the window is, as the line is too wide to see it
+                if width > self.max_width {
+                    let _ = write!(out, " column {}, byte {}", column, err.offset());
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    /// The `max_width` characters of `text` from `start`, tabs and control
+    /// characters shown as spaces to keep the caret aligned, and whether
+    /// some were cut before them.
+    fn window(&self, text: &str, start: usize) -> (String, bool) {
+        let mut chars = text
+            .chars()
+            .map(|ch| if ch.is_control() { ' ' } else { ch });
+        let cut_before = chars.by_ref().take(start).count() > 0;
+        let mut shown = String::new();
+        if cut_before {
+            shown.push('…');
+        }
+        shown.extend(chars.by_ref().take(self.max_width));
+        if chars.next().is_some() {
+            shown.push('…');
+        }
+        (shown, cut_before)
+    }
+}
+
+/// What the parser was waiting for when `input` ended, if it did: `rest` is
+/// where the parser failed, and `innermost` the `(rest, label)` of the
+/// innermost production it was in. A failure just before the end can be a
+/// truncated token, such as `[1,` or `[tr`.
+fn expected_at_end(
+    input: &str,
+    rest: &str,
+    innermost: Option<(&str, &str)>,
+) -> Option<&'static str> {
+    let ws = [' ', '\n', '\r', '\t'];
+    // (whether only the opening bracket was read, whether a `,` was last)
+    let read = |span: &str| {
+        let span = span.trim_end_matches(ws);
+        (span.len() == 1, span.ends_with(','))
+    };
+    let before = input[..input.len() - rest.len()].chars().next_back();
+    let rest = rest.trim_end_matches(ws);
+    if !rest.is_empty() {
+        return match rest {
+            "," if innermost.is_some_and(|(_, label)| label == "array") => Some("a value"),
+            "-" => Some("a digit"),
+            "." | "e" | "E" | "e+" | "e-" | "E+" | "E-"
+                if before.is_some_and(|ch| ch.is_ascii_digit()) =>
+            {
+                Some("a digit")
+            }
+            _ => [("true", "`true`"), ("false", "`false`"), ("null", "`null`")]
+                .into_iter()
+                .find(|(literal, _)| literal.len() > rest.len() && literal.starts_with(rest))
+                .map(|(_, expected)| expected),
+        };
+    }
+    match innermost {
+        None => Some("`{` or `[`"),
+        Some((_, "value")) => Some("a value"),
+        Some((span, "array")) => match read(span) {
+            (true, _) => Some("a value or `]`"),
+            (_, true) => Some("a value"),
+            _ => Some("`,` or `]`"),
+        },
+        Some((span, "object")) => match read(span) {
+            (true, _) => Some("a key or `}`"),
+            (_, true) => Some("a key"),
+            _ => Some("`,` or `}`"),
+        },
+        _ => None,
+    }
+}
+
+/// Where the parser failed, from the `(rest, label)` of the productions it
+/// was in, innermost first, and where it failed: e.g. `in string, inside
+/// array element 17`, or `after `:` expecting value of member "a"`.
+fn describe(contexts: &[(&str, &str)], rest: &str) -> Option<String> {
+    // the productions worth naming, skipping the labels that only classify
+    let named: Vec<usize> = (0..contexts.len())
+        .filter(|&i| {
+            let label = contexts[i].1;
+            !matches!(label, "value" | "escape" | ErrorKind::KEY_LABEL)
+                && ErrorKind::from_label(label).is_none()
+        })
+        .collect();
+    let mut named = named.into_iter().peekable();
+    let mut i = named.next()?;
+    // the string of a key is the key
+    if contexts[i].1 == "string" && named.peek().is_some_and(|&j| contexts[j].1 == "object key") {
+        i = named.next()?;
+    }
+    let (span, label) = contexts[i];
+    let place = match label {
+        "object key" => "while parsing object key".to_owned(),
+        "member value" if span.trim_start_matches([' ', '\n', '\r', '\t']) == rest => {
+            let member = location(contexts, i)?;
+            return Some(format!("after `:` expecting value of {}", member));
+        }
+        "member value" | "array element" => {
+            return Some(format!("inside {}", location(contexts, i)?))
+        }
+        label => format!("in {}", label),
+    };
+    // the nearest element or member around it
+    match named.find_map(|j| location(contexts, j)) {
+        Some(outer) => Some(format!("{}, inside {}", place, outer)),
+        None => Some(place),
+    }
+}
+
+/// `array element 17` or `member "a"` for the array element or member value
+/// production at `i` of `contexts`.
+fn location(contexts: &[(&str, &str)], i: usize) -> Option<String> {
+    let (span, label) = contexts[i];
+    let enclosing = |open| {
+        contexts[i + 1..]
+            .iter()
+            .find(|&&(_, label)| label == open)
+            .map(|&(span, _)| span)
+    };
+    match label {
+        "array element" => {
+            let array = enclosing("array")?;
+            Some(format!(
+                "array element {}",
+                element_index(&array[1..array.len() - span.len()])
+            ))
+        }
+        "member value" => {
+            let object = enclosing("object")?;
+            Some(format!(
+                "member {}",
+                member_key(&object[..object.len() - span.len()])?
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Index of the element after `prefix`, the elements of an array before it
+/// up to and including their last `,`.
+fn element_index(prefix: &str) -> usize {
+    let (mut index, mut depth, mut in_string, mut escaped) = (0, 0, false, false);
+    for b in prefix.bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match b {
+                b'"' => in_string = true,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' => depth -= 1,
+                b',' if depth == 0 => index += 1,
+                _ => {}
+            }
+        }
+    }
+    index
+}
+
+/// The key, quotes included, of the member whose value follows `prefix`,
+/// which ends with the `:` after the key.
+fn member_key(prefix: &str) -> Option<&str> {
+    let key = prefix
+        .strip_suffix(':')?
+        .trim_end_matches([' ', '\n', '\r', '\t']);
+    let body = key.strip_suffix('"')?;
+    // the opening quote is the last one that isn't escaped
+    let start = body.char_indices().rev().find(|&(i, ch)| {
+        ch == '"' && body[..i].bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 0
+    })?;
+    Some(&key[start.0..])
+}
+
+/// The string token at the start of `s`, quotes included.
+fn string_token(s: &str) -> &str {
+    let mut escaped = false;
+    for (i, b) in s.bytes().enumerate().skip(1) {
+        match b {
+            b'"' if !escaped => return &s[..=i],
+            b'\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    s
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source)
+                if matches!(self.kind, ErrorKind::Io | ErrorKind::UnsupportedMediaType) =>
+            {
+                write!(f, "{}: {}", self.kind, source)?
+            }
+            _ => {
+                write!(f, "{}", self.kind)?;
+                if let Some(key) = &self.key {
+                    write!(f, " after key {}", key)?;
+                }
+                write!(f, " at {}", self.position)?;
+                if let Some(expected) = self.expected {
+                    write!(f, ", expected {}", expected)?;
+                }
+            }
+        }
+        if let Some(context) = &self.context {
+            write!(f, ", {}", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io, time::Duration};
+
+    use crate::{
+        parse, parse_bytes, parse_bytes_with, parse_fast, parse_fast_with, parse_reader,
+        parse_reader_with, parse_with, InvalidUtf8, JsonValue, ParserOptions,
+    };
+
+    use super::{ErrorKind, ParseError, Snippet};
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<ParseError>();
+    }
+
+    #[test]
+    fn test_kind() {
+        let kind = |s| parse(s).unwrap_err().kind();
+        assert_eq!(kind(r#"{"a" 1}"#), ErrorKind::MissingColon);
+        assert_eq!(kind(r#"{"a": 1 "b": 2}"#), ErrorKind::MissingComma);
+        assert_eq!(kind(r#"{"a": 1 2}"#), ErrorKind::UnexpectedToken);
+        assert_eq!(kind(r#"{"a": 1"#), ErrorKind::UnexpectedEof);
+        assert_eq!(kind(r#"["abc"#), ErrorKind::UnterminatedString);
+        assert_eq!(kind("[\"a\nb\"]"), ErrorKind::ControlCharacter);
+        assert_eq!(kind(r#"["\x"]"#), ErrorKind::InvalidEscape);
+        assert_eq!(kind(r#"["\u12G4"]"#), ErrorKind::InvalidUnicodeEscape);
+        assert_eq!(kind(r#"["\uD800"]"#), ErrorKind::LoneSurrogate);
+        assert_eq!(kind("[] []"), ErrorKind::TrailingCharacters);
+        assert_eq!(kind(&"[".repeat(200)), ErrorKind::DepthLimitExceeded);
+    }
+
+    #[test]
+    fn test_expected() {
+        let expected = |input| {
+            let err = parse(input).unwrap_err();
+            assert_eq!(err.offset(), input.len(), "{}", input);
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{}", input);
+            err.expected().unwrap().to_owned()
+        };
+        assert_eq!(expected(" "), "`{` or `[`");
+        assert_eq!(expected("[ "), "a value or `]`");
+        assert_eq!(expected("[1, 2"), "`,` or `]`");
+        assert_eq!(expected("[1, "), "a value");
+        assert_eq!(expected("{"), "a key or `}`");
+        assert_eq!(expected(r#"{"a": 1,"#), "a key");
+        assert_eq!(expected(r#"{"a": 1"#), "`,` or `}`");
+        assert_eq!(expected(r#"{"a""#), "`:`");
+        assert_eq!(expected(r#"{"a":"#), "a value");
+        assert_eq!(expected("[tr"), "`true`");
+        assert_eq!(expected("[1.5e"), "a digit");
+
+        let err = parse(r#"{"a": [1"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of input at line 1, column 9, expected `,` or `]`, in array, \
+             inside member \"a\""
+        );
+        assert_eq!(parse("[1 x").unwrap_err().expected(), None);
+        assert_eq!(
+            parse(r#"["a"#).unwrap_err().kind(),
+            ErrorKind::UnterminatedString
+        );
+    }
+
+    #[test]
+    fn test_context() {
+        let context = |input| {
+            let err = parse(input).unwrap_err().to_string();
+            // after `... at line 1, column 2, `
+            err.splitn(3, ", ").nth(2).unwrap().to_owned()
+        };
+        assert_eq!(
+            context(r#"[1, "a", "\x"]"#),
+            "in string, inside array element 2"
+        );
+        assert_eq!(
+            context(r#"[[], [1, {"b": 2}, 3 4]]"#),
+            "in array, inside array element 1"
+        );
+        assert_eq!(context(r#"{"a": 1, "b\x": 2}"#), "while parsing object key");
+        assert_eq!(
+            context(r#"{"a": {"b": [1], "c\"d": }}"#),
+            r#"after `:` expecting value of member "c\"d""#
+        );
+        assert_eq!(
+            context(r#"{"a": {"b": [1,, 2]}}"#),
+            r#"in array, inside member "b""#
+        );
+        assert_eq!(context(r#"{"a": 1 x}"#), "in object");
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "{\n  \"a\": 1 \"b\": 2\n}";
+        assert_eq!(
+            parse(input).unwrap_err().render(input),
+            "error[E0019]: expected `,` after key \"a\" at line 2, column 10, in object\n  \
+             |\n\
+             1 | {\n\
+             2 |   \"a\": 1 \"b\": 2\n  \
+             |          ^\n\
+             3 | }\n"
+        );
+
+        let input = format!("[{}x, {}]", "1, ".repeat(20), "2, ".repeat(20));
+        let err = parse(&input).unwrap_err();
+        let rendered = Snippet::new().max_width(20).render(&err, &input);
+        assert_eq!(
+            rendered.split_once('\n').unwrap().1,
+            "  |\n\
+             1 | …1, 1, 1, 1, x, 2, 2,…\n  \
+             |            ^ column 60, byte 59\n"
+        );
+
+        // minified, with multi-byte characters: columns count `char`s
+        let input = format!(r#"["{}", 1 2]"#, "é".repeat(1 << 16));
+        let err = parse(&input).unwrap_err();
+        let rendered = Snippet::new().max_width(10).render(&err, &input);
+        assert_eq!(
+            rendered.split_once('\n').unwrap().1,
+            "  |\n\
+             1 | …éé\", 1 2]\n  \
+             |         ^ column 65544, byte 131079\n"
+        );
+
+        let input = "[\n1,\n2,\n3,\n4,\n5\n6]";
+        let rendered = Snippet::new()
+            .context_lines(1)
+            .render(&parse(input).unwrap_err(), input);
+        assert_eq!(
+            rendered.split_once('\n').unwrap().1,
+            "  |\n6 | 5\n7 | 6]\n  | ^\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let input = r#"{"a": 1 "b": 2}"#;
+        let expect = parse(
+            r#"{
+                "code": "E0019",
+                "message": "expected `,` after key \"a\" at line 1, column 9, in object",
+                "offset": 8, "line": 1, "column": 9,
+                "key": "\"a\"",
+                "context": "in object",
+                "snippet": "  |\n1 | {\"a\": 1 \"b\": 2}\n  |         ^\n",
+                "hints": ["separate object members with `,`"]
+            }"#,
+        );
+        assert_eq!(parse(input).unwrap_err().to_json(input), expect.unwrap());
+
+        let json = parse("[1").unwrap_err().to_json("[1");
+        assert_eq!(json.pointer("/expected"), Some(&"`,` or `]`".into()));
+        let json = parse("[1 x]").unwrap_err().to_json("[1 x]");
+        assert_eq!(json.pointer("/hints"), Some(&JsonValue::Array(vec![])));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_prevalidated() {
+        assert_eq!(
+            crate::parse_prevalidated(r#" {"a": [1, "b"]} "#).unwrap(),
+            parse(r#"{"a": [1, "b"]}"#).unwrap()
+        );
+        for input in [r#"{"a": [1, "b"}"#, "[1] x", "[\"\\x\"]", ""] {
+            let err = crate::parse_prevalidated(input).unwrap_err();
+            assert_eq!(err.to_string(), parse(input).unwrap_err().to_string());
+        }
+    }
+
+    #[test]
+    fn test_fast() {
+        assert_eq!(
+            parse_fast(r#" {"a": [1, "b"]} "#),
+            Some(parse(r#"{"a": [1, "b"]}"#).unwrap())
+        );
+        for input in [r#"{"a": [1, "b"}"#, "[1] x", "[\"\\x\"]", ""] {
+            assert_eq!(parse_fast(input), None);
+        }
+        let input = r#"{"a": 1, "a": 2}"#;
+        assert!(parse_fast(input).is_some());
+        assert_eq!(parse_fast_with(input, &ParserOptions::strict()), None);
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = ParserOptions::untrusted()
+            .max_string_len(Some(3))
+            .max_values(Some(4));
+        let err = parse_with(r#"{"abc": ["abcd"]}"#, &options).unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::StringTooLong, 9));
+        assert!(parse_with(r#"[1, 2, [3]]"#, &options).is_ok());
+        let err = parse_with(r#"[1, 2, [3, 4]]"#, &options).unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::TooManyValues, 7));
+        assert_eq!(err.code(), "E0017");
+
+        let options = ParserOptions::default().max_duration(Some(Duration::ZERO));
+        let err = parse_with(r#"  {"a": 1}"#, &options).unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::Timeout, 3));
+        let options = ParserOptions::default().max_duration(Some(Duration::from_secs(60)));
+        assert!(parse_with(r#"{"a": [1]}"#, &options).is_ok());
+
+        // long arrays and objects are cut short, not only deep ones
+        let options = ParserOptions::default().max_duration(Some(Duration::from_millis(1)));
+        let array = format!("[{}0]", "0,".repeat(1_000_000));
+        let err = parse_with(&array, &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+        let members: Vec<_> = (0..1_000_000).map(|i| format!(r#""{}":0"#, i)).collect();
+        let err = parse_with(&format!("{{{}}}", members.join(",")), &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_code() {
+        let err = parse(r#"["abc"#).unwrap_err();
+        assert_eq!(err.code(), "E0003");
+        assert_eq!(parse("[] []").unwrap_err().code(), "E0011");
+        assert_eq!(ErrorKind::Io.code(), "E0013");
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(parse(r#"{"a" 1}"#).unwrap_err().offset(), 5);
+        assert_eq!(parse("[] []").unwrap_err().offset(), 3);
+
+        let err = parse("{\n  \"a\": 1,\n  \"b\" 2\n}").unwrap_err();
+        assert_eq!((err.line(), err.column()), (3, 7));
+        assert_eq!(
+            err.to_string(),
+            "expected `:` after key \"b\" at line 3, column 7, in object"
+        );
+        assert_eq!(err.key(), Some(r#""b""#));
+        let err = parse(r#"{"a\"b": [1] "c": 2}"#).unwrap_err();
+        assert_eq!((err.offset(), err.key()), (13, Some(r#""a\"b""#)));
+        assert_eq!(parse("[1 2]").unwrap_err().key(), None);
+    }
+
+    #[test]
+    fn test_source() {
+        let err = parse_bytes(b"[\"\xff\"]").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidUtf8);
+        assert_eq!(err.offset(), 2);
+        assert!(err.source().unwrap().is::<std::str::Utf8Error>());
+
+        struct Broken;
+        impl io::Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+        let err = parse_reader(Broken).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert!(err.source().unwrap().is::<io::Error>());
+        assert_eq!(err.to_string(), "I/O error: broken pipe");
+    }
+
+    #[test]
+    fn test_encodings() {
+        let replace = ParserOptions::default().invalid_utf8(InvalidUtf8::Replace);
+        let value = parse_bytes_with(b"[\"a\xff\xc3\"]", &replace).unwrap();
+        assert_eq!(
+            value,
+            JsonValue::from(vec![JsonValue::from("a\u{fffd}\u{fffd}")])
+        );
+        // positions in the decoded text
+        let err = parse_bytes_with(b"[\"\xff\" 1]", &replace).unwrap_err();
+        assert_eq!(err.offset(), 7);
+
+        let utf16 = ParserOptions::default().allow_utf16(true);
+        let expect = JsonValue::from(vec![JsonValue::from("é😀")]);
+        let text: Vec<u16> = "[\"é😀\"]".encode_utf16().collect();
+        let be: Vec<u8> = text.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        let le: Vec<u8> = text.iter().flat_map(|unit| unit.to_le_bytes()).collect();
+        for bytes in [be.clone(), le.clone(), [&[0xfe, 0xff][..], &be].concat()] {
+            assert!(parse_bytes(&bytes).is_err());
+            assert_eq!(parse_bytes_with(&bytes, &utf16).unwrap(), expect);
+        }
+        let bom = [&[0xff, 0xfe][..], &le].concat();
+        assert_eq!(parse_reader_with(&bom[..], &utf16).unwrap(), expect);
+        // an unpaired surrogate, replaced or not
+        let lone = [&le[..6], &[0x3d, 0xd8], &le[10..]].concat();
+        assert_eq!(
+            parse_bytes_with(&lone, &utf16).unwrap_err().kind(),
+            ErrorKind::InvalidUtf8
+        );
+        let value = parse_bytes_with(&lone, &utf16.invalid_utf8(InvalidUtf8::Replace)).unwrap();
+        assert_eq!(value, JsonValue::from(vec![JsonValue::from("é\u{fffd}")]));
+    }
+}
@@ -0,0 +1,773 @@
+//! A pull parser yielding the tokens of a document as events, without
+//! building it.
+
+use std::{borrow::Cow, io::BufRead, mem, sync::Arc};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::map,
+    error::{context, VerboseError},
+    Err,
+};
+
+use crate::{
+    error::ErrorKind,
+    parser::{
+        parse_bool, parse_non_finite_null, parse_number, parse_string, parse_whitespace,
+        skip_string, ParseState,
+    },
+    InvalidUtf8, LineIndex, Number, ParseError, ParserOptions, Position,
+};
+
+/// A token of a document, as yielded by [`JsonEvents`] and [`ReaderEvents`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// An object key, followed by the events of its value.
+    Key(Cow<'a, str>),
+    /// A string, borrowed from the input unless it has escapes.
+    String(Cow<'a, str>),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+impl Event<'_> {
+    pub(crate) fn into_owned(self) -> Event<'static> {
+        match self {
+            Event::StartObject => Event::StartObject,
+            Event::EndObject => Event::EndObject,
+            Event::StartArray => Event::StartArray,
+            Event::EndArray => Event::EndArray,
+            Event::Key(key) => Event::Key(Cow::Owned(key.into_owned())),
+            Event::String(s) => Event::String(Cow::Owned(s.into_owned())),
+            Event::Number(n) => Event::Number(n),
+            Event::Bool(b) => Event::Bool(b),
+            Event::Null => Event::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// what the next token may be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Root,
+    /// the first element of an array, or `]`
+    FirstElement,
+    /// the first key of an object, or `}`
+    FirstKey,
+    /// a key after a `,`
+    Key,
+    /// an element after a `,`, or a member value after a `:`
+    Value,
+    /// a `,` or the end of the innermost container
+    CommaOrEnd,
+    /// the end of the input
+    End,
+}
+
+/// The events of a document, read one at a time: `{"a": [1]}` yields
+/// `StartObject`, `Key("a")`, `StartArray`, `Number(1)`, `EndArray` and
+/// `EndObject`.
+///
+/// Only a stack of the open arrays and objects is kept, so large documents
+/// are processed without the memory their tree would take. The options of
+/// strings and numbers apply, as do `max_depth`, `max_values`,
+/// `allow_bom` and `scalar_roots`; those about whole arrays and objects, such as
+/// `duplicate_keys`, and custom literals don't.
+///
+/// The first error ends the events:
+///
+/// ```
+/// use nom_json_parser::{ErrorKind, Event, JsonEvents};
+///
+/// let mut events = JsonEvents::new(r#"{"a": [true, "x"]}"#);
+/// assert_eq!(events.next().unwrap()?, Event::StartObject);
+/// assert_eq!(events.next().unwrap()?, Event::Key("a".into()));
+/// let strings = events.filter(|event| matches!(event, Ok(Event::String(_))));
+/// assert_eq!(strings.count(), 1);
+///
+/// let events: Vec<_> = JsonEvents::new("[1 2]").collect();
+/// assert_eq!(events.len(), 3);
+/// assert_eq!(events[2].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedToken);
+/// # Ok::<(), nom_json_parser::ParseError>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonEvents<'a> {
+    options: Arc<ParserOptions>,
+    input: &'a str,
+    rest: &'a str,
+    stack: Vec<Container>,
+    expect: Expect,
+    values: usize,
+    done: bool,
+    /// strings and keys are checked without being decoded, while skipping
+    raw: bool,
+}
+
+impl<'a> JsonEvents<'a> {
+    pub fn new(input: &'a str) -> Self {
+        JsonEvents {
+            options: Arc::default(),
+            input,
+            rest: input,
+            stack: Vec::new(),
+            expect: Expect::Root,
+            values: 0,
+            done: false,
+            raw: false,
+        }
+    }
+
+    /// Options the tokens are parsed with.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = Arc::new(options);
+        self
+    }
+
+    /// Open arrays and objects.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Read past the rest of the innermost open array or object, its end
+    /// included, checking it as its events would be but without decoding
+    /// its strings and keys.
+    ///
+    /// ```
+    /// use nom_json_parser::{Event, JsonEvents};
+    ///
+    /// let mut events = JsonEvents::new(r#"[{"a": ["\u00e9", {}]}, 1]"#);
+    /// assert_eq!(events.next().unwrap()?, Event::StartArray);
+    /// assert_eq!(events.next().unwrap()?, Event::StartObject);
+    /// events.skip_container()?;
+    /// assert_eq!(events.next().unwrap()?, Event::Number(1.into()));
+    /// # Ok::<(), nom_json_parser::ParseError>(())
+    /// ```
+    pub fn skip_container(&mut self) -> Result<(), ParseError> {
+        let depth = self.depth();
+        self.raw = true;
+        let mut rst = Ok(());
+        while depth > 0 && self.depth() >= depth {
+            match self.next() {
+                Some(Ok(_)) => {}
+                Some(Err(err)) => rst = Err(err),
+                None if rst.is_ok() => rst = Err(self.error(ErrorKind::UnexpectedEof)),
+                None => break,
+            }
+        }
+        self.raw = false;
+        rst
+    }
+
+    fn step(&mut self) -> Result<Option<Event<'a>>, ParseError> {
+        if self.expect == Expect::Root && self.options.allow_bom {
+            self.rest = self.rest.strip_prefix('\u{FEFF}').unwrap_or(self.rest);
+        }
+        loop {
+            let (rest, _) = parse_whitespace::<()>(self.rest).unwrap_or((self.rest, ""));
+            self.rest = rest;
+            match self.expect {
+                Expect::Root => {
+                    return match rest.chars().next() {
+                        Some('{' | '[') => self.value().map(Some),
+                        Some(_) if self.options.scalar_roots => self.value().map(Some),
+                        Some(_) => Err(self.error(ErrorKind::UnexpectedToken)),
+                        None => Err(self.error(ErrorKind::UnexpectedEof)),
+                    }
+                }
+                Expect::FirstElement if rest.starts_with(']') => return self.end(),
+                Expect::FirstKey if rest.starts_with('}') => return self.end(),
+                Expect::FirstElement | Expect::Value => return self.value().map(Some),
+                Expect::FirstKey | Expect::Key => return self.key().map(Some),
+                Expect::CommaOrEnd => {
+                    let container = self.stack.last().copied();
+                    match (rest.chars().next(), container) {
+                        (Some(','), Some(Container::Object)) => self.expect = Expect::Key,
+                        (Some(','), _) => self.expect = Expect::Value,
+                        (Some('}'), Some(Container::Object))
+                        | (Some(']'), Some(Container::Array)) => return self.end(),
+                        (Some('"'), Some(Container::Object)) => {
+                            return Err(self.error(ErrorKind::MissingComma))
+                        }
+                        (Some(_), _) => return Err(self.error(ErrorKind::UnexpectedToken)),
+                        (None, _) => return Err(self.error(ErrorKind::UnexpectedEof)),
+                    }
+                    self.rest = &rest[1..];
+                }
+                Expect::End if rest.is_empty() => return Ok(None),
+                Expect::End => return Err(self.error(ErrorKind::TrailingCharacters)),
+            }
+        }
+    }
+
+    /// a key and its `:`
+    fn key(&mut self) -> Result<Event<'a>, ParseError> {
+        if self.rest.is_empty() {
+            return Err(self.error(ErrorKind::UnexpectedEof));
+        }
+        let st = ParseState::new(&self.options);
+        let key = if self.raw {
+            skip_string::<VerboseError<&str>>(self.rest, &st).map(|(rest, raw)| (rest, raw.into()))
+        } else {
+            parse_string::<VerboseError<&str>>(self.rest, &st)
+        };
+        let (rest, key) = key.map_err(|err| self.nom_error(err))?;
+        let (rest, _) = parse_whitespace::<()>(rest).unwrap_or((rest, ""));
+        self.rest = rest;
+        match rest.strip_prefix(':') {
+            Some(rest) => {
+                self.rest = rest;
+                self.expect = Expect::Value;
+                Ok(Event::Key(key))
+            }
+            None if rest.is_empty() => Err(self.error(ErrorKind::UnexpectedEof)),
+            None => Err(self.error(ErrorKind::MissingColon)),
+        }
+    }
+
+    /// a scalar, or the start of an array or object
+    fn value(&mut self) -> Result<Event<'a>, ParseError> {
+        self.values += 1;
+        if self.options.max_values.is_some_and(|max| self.values > max) {
+            return Err(self.error(ErrorKind::TooManyValues));
+        }
+        let container = match self.rest.chars().next() {
+            Some('{') => Some((Container::Object, Expect::FirstKey, Event::StartObject)),
+            Some('[') => Some((Container::Array, Expect::FirstElement, Event::StartArray)),
+            Some(_) => None,
+            None => return Err(self.error(ErrorKind::UnexpectedEof)),
+        };
+        if let Some((container, expect, event)) = container {
+            if self
+                .options
+                .max_depth
+                .is_some_and(|max| self.stack.len() >= max)
+            {
+                return Err(self.error(ErrorKind::DepthLimitExceeded));
+            }
+            self.stack.push(container);
+            self.expect = expect;
+            self.rest = &self.rest[1..];
+            return Ok(event);
+        }
+        let st = ParseState::new(&self.options);
+        let raw = self.raw;
+        let (rest, event) = context(
+            "value",
+            alt((
+                map(
+                    |i| {
+                        if raw {
+                            skip_string(i, &st).map(|(rest, raw)| (rest, raw.into()))
+                        } else {
+                            parse_string(i, &st)
+                        }
+                    },
+                    Event::String,
+                ),
+                map(parse_bool, Event::Bool),
+                map(|i| parse_non_finite_null(i, &st), |()| Event::Null),
+                map(|i| parse_number(i, &st), Event::Number),
+                map(tag("null"), |_| Event::Null),
+            )),
+        )(self.rest)
+        .map_err(|err| self.nom_error(err))?;
+        self.rest = rest;
+        self.expect = if self.stack.is_empty() {
+            Expect::End
+        } else {
+            Expect::CommaOrEnd
+        };
+        Ok(event)
+    }
+
+    /// the end of the innermost array or object
+    fn end(&mut self) -> Result<Option<Event<'a>>, ParseError> {
+        self.rest = &self.rest[1..];
+        let event = match self.stack.pop() {
+            Some(Container::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        };
+        self.expect = if self.stack.is_empty() {
+            Expect::End
+        } else {
+            Expect::CommaOrEnd
+        };
+        Ok(Some(event))
+    }
+
+    /// these events, continued over `text` until [`suspend`](Self::suspend)
+    fn resume<'b>(&mut self, text: &'b str) -> JsonEvents<'b> {
+        JsonEvents {
+            options: Arc::clone(&self.options),
+            input: text,
+            rest: text,
+            stack: mem::take(&mut self.stack),
+            expect: self.expect,
+            values: self.values,
+            done: self.done,
+            raw: false,
+        }
+    }
+
+    fn suspend(&mut self, events: JsonEvents<'_>) {
+        self.stack = events.stack;
+        self.expect = events.expect;
+        self.values = events.values;
+    }
+
+    /// the state before the next event, which pushes or pops at most one
+    /// container
+    fn checkpoint(&self) -> (Expect, usize, usize, Option<Container>) {
+        let depth = self.stack.len();
+        (self.expect, self.values, depth, self.stack.last().copied())
+    }
+
+    fn restore(
+        &mut self,
+        (expect, values, depth, last): (Expect, usize, usize, Option<Container>),
+    ) {
+        self.expect = expect;
+        self.values = values;
+        self.stack.truncate(depth);
+        if self.stack.len() < depth {
+            self.stack.extend(last);
+        }
+    }
+
+    /// a `kind` error at the current position
+    pub(crate) fn error(&self, kind: ErrorKind) -> ParseError {
+        let offset = self.input.len() - self.rest.len();
+        ParseError::new(kind, LineIndex::new(self.input).position(offset))
+    }
+
+    fn nom_error(&self, err: Err<VerboseError<&str>>) -> ParseError {
+        match err {
+            Err::Incomplete(_) => self.error(ErrorKind::UnexpectedEof),
+            Err::Error(err) | Err::Failure(err) => ParseError::from_verbose(self.input, &err),
+        }
+    }
+}
+
+impl<'a> Iterator for JsonEvents<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let rst = self.step();
+        self.done = !matches!(rst, Ok(Some(_)));
+        rst.transpose()
+    }
+}
+
+/// The events of a document read from a [`BufRead`], as [`JsonEvents`]
+/// yields them from a `&str`, with owned keys and strings.
+///
+/// Only the token being read is buffered, as text, so documents larger
+/// than memory are read in the memory of their longest string and deepest
+/// path. The input is UTF-8, with invalid sequences handled as
+/// `invalid_utf8` says; a failed read is an [`Io`](ErrorKind::Io) error.
+///
+/// ```
+/// use nom_json_parser::{Event, ReaderEvents};
+///
+/// let input = std::io::Cursor::new(r#"{"a": [true, "x"]}"#);
+/// let events: Vec<_> = ReaderEvents::new(input).collect::<Result<_, _>>()?;
+/// assert_eq!(events[1], Event::Key("a".into()));
+/// assert_eq!(events.len(), 7);
+/// # Ok::<(), nom_json_parser::ParseError>(())
+/// ```
+#[derive(Debug)]
+pub struct ReaderEvents<R> {
+    reader: R,
+    /// the parser between events, over no input
+    events: JsonEvents<'static>,
+    /// text read, parsed up to `pos`
+    buf: String,
+    pos: usize,
+    /// the position of `buf[pos..]` in the input
+    origin: Position,
+    /// the start of a UTF-8 sequence the last read ended in
+    pending: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: BufRead> ReaderEvents<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderEvents {
+            reader,
+            events: JsonEvents::new(""),
+            buf: String::new(),
+            pos: 0,
+            origin: Position {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
+            pending: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Options the tokens are parsed with.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.events.options = Arc::new(options);
+        self
+    }
+
+    /// Open arrays and objects.
+    pub fn depth(&self) -> usize {
+        self.events.depth()
+    }
+
+    fn step(&mut self) -> Result<Option<Event<'static>>, ParseError> {
+        loop {
+            let checkpoint = self.events.checkpoint();
+            let text = &self.buf[self.pos..];
+            let mut events = self.events.resume(text);
+            let rst = events.step();
+            let used = text.len() - events.rest.len();
+            self.events.suspend(events);
+            // a token at the end of the text read may go on, and an error
+            // near it may be the text ending early
+            let whole = self.eof
+                || match &rst {
+                    Ok(Some(_)) => used < text.len(),
+                    Ok(None) => false,
+                    Err(err) => {
+                        !matches!(
+                            err.kind(),
+                            ErrorKind::UnexpectedEof | ErrorKind::UnterminatedString
+                        ) && text.len() - err.offset().min(text.len()) > 16
+                    }
+                };
+            if whole {
+                let rst = rst
+                    .map(|event| event.map(Event::into_owned))
+                    .map_err(|err| err.starting_at(self.origin));
+                self.consume(used);
+                return rst;
+            }
+            self.events.restore(checkpoint);
+            self.read()?;
+        }
+    }
+
+    /// at least as much again as the text not yet parsed, so a long token
+    /// is parsed a bounded number of times
+    fn read(&mut self) -> Result<(), ParseError> {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        let want = self.buf.len().max(8 * 1024);
+        let mut read = 0;
+        while read < want {
+            let chunk = self.reader.fill_buf().map_err(ParseError::io)?;
+            if chunk.is_empty() {
+                self.eof = true;
+                return self.decode();
+            }
+            let len = chunk.len();
+            self.pending.extend_from_slice(chunk);
+            self.reader.consume(len);
+            read += len;
+            self.decode()?;
+        }
+        Ok(())
+    }
+
+    /// the complete UTF-8 sequences of `pending`, into `buf`
+    fn decode(&mut self) -> Result<(), ParseError> {
+        loop {
+            let err = match std::str::from_utf8(&self.pending) {
+                Ok(text) => {
+                    self.buf.push_str(text);
+                    self.pending.clear();
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+            let valid = err.valid_up_to();
+            self.buf
+                .push_str(std::str::from_utf8(&self.pending[..valid]).unwrap_or_default());
+            // the rest of a sequence is in the next read, unless there's none
+            let len = match err.error_len() {
+                Some(len) => len,
+                None if self.eof => self.pending.len() - valid,
+                None => {
+                    self.pending.drain(..valid);
+                    return Ok(());
+                }
+            };
+            if self.events.options.invalid_utf8 == InvalidUtf8::Error {
+                let position = LineIndex::new(&self.buf).position(self.buf.len());
+                return Err(
+                    ParseError::new(ErrorKind::InvalidUtf8, position).starting_at(self.origin)
+                );
+            }
+            self.buf.push(char::REPLACEMENT_CHARACTER);
+            self.pending.drain(..valid + len);
+        }
+    }
+
+    fn consume(&mut self, len: usize) {
+        let consumed = &self.buf[self.pos..self.pos + len];
+        match consumed.rfind('\n') {
+            Some(i) => {
+                self.origin.line += consumed.matches('\n').count();
+                self.origin.column = consumed[i + 1..].chars().count() + 1;
+            }
+            None => self.origin.column += consumed.chars().count(),
+        }
+        self.origin.offset += len;
+        self.pos += len;
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderEvents<R> {
+    type Item = Result<Event<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.done {
+            return None;
+        }
+        let rst = self.step();
+        self.events.done = !matches!(rst, Ok(Some(_)));
+        rst.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use crate::{ErrorKind, InvalidUtf8, JsonValue, NonFiniteNumbers, Number, ParserOptions};
+
+    use super::{Event, JsonEvents, ReaderEvents};
+
+    fn events(input: &str) -> Result<Vec<Event<'_>>, ErrorKind> {
+        JsonEvents::new(input)
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.kind())
+    }
+
+    #[test]
+    fn test_events() {
+        let input = " {\"a\\n\": [1.5, \"x\", {}, []], \"b\": {\"c\": null}, \"d\": false} ";
+        assert_eq!(
+            events(input),
+            Ok(vec![
+                Event::StartObject,
+                Event::Key("a\n".into()),
+                Event::StartArray,
+                Event::Number(Number::from_f64(1.5).unwrap()),
+                Event::String("x".into()),
+                Event::StartObject,
+                Event::EndObject,
+                Event::StartArray,
+                Event::EndArray,
+                Event::EndArray,
+                Event::Key("b".into()),
+                Event::StartObject,
+                Event::Key("c".into()),
+                Event::Null,
+                Event::EndObject,
+                Event::Key("d".into()),
+                Event::Bool(false),
+                Event::EndObject,
+            ])
+        );
+        assert_eq!(events("[]"), Ok(vec![Event::StartArray, Event::EndArray]));
+        let options = ParserOptions::default().allow_bom(true);
+        let events = JsonEvents::new("\u{FEFF}[]").options(options);
+        assert_eq!(events.count(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        for (input, kind, offset) in [
+            ("", ErrorKind::UnexpectedEof, 0),
+            ("1 2", ErrorKind::TrailingCharacters, 2),
+            ("[1,", ErrorKind::UnexpectedEof, 3),
+            ("[1,]", ErrorKind::UnexpectedToken, 3),
+            ("[1}", ErrorKind::UnexpectedToken, 2),
+            (r#"{"a" 1}"#, ErrorKind::MissingColon, 5),
+            (r#"{"a": 1 "b": 2}"#, ErrorKind::MissingComma, 8),
+            (r#"["\x"]"#, ErrorKind::InvalidEscape, 3),
+            ("[1] [", ErrorKind::TrailingCharacters, 4),
+        ] {
+            let err = JsonEvents::new(input)
+                .find_map(Result::err)
+                .unwrap_or_else(|| panic!("{:?} parsed", input));
+            assert_eq!((err.kind(), err.offset()), (kind, offset), "{:?}", input);
+        }
+        // nothing after an error
+        let mut events = JsonEvents::new("[x, 1]");
+        assert_eq!(events.next().unwrap().unwrap(), Event::StartArray);
+        assert!(events.next().unwrap().is_err());
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn test_options() {
+        let options = ParserOptions::default().max_depth(Some(2));
+        let mut events = JsonEvents::new("[[[1]]]").options(options);
+        assert_eq!(events.by_ref().take(2).count(), 2);
+        assert_eq!(events.depth(), 2);
+        let err = events.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DepthLimitExceeded);
+
+        let options = ParserOptions::default().non_finite_numbers(NonFiniteNumbers::Null);
+        let events: Vec<_> = JsonEvents::new("[NaN]")
+            .options(options)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(events, [Event::StartArray, Event::Null, Event::EndArray]);
+
+        let options = ParserOptions::default().max_string_len(Some(2));
+        let mut events = JsonEvents::new(r#"["abc"]"#).options(options);
+        let err = events.find_map(Result::err).unwrap();
+        assert_eq!(err.kind(), ErrorKind::StringTooLong);
+
+        // the same values as the DOM
+        let input = r#"[1e400, "\u00e9", -0]"#;
+        let value = crate::parse(input).unwrap();
+        let JsonValue::Array(items) = value else {
+            panic!("not an array");
+        };
+        let scalars: Vec<_> = JsonEvents::new(input)
+            .filter_map(|event| match event.unwrap() {
+                Event::Number(n) => Some(JsonValue::Number(n)),
+                Event::String(s) => Some(JsonValue::String(s.into_owned())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(scalars, items);
+
+        let events: Vec<_> = JsonEvents::new(" true ").map(Result::unwrap).collect();
+        assert_eq!(events, [Event::Bool(true)]);
+        let options = ParserOptions::default().scalar_roots(false);
+        let err = JsonEvents::new("true").options(options).next().unwrap();
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::UnexpectedToken);
+    }
+
+    #[test]
+    fn test_skip_container() {
+        let mut events = JsonEvents::new(r#"[{"a\n": ["\u00e9", "", {}]}, "b"]"#);
+        events.skip_container().unwrap();
+        assert_eq!(events.next().unwrap().unwrap(), Event::StartArray);
+        assert_eq!(events.next().unwrap().unwrap(), Event::StartObject);
+        events.skip_container().unwrap();
+        assert_eq!(events.depth(), 1);
+        assert_eq!(events.next().unwrap().unwrap(), Event::String("b".into()));
+
+        // the errors of the events
+        for input in [
+            r#"[["\x"]]"#,
+            r#"[["\ud800"]]"#,
+            r#"[{"a\u12": 1}]"#,
+            "[[\"\n\"]]",
+            r#"[["ab"#,
+            "[[1 2]]",
+            "[[",
+        ] {
+            let options = ParserOptions::default().max_string_len(Some(2));
+            let expected = JsonEvents::new(input)
+                .options(options.clone())
+                .find_map(Result::err)
+                .map(|err| (err.kind(), err.offset()));
+            let mut events = JsonEvents::new(input).options(options);
+            events.nth(1).unwrap().unwrap();
+            let err = events.skip_container().unwrap_err();
+            assert_eq!(Some((err.kind(), err.offset())), expected, "{:?}", input);
+            assert!(events.next().is_none());
+        }
+        // the length of strings once decoded
+        let options = ParserOptions::default().max_string_len(Some(2));
+        let mut events = JsonEvents::new(r#"[["\u0041\u0042"], ["abc"]]"#).options(options);
+        events.nth(1).unwrap().unwrap();
+        events.skip_container().unwrap();
+        events.next().unwrap().unwrap();
+        let err = events.skip_container().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::StringTooLong);
+    }
+
+    #[test]
+    fn test_reader() {
+        let long = format!("[\"{}\", 12345678901234567890, 1e5]", "é\\n".repeat(10_000));
+        for input in [
+            " {\"a\\n\": [1.5, \"x\", {}, []], \"b\": {\"c\": null}, \"d\": false} ",
+            "{\n  \"é\"  :\n  true\n}\n",
+            long.as_str(),
+            "123",
+            "",
+            "[1, 2",
+            "[1 2]",
+            "[\"ab",
+            "{\"a\" 1}",
+            "[1] [",
+            "[tru]",
+        ] {
+            let expected: Vec<_> = JsonEvents::new(input)
+                .map(|event| event.map_err(|err| (err.kind(), err.position())))
+                .collect();
+            for capacity in [1, 3, 8 * 1024] {
+                let reader = BufReader::with_capacity(capacity, input.as_bytes());
+                let events: Vec<_> = ReaderEvents::new(reader)
+                    .map(|event| event.map_err(|err| (err.kind(), err.position())))
+                    .collect();
+                assert_eq!(events, expected, "{:?} in reads of {}", input, capacity);
+            }
+        }
+
+        let options = ParserOptions::default().max_depth(Some(1));
+        let mut events = ReaderEvents::new(&b"[[1]]"[..]).options(options);
+        assert_eq!(events.next().unwrap().unwrap(), Event::StartArray);
+        assert_eq!(events.depth(), 1);
+        let err = events.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DepthLimitExceeded);
+        assert!(events.next().is_none());
+
+        // invalid UTF-8, and a read failing
+        let input = b"[\"a\xFF\", \"\xC3";
+        let err = ReaderEvents::new(BufReader::with_capacity(1, &input[..]))
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::InvalidUtf8, 3));
+        let options = ParserOptions::default().invalid_utf8(InvalidUtf8::Replace);
+        let events: Vec<_> = ReaderEvents::new(BufReader::with_capacity(1, &input[..]))
+            .options(options)
+            .collect();
+        assert_eq!(
+            events[1].as_ref().unwrap(),
+            &Event::String("a\u{FFFD}".into())
+        );
+        assert_eq!(
+            events[2].as_ref().unwrap_err().kind(),
+            ErrorKind::UnterminatedString
+        );
+        let reader = BufReader::new(b"[1, ".chain(Failing));
+        let err = ReaderEvents::new(reader).find_map(Result::err).unwrap();
+        assert_eq!(err.kind(), ErrorKind::Io);
+    }
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+}
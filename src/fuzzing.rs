@@ -0,0 +1,101 @@
+//! Entry points into individual parser stages for the `fuzz/` targets.
+//!
+//! Not part of the public API.
+
+use nom::error::Error;
+
+use crate::{
+    number::Number,
+    options::ParserOptions,
+    parser::{self, ParseState},
+};
+
+/// Decode a quoted JSON string at the start of `input`, returning it and the rest.
+pub fn parse_string(input: &str) -> Option<(String, &str)> {
+    let options = ParserOptions::default();
+    let st = ParseState::new(&options);
+    parser::parse_string::<Error<&str>>(input, &st)
+        .ok()
+        .map(|(rest, s)| (s.into_owned(), rest))
+}
+
+/// Parse a JSON number at the start of `input`, returning it and the rest.
+pub fn parse_number(input: &str) -> Option<(Number, &str)> {
+    let options = ParserOptions::default();
+    let st = ParseState::new(&options);
+    parser::parse_number::<Error<&str>>(input, &st)
+        .ok()
+        .map(|(rest, n)| (n, rest))
+}
+
+/// Run `data` through every entry point that takes untrusted input, which
+/// must never panic.
+pub fn entry_points(data: &[u8]) {
+    let _ = crate::parse_bytes(data);
+    let lenient = ParserOptions::default()
+        .invalid_utf8(crate::InvalidUtf8::Replace)
+        .allow_utf16(true);
+    let _ = crate::parse_bytes_with(data, &lenient);
+    let _ = crate::from_http_body("application/json", data);
+    let _ = crate::minify(data, std::io::sink());
+    crate::JsonValue::from_reader_many(data, 3).for_each(drop);
+    let pipeline = crate::Pipeline::new()
+        .pointer("/a")
+        .project(["b", "c"])
+        .filter(|record| !matches!(record, crate::JsonValue::Null));
+    pipeline.run(data).for_each(drop);
+    let _ = pipeline.run_to(data, std::io::sink(), &crate::WriteOptions::compact());
+    let reader = std::io::BufReader::with_capacity(7, data);
+    crate::ReaderEvents::new(reader)
+        .options(lenient)
+        .for_each(drop);
+    let patch = crate::Patch::new()
+        .replace("/0", 1)
+        .and_then(|patch| patch.remove("/a"))
+        .and_then(|patch| patch.add("/-", "x"));
+    if let Ok(patch) = patch {
+        let events = crate::ReaderEvents::new(data);
+        let _ = patch.rewrite(events, std::io::sink(), &crate::WriteOptions::relaxed());
+    }
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::strict(),
+        ParserOptions::untrusted(),
+    ] {
+        if let Err(err) = crate::parse_with(text, &options) {
+            let _ = err.to_string();
+            let _ = err.render(text);
+            let _ = crate::Snippet::new().max_width(8).render(&err, text);
+            let _ = err.to_json(text);
+        }
+        let _ = crate::parse_multi_with(text, &options);
+        let _ = crate::parse_fast_with(text, &options);
+        let _ = crate::parse_borrowed(text, &options);
+        let _ = crate::parse_with_transform(text, &options, |pointer, value| {
+            (!pointer.as_str().ends_with('0')).then_some(value)
+        });
+        let _ = crate::complete_partial_with(text, &options);
+        crate::parse_lines_with(text, &options).for_each(drop);
+        let events = crate::JsonEvents::new(text).options(options.clone());
+        events.for_each(drop);
+        let events = crate::JsonEvents::new(text).options(options);
+        if let Ok(subscription) = events.subscribe(["/*", "/a/*/b"]) {
+            subscription.for_each(drop);
+        }
+    }
+    let _ = crate::parse_preview(text, &ParserOptions::default());
+    let _ = crate::roundtrip(text, &crate::WriteOptions::pretty().width(Some(20)));
+    let mut decoder = crate::ChunkDecoder::new();
+    let mut mid = text.len() / 2;
+    while !text.is_char_boundary(mid) {
+        mid -= 1;
+    }
+    let (head, tail) = text.split_at(mid);
+    for chunk in [head, tail] {
+        decoder.push(chunk);
+        decoder.by_ref().for_each(drop);
+    }
+}
@@ -0,0 +1,79 @@
+//! The type of a value, without its content.
+
+use std::fmt;
+
+use crate::{
+    map::{Key, MapKind},
+    JsonValue,
+};
+
+/// Which of the six JSON types a [`JsonValue`] is, e.g. for matching on the
+/// type alone or for messages like "expected object, found array".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum JsonKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl fmt::Display for JsonKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JsonKind::Null => "null",
+            JsonKind::Bool => "bool",
+            JsonKind::Number => "number",
+            JsonKind::String => "string",
+            JsonKind::Array => "array",
+            JsonKind::Object => "object",
+        })
+    }
+}
+
+impl<S: Key, M: MapKind> JsonValue<S, M> {
+    /// The type of the value.
+    pub fn kind(&self) -> JsonKind {
+        match self {
+            JsonValue::Null => JsonKind::Null,
+            JsonValue::Bool(_) => JsonKind::Bool,
+            JsonValue::Number(_) => JsonKind::Number,
+            JsonValue::String(_) => JsonKind::String,
+            JsonValue::Array(_) => JsonKind::Array,
+            JsonValue::Object(_) => JsonKind::Object,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    use super::JsonKind;
+
+    #[test]
+    fn test_kind() {
+        let value = parse(r#"[null, true, 1.5, "s", [], {}]"#).unwrap();
+        let JsonValue::Array(items) = &value else {
+            panic!("not an array");
+        };
+        let kinds: Vec<JsonKind> = items.iter().map(JsonValue::kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                JsonKind::Null,
+                JsonKind::Bool,
+                JsonKind::Number,
+                JsonKind::String,
+                JsonKind::Array,
+                JsonKind::Object
+            ]
+        );
+        assert_eq!(value.kind(), JsonKind::Array);
+        assert_eq!(
+            format!("expected {}, found {}", JsonKind::Object, value.kind()),
+            "expected object, found array"
+        );
+    }
+}
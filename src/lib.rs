@@ -1,18 +1,356 @@
+#[macro_use]
+mod logging;
+mod body;
+mod chunks;
+mod codegen;
+mod compare;
+mod config;
+mod conformance;
+mod convert;
+mod cursor;
+#[cfg(feature = "chrono")]
+mod datetime;
+#[cfg(any(feature = "uuid", feature = "base64"))]
+mod encoded;
+mod error;
+mod events;
+#[doc(hidden)]
+pub mod fuzzing;
+mod kind;
+mod map;
+mod merge;
+mod migrate;
+mod minify;
+mod multi;
+mod number;
+mod options;
 mod parser;
+mod partial;
+mod patch;
+mod pipeline;
+mod pointer;
+mod position;
+mod rewrite;
+mod roundtrip;
+mod search;
+mod shared;
+#[cfg(feature = "stats")]
+mod stats;
+mod store;
+mod subscribe;
+mod tagged;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod walk;
+#[cfg(feature = "web")]
+pub mod web;
+mod write;
+
+use std::{borrow::Cow, cell::RefCell, io::Read};
 
 use nom::{
-    error::{convert_error, VerboseError},
-    Err,
+    error::{ContextError, VerboseError},
+    Err, IResult,
 };
-use parser::parse_root;
+use parser::{parse_root_dom, Borrowed, Dom, ParseState, Transformed};
 
+pub use body::{from_http_body, from_http_body_with};
+pub use chunks::{ChunkDecoder, Decoded};
+pub use codegen::{Field, Shape};
+pub use compare::{ChangeTracker, Changes, EqOptions};
+#[cfg(feature = "notify")]
+pub use config::ConfigWatcher;
+pub use config::{Config, ConfigError, ConfigSchema};
+pub use conformance::{run_conformance, ConformanceReport, Expected, TestFile, Verdict};
+pub use convert::{ConversionError, FromJsonError};
+pub use cursor::Cursor;
+#[cfg(feature = "base64")]
+pub use encoded::BinaryEncoding;
+pub use error::{ErrorKind, ParseError, Snippet};
+pub use events::{Event, JsonEvents, ReaderEvents};
+pub use kind::JsonKind;
+#[cfg(feature = "indexmap")]
+pub use map::IndexMapKind;
+pub use map::{BTreeMapKind, DefaultMapKind, HashMapKind, Key, Map, MapKind, Object};
+pub use merge::{merge3, merge_layers, merge_patch, merge_patch_between, Conflict, Provenance};
+pub use migrate::{JsonType, Migration, MigrationError, MigrationErrorKind, Outcome};
+pub use minify::minify;
+pub use multi::{DuplicateKeyError, MultiMap, MultiValue};
+pub use number::Number;
+#[cfg(feature = "decimal")]
+pub use options::DecimalNumbers;
+#[cfg(feature = "unicode-normalization")]
+pub use options::KeyNormalization;
+pub use options::{
+    CEscapes, Case, DuplicateKeys, EscapeDecoder, InvalidUnicodeEscapes, InvalidUtf8, KeyFilter,
+    LargeIntegers, LiteralParser, LoneSurrogates, MissingSeparators, NegativeZero,
+    NonFiniteNumbers, NumberOverflow, NumberUnderflow, ParserOptions, Profile,
+};
 pub use parser::JsonValue;
+pub use partial::{complete_partial, complete_partial_with, Partial};
+pub use patch::{Operation, Patch};
+#[cfg(feature = "rayon")]
+pub use pipeline::{par_parse_ndjson, par_parse_ndjson_with};
+pub use pipeline::{parse_lines, parse_lines_with, Batches, Lines, Pipeline, Records};
+pub use pointer::{InvalidPath, InvalidPointer, JsonPointer};
+pub use position::{LineIndex, Position};
+pub use rewrite::RewriteError;
+pub use roundtrip::{roundtrip, roundtrip_with, RoundtripReport};
+pub use search::{string_tokens, words};
+pub use shared::SharedValue;
+#[cfg(feature = "stats")]
+pub use stats::{parse_with_stats, CountingAllocator, ParseStats};
+pub use store::{DocId, JsonStore, StoredValue};
+pub use subscribe::Subscription;
+pub use tagged::{TAG_KEY, VALUE_KEY};
+pub use walk::{MapTreeError, NodeRef, Walk};
+pub use write::{WriteError, WriteOptions};
+
+pub fn parse(s: &str) -> Result<JsonValue, ParseError> {
+    parse_with(s, &ParserOptions::default())
+}
 
-pub fn parse(s: &str) -> Result<JsonValue, String> {
-    match parse_root::<VerboseError<&str>>(s) {
-        Err(Err::Incomplete(_)) | Err(Err::Failure(_)) => Err("failure".to_owned()),
-        Err(Err::Error(err)) => Err(convert_error(s, err)),
-        Ok((rest, _)) if rest.len() > 0 => Err("错误".to_owned()),
+pub fn parse_with(s: &str, options: &ParserOptions) -> Result<JsonValue, ParseError> {
+    parse_dom(s, options)
+}
+
+/// Like [`parse_with`], handing each value to `transform` with its JSON
+/// Pointer as soon as it is parsed, children before their parents; the value
+/// returned takes its place in the document. Returning `None` drops an array
+/// element or object member, or makes the root `null`.
+///
+/// This redacts, coerces or downsamples values without a second pass over
+/// the document.
+pub fn parse_with_transform<F>(
+    s: &str,
+    options: &ParserOptions,
+    transform: F,
+) -> Result<JsonValue, ParseError>
+where
+    F: FnMut(&JsonPointer, JsonValue) -> Option<JsonValue>,
+{
+    let transform = RefCell::new(transform);
+    let st = ParseState::with_transform(options, &transform);
+    parse_dom_state(s, &st).map(|Transformed(value)| value)
+}
+
+/// Like [`parse_with`], with the strings and keys that have no escapes
+/// borrowed from `s` instead of copied, saving an allocation for each in large
+/// documents; the others are decoded into owned strings.
+pub fn parse_borrowed<'a>(
+    s: &'a str,
+    options: &ParserOptions,
+) -> Result<JsonValue<Cow<'a, str>>, ParseError> {
+    parse_dom(s, options).map(|Borrowed(value)| value)
+}
+
+/// Parse keeping every object member, duplicate keys included; see
+/// [`MultiValue`].
+pub fn parse_multi(s: &str) -> Result<MultiValue, ParseError> {
+    parse_multi_with(s, &ParserOptions::default())
+}
+
+/// Like [`parse_multi`]; the `duplicate_keys` option has no effect.
+pub fn parse_multi_with(s: &str, options: &ParserOptions) -> Result<MultiValue, ParseError> {
+    parse_dom(s, options)
+}
+
+/// Every duplicate key in the document, as `(pointer, key, occurrences)`
+/// where `pointer` is the JSON Pointer of the object; see
+/// [`MultiValue::duplicate_keys`].
+pub fn find_duplicate_keys(s: &str) -> Result<Vec<(JsonPointer, String, usize)>, ParseError> {
+    Ok(parse_multi(s)?.duplicate_keys())
+}
+
+/// Parse into a [`JsonValue`] with strings of type `S` and objects from the
+/// [`MapKind`] `M`.
+pub fn parse_generic<S, M>(s: &str, options: &ParserOptions) -> Result<JsonValue<S, M>, ParseError>
+where
+    S: Key + From<String>,
+    M: MapKind,
+{
+    parse_dom(s, options)
+}
+
+/// [`nom`] parser for a document embedded in a larger grammar, with the
+/// default options; see [`parse_root_with`].
+pub fn parse_root<'a, E>(input: &'a str) -> IResult<&'a str, JsonValue, E>
+where
+    E: nom::error::ParseError<&'a str> + ContextError<&'a str>,
+{
+    parse_root_dom(input, &ParseState::new(&ParserOptions::default()))
+}
+
+/// [`nom`] parser for a document embedded in a larger grammar.
+///
+/// The parser skips leading whitespace (and a byte order mark if the options
+/// allow it), parses one value, and consumes the whitespace after it;
+/// everything else is left in the returned input, unlike [`parse_with`]
+/// which rejects trailing characters. Only an object or array is a document
+/// with [`scalar_roots(false)`](ParserOptions::scalar_roots).
+///
+/// Input that doesn't start with a value gives a recoverable
+/// [`Err::Error`], so the parser can be one branch of an `alt`. Once a
+/// document has started, any error is an [`Err::Failure`], labelled with
+/// [`ContextError`] contexts such as `"object"` and the messages of
+/// [`ErrorKind`]s, e.g. `"duplicate key"`.
+///
+/// Each call starts afresh: depth and size limits apply to one document.
+pub fn parse_root_with<'a, E>(
+    options: &ParserOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue, E>
+where
+    E: nom::error::ParseError<&'a str> + ContextError<&'a str>,
+{
+    let options = options.clone();
+    move |input| parse_root_dom(input, &ParseState::new(&options))
+}
+
+/// Like [`parse_with`], also returning the number of array elements dropped
+/// by the [`max_array_len`](ParserOptions::max_array_len) option.
+pub fn parse_preview(s: &str, options: &ParserOptions) -> Result<(JsonValue, usize), ParseError> {
+    let st = ParseState::new(options);
+    let value = parse_dom_state(s, &st)?;
+    Ok((value, st.skipped()))
+}
+
+/// The same as [`parse`].
+#[deprecated(note = "use `parse`")]
+pub fn parse_prevalidated(s: &str) -> Result<JsonValue, ParseError> {
+    parse(s)
+}
+
+/// Parse with the default options, `None` if the input isn't valid.
+///
+/// No error context is built at all, so this is the fastest way to parse
+/// when callers don't need to know why the input is invalid; [`parse`] only
+/// adds a second pass over invalid input to build the [`ParseError`].
+pub fn parse_fast(s: &str) -> Option<JsonValue> {
+    parse_fast_with(s, &ParserOptions::default())
+}
+
+/// Like [`parse_fast`], with `options`.
+pub fn parse_fast_with(s: &str, options: &ParserOptions) -> Option<JsonValue> {
+    match parse_root_dom::<_, ()>(s, &ParseState::new(options)) {
+        Ok(("", value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// A first pass keeps no error context; only invalid input is parsed again
+/// to build the [`ParseError`]. Only the pass whose result is returned logs.
+fn parse_dom<'a, V: Dom<'a>>(s: &'a str, options: &ParserOptions) -> Result<V, ParseError> {
+    let st = ParseState::new(options).holding_logs();
+    match parse_root_dom::<V, ()>(s, &st) {
+        Ok(("", value)) => {
+            debug!("parse start: {} bytes", s.len());
+            st.release_logs();
+            debug!("parse end: ok");
+            Ok(value)
+        }
+        _ => parse_dom_state(s, &st.restart()),
+    }
+}
+
+fn parse_dom_state<'a, V: Dom<'a>>(s: &'a str, st: &ParseState) -> Result<V, ParseError> {
+    debug!("parse start: {} bytes", s.len());
+    let rst = match parse_root_dom::<V, VerboseError<&str>>(s, st) {
+        Err(Err::Incomplete(_)) => Err(ParseError::new(
+            ErrorKind::UnexpectedEof,
+            LineIndex::new(s).position(s.len()),
+        )),
+        Err(Err::Error(err)) | Err(Err::Failure(err)) => Err(ParseError::from_verbose(s, &err)),
+        Ok((rest, _)) if !rest.is_empty() => {
+            trace!("trailing characters at offset {}", s.len() - rest.len());
+            Err(ParseError::new(
+                ErrorKind::TrailingCharacters,
+                LineIndex::new(s).position(s.len() - rest.len()),
+            ))
+        }
         Ok((_, rst)) => Ok(rst),
+    };
+    match &rst {
+        Ok(_) => debug!("parse end: ok"),
+        Err(err) => debug!("parse end: error: {}", err),
+    }
+    rst
+}
+
+/// Parse UTF-8 encoded bytes.
+pub fn parse_bytes(b: &[u8]) -> Result<JsonValue, ParseError> {
+    parse_bytes_with(b, &ParserOptions::default())
+}
+
+/// Like [`parse_bytes`], with `options`, which say what to do with bytes
+/// that aren't UTF-8, see [`ParserOptions::invalid_utf8`] and
+/// [`ParserOptions::allow_utf16`].
+pub fn parse_bytes_with(b: &[u8], options: &ParserOptions) -> Result<JsonValue, ParseError> {
+    parse_with(&decode(b, options)?, options)
+}
+
+/// Read `reader` to the end and parse its contents.
+pub fn parse_reader<R: Read>(reader: R) -> Result<JsonValue, ParseError> {
+    parse_reader_with(reader, &ParserOptions::default())
+}
+
+/// Like [`parse_reader`], with `options`, see [`parse_bytes_with`].
+pub fn parse_reader_with<R: Read>(
+    mut reader: R,
+    options: &ParserOptions,
+) -> Result<JsonValue, ParseError> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).map_err(ParseError::io)?;
+    parse_bytes_with(&buf, options)
+}
+
+/// `b` as text: UTF-16 if `options` allow it and it looks like it, UTF-8
+/// otherwise
+pub(crate) fn decode<'b>(b: &'b [u8], options: &ParserOptions) -> Result<Cow<'b, str>, ParseError> {
+    let big_endian = match *b {
+        _ if !options.allow_utf16 => None,
+        [0xfe, 0xff, ..] => Some(true),
+        [0xff, 0xfe, ..] => Some(false),
+        // no byte order mark, but an ASCII char first
+        [0, ch, ..] if ch != 0 => Some(true),
+        [ch, 0, ..] if ch != 0 => Some(false),
+        _ => None,
+    };
+    let Some(big_endian) = big_endian else {
+        return decode_utf8(b, options);
+    };
+    let units = b.chunks(2).map(|unit| match *unit {
+        [hi, lo] if big_endian => u16::from_be_bytes([hi, lo]),
+        [lo, hi] => u16::from_le_bytes([lo, hi]),
+        // a lone byte at the end, never valid
+        _ => 0xdc00,
+    });
+    let mut text = String::with_capacity(b.len() / 2);
+    for ch in char::decode_utf16(units) {
+        match ch {
+            Ok(ch) => text.push(ch),
+            Err(_) if options.invalid_utf8 == InvalidUtf8::Replace => {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            Err(_) => {
+                let position = LineIndex::new(&text).position(text.len());
+                return Err(ParseError::new(ErrorKind::InvalidUtf8, position));
+            }
+        }
+    }
+    if text.starts_with('\u{feff}') {
+        text.drain(..'\u{feff}'.len_utf8());
+    }
+    Ok(Cow::Owned(text))
+}
+
+/// `b` as UTF-8 text, with invalid sequences replaced if `options` say so
+pub(crate) fn decode_utf8<'b>(
+    b: &'b [u8],
+    options: &ParserOptions,
+) -> Result<Cow<'b, str>, ParseError> {
+    match std::str::from_utf8(b) {
+        Ok(text) => Ok(Cow::Borrowed(text)),
+        Err(_) if options.invalid_utf8 == InvalidUtf8::Replace => Ok(String::from_utf8_lossy(b)),
+        Err(err) => Err(ParseError::utf8(b, err)),
     }
 }
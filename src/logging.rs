@@ -0,0 +1,119 @@
+//! Internal logging macros.
+//!
+//! With the `log` feature they forward to the `log` crate under the
+//! `nom_json_parser` target; without it they compile to nothing, while still
+//! type-checking their arguments.
+//!
+//! Given a `ParseState` first, as in `warn!(st => "...")`, they log through
+//! it, so that a pass whose result may be thrown away can hold its records.
+
+macro_rules! warn {
+    ($st:expr => $($arg:tt)+) => {
+        log_with!($st, Warn, $($arg)+)
+    };
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::warn!(target: "nom_json_parser", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! debug {
+    ($st:expr => $($arg:tt)+) => {
+        log_with!($st, Debug, $($arg)+)
+    };
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::debug!(target: "nom_json_parser", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! trace {
+    ($st:expr => $($arg:tt)+) => {
+        log_with!($st, Trace, $($arg)+)
+    };
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::trace!(target: "nom_json_parser", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! log_with {
+    ($st:expr, $level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        $st.log(::log::Level::$level, format_args!($($arg)+));
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = (&$st, format_args!($($arg)+));
+        }
+    }};
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{parse, parse_with, ParserOptions};
+
+    thread_local! {
+        static RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// keeps the records of the thread logging them
+    struct Capture;
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            RECORDS.with(|records| records.borrow_mut().push(record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn logged(f: impl FnOnce()) -> Vec<String> {
+        let _ = log::set_logger(&Capture);
+        log::set_max_level(log::LevelFilter::Trace);
+        RECORDS.with(|records| records.borrow_mut().clear());
+        f();
+        RECORDS.with(|records| records.take())
+    }
+
+    #[test]
+    fn test_returned_pass_logs() {
+        let records = logged(|| drop(parse("[10000000000000000000000000000000000000001]")));
+        assert_eq!(
+            records,
+            [
+                "parse start: 43 bytes",
+                "integer 10000000000000000000000000000000000000001 rounded to 1e40",
+                "parse end: ok",
+            ]
+        );
+
+        // invalid input is parsed twice, only the second pass logs
+        let records = logged(|| drop(parse("[10000000000000000000000000000000000000001 1]")));
+        assert_eq!(records.len(), 3, "{:?}", records);
+        assert_eq!(records[0], "parse start: 45 bytes");
+        assert!(records[1].starts_with("integer"));
+        assert!(records[2].starts_with("parse end: error"));
+
+        let options = ParserOptions::default().allow_bom(true);
+        let records = logged(|| drop(parse_with("\u{FEFF}[]", &options)));
+        assert_eq!(records[1], "skipped byte order mark");
+    }
+}
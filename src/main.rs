@@ -25,6 +25,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         data
     );
 
-    println!("Error information:\n{}\n", parse(data).unwrap_err());
+    let err = parse(data).unwrap_err();
+    println!("Error information:\nerror[{}]: {}\n", err.code(), err);
     Ok(())
 }
@@ -0,0 +1,321 @@
+//! The map types backing [`JsonValue::Object`](crate::JsonValue::Object).
+//!
+//! A [`JsonValue<S, M>`](crate::JsonValue) stores its objects in
+//! `M::Map<S, JsonValue<S, M>>` for a [`MapKind`] `M`. The default kind is
+//! selected at compile time:
+//!
+//! - [`HashMapKind`] unless a feature says otherwise, iterating in arbitrary
+//!   order;
+//! - [`BTreeMapKind`] with the `btreemap` feature, iterating in key order;
+//! - [`IndexMapKind`] with the `indexmap` feature, iterating in insertion
+//!   order. It takes precedence over `btreemap`.
+//!
+//! Other kinds can be used by naming them explicitly, e.g.
+//! `JsonValue<String, BTreeMapKind>`.
+
+use std::{
+    borrow::Borrow,
+    collections::{btree_map, hash_map, BTreeMap, HashMap},
+    hash::Hash,
+};
+
+/// Bounds on the key type of [`MapKind`] maps.
+pub trait Key: Eq + Ord + Hash {}
+
+impl<K: Eq + Ord + Hash + ?Sized> Key for K {}
+
+/// The operations the parser and [`JsonValue`](crate::JsonValue) need from an
+/// object map; consuming it yields its entries.
+pub trait Object<K, V>: Default + IntoIterator<Item = (K, V)> {
+    type Iter<'m>: Iterator<Item = (&'m K, &'m V)>
+    where
+        Self: 'm,
+        K: 'm,
+        V: 'm;
+
+    /// An empty map with room for `capacity` entries, for the maps that can
+    /// reserve it.
+    fn with_capacity(capacity: usize) -> Self {
+        let _ = capacity;
+        Self::default()
+    }
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get<Q: Key + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>;
+
+    fn get_mut<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>;
+
+    /// Insert, replacing and returning the previous value for `key`.
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Remove and return the value for `key`, keeping the order of the other
+    /// entries.
+    fn remove<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>;
+
+    fn iter(&self) -> Self::Iter<'_>;
+}
+
+/// A family of maps, one for each key and value type.
+///
+/// # Member order
+///
+/// With an insertion-ordered kind, the operations that build or edit objects
+/// keep a predictable order:
+///
+/// - parsing keeps the order of the input; a duplicate key overwritten with
+///   [`DuplicateKeys::LastWins`](crate::DuplicateKeys::LastWins) keeps the
+///   position of its first occurrence;
+/// - [`Object::insert`] replaces a value in place and appends new keys, and
+///   [`Object::remove`] keeps the order of the other entries;
+/// - [`merge_patch`](crate::merge_patch) updates members in place and
+///   appends new ones in the order of the patch, and
+///   [`merge3`](crate::merge3) keeps the order of `ours` followed by the
+///   members only `theirs` added;
+/// - [`Patch::to_value`](crate::Patch::to_value) writes `op`, `from`, `path`
+///   and `value`, in that order.
+pub trait MapKind {
+    type Map<K: Key, V>: Object<K, V>;
+}
+
+/// [`HashMap`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HashMapKind;
+
+impl MapKind for HashMapKind {
+    type Map<K: Key, V> = HashMap<K, V>;
+}
+
+impl<K: Eq + Hash, V> Object<K, V> for HashMap<K, V> {
+    type Iter<'m>
+        = hash_map::Iter<'m, K, V>
+    where
+        K: 'm,
+        V: 'm;
+
+    fn with_capacity(capacity: usize) -> Self {
+        HashMap::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn get<Q: Key + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn remove<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        HashMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashMap::iter(self)
+    }
+}
+
+/// [`BTreeMap`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BTreeMapKind;
+
+impl MapKind for BTreeMapKind {
+    type Map<K: Key, V> = BTreeMap<K, V>;
+}
+
+impl<K: Ord, V> Object<K, V> for BTreeMap<K, V> {
+    type Iter<'m>
+        = btree_map::Iter<'m, K, V>
+    where
+        K: 'm,
+        V: 'm;
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn get<Q: Key + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        BTreeMap::get(self, key)
+    }
+
+    fn get_mut<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn remove<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        BTreeMap::remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeMap::iter(self)
+    }
+}
+
+/// [`IndexMap`](indexmap::IndexMap)s.
+#[cfg(feature = "indexmap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IndexMapKind;
+
+#[cfg(feature = "indexmap")]
+impl MapKind for IndexMapKind {
+    type Map<K: Key, V> = indexmap::IndexMap<K, V>;
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash, V> Object<K, V> for indexmap::IndexMap<K, V> {
+    type Iter<'m>
+        = indexmap::map::Iter<'m, K, V>
+    where
+        K: 'm,
+        V: 'm;
+
+    fn with_capacity(capacity: usize) -> Self {
+        indexmap::IndexMap::with_capacity(capacity)
+    }
+
+    fn len(&self) -> usize {
+        indexmap::IndexMap::len(self)
+    }
+
+    fn get<Q: Key + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        indexmap::IndexMap::get(self, key)
+    }
+
+    fn get_mut<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        indexmap::IndexMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        indexmap::IndexMap::insert(self, key, value)
+    }
+
+    fn remove<Q: Key + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        indexmap::IndexMap::shift_remove(self, key)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        indexmap::IndexMap::iter(self)
+    }
+}
+
+#[cfg(not(any(feature = "btreemap", feature = "indexmap")))]
+pub type DefaultMapKind = HashMapKind;
+
+#[cfg(all(feature = "btreemap", not(feature = "indexmap")))]
+pub type DefaultMapKind = BTreeMapKind;
+
+#[cfg(feature = "indexmap")]
+pub type DefaultMapKind = IndexMapKind;
+
+/// The map of the default [`MapKind`].
+pub type Map<K, V> = <DefaultMapKind as MapKind>::Map<K, V>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_generic, JsonValue, ParserOptions};
+
+    use super::{BTreeMapKind, HashMapKind};
+
+    #[cfg(any(feature = "btreemap", feature = "indexmap"))]
+    fn keys(input: &str) -> Vec<String> {
+        match crate::parse(input) {
+            Ok(JsonValue::Object(map)) => map.into_keys().collect(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_explicit_kind() {
+        let value = parse_generic::<String, BTreeMapKind>(
+            r#"{"b": 1, "c": [{"e": 2, "d": 3}], "a": 4}"#,
+            &ParserOptions::default(),
+        )
+        .unwrap();
+        let keys = match &value {
+            JsonValue::Object(map) => map.keys().collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(keys, ["a", "b", "c"]);
+        assert_eq!(value.clone(), value);
+        assert_eq!(
+            format!("{:?}", value),
+            r#"Object({"a": Number(4), "b": Number(1), "c": Array([Object({"d": Number(3), "e": Number(2)})])})"#
+        );
+    }
+
+    #[test]
+    fn test_string_type() {
+        let value = parse_generic::<std::rc::Rc<str>, HashMapKind>(
+            r#"{"a": ["b"]}"#,
+            &ParserOptions::default(),
+        )
+        .unwrap();
+        let mut map = std::collections::HashMap::new();
+        map.insert(
+            "a".into(),
+            JsonValue::Array(vec![JsonValue::String("b".into())]),
+        );
+        assert_eq!(value, JsonValue::Object(map));
+    }
+
+    #[cfg(all(feature = "btreemap", not(feature = "indexmap")))]
+    #[test]
+    fn test_key_order() {
+        assert_eq!(keys(r#"{"b": 1, "c": 2, "a": 3}"#), ["a", "b", "c"]);
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_insertion_order() {
+        assert_eq!(keys(r#"{"b": 1, "c": 2, "a": 3}"#), ["b", "c", "a"]);
+        assert_eq!(keys(r#"{"b": 1, "a": 2, "b": 3}"#), ["b", "a"]);
+    }
+}
@@ -0,0 +1,408 @@
+//! Merging documents: JSON Merge Patch (RFC 7386) and three-way merges.
+//!
+//! A merge patch is a partial document: its members replace those of the
+//! target, recursively for objects, and its `null` members delete them.
+
+use std::{borrow::Borrow, collections::BTreeMap, fmt};
+
+use crate::{
+    map::{DefaultMapKind, Key, MapKind, Object},
+    JsonPointer, JsonValue,
+};
+
+/// Apply the merge patch `patch` to `target`.
+///
+/// Updated members keep their place and new ones are appended in the order
+/// of the patch, for maps that have an order; see [`MapKind`].
+pub fn merge_patch<S: Key + Clone, M: MapKind>(
+    target: &mut JsonValue<S, M>,
+    patch: &JsonValue<S, M>,
+) {
+    let patch = match patch {
+        JsonValue::Object(patch) => patch,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !matches!(target, JsonValue::Object(_)) {
+        *target = JsonValue::Object(M::Map::default());
+    }
+    if let JsonValue::Object(map) = target {
+        for (key, value) in patch.iter() {
+            match (value, map.get_mut(key)) {
+                (JsonValue::Null, _) => {
+                    map.remove(key);
+                }
+                (_, Some(member)) => merge_patch(member, value),
+                (_, None) => {
+                    let mut member = JsonValue::Null;
+                    merge_patch(&mut member, value);
+                    map.insert(key.clone(), member);
+                }
+            }
+        }
+    }
+}
+
+/// The smallest merge patch turning `from` into `to`.
+///
+/// Merge patches can't set a member to `null`, so `null` members of objects
+/// in `to` that aren't in `from` are lost when the patch is applied.
+pub fn merge_patch_between<S: Key + Clone, M: MapKind>(
+    from: &JsonValue<S, M>,
+    to: &JsonValue<S, M>,
+) -> JsonValue<S, M> {
+    let (from, to) = match (from, to) {
+        (JsonValue::Object(from), JsonValue::Object(to)) => (from, to),
+        _ => return to.clone(),
+    };
+    let mut patch = M::Map::default();
+    for (key, _) in from.iter() {
+        if to.get(key).is_none() {
+            patch.insert(key.clone(), JsonValue::Null);
+        }
+    }
+    for (key, value) in to.iter() {
+        match from.get(key) {
+            Some(old) if old == value => {}
+            Some(old) => {
+                patch.insert(key.clone(), merge_patch_between(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    JsonValue::Object(patch)
+}
+
+/// The merge patches `layers` applied in turn to `null`, e.g. the default,
+/// system and user configuration files, with the [`Provenance`] of every
+/// value of the result.
+pub fn merge_layers<'v, S, M, I>(layers: I) -> (JsonValue<S, M>, Provenance)
+where
+    S: Key + Clone + Borrow<str> + 'v,
+    M: MapKind + 'v,
+    I: IntoIterator<Item = &'v JsonValue<S, M>>,
+{
+    let mut merged = JsonValue::Null;
+    let mut provenance = Provenance::default();
+    for (layer, patch) in layers.into_iter().enumerate() {
+        merge_patch(&mut merged, patch);
+        provenance.record(patch, &merged, &mut JsonPointer::root(), layer);
+    }
+    (merged, provenance)
+}
+
+/// Which layer of a [`merge_layers`] set each leaf of the result: each
+/// value that isn't an object with members.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance(BTreeMap<JsonPointer, usize>);
+
+impl Provenance {
+    /// Index of the layer that set the leaf at `pointer`.
+    pub fn source(&self, pointer: &str) -> Option<usize> {
+        self.0.get(&JsonPointer::parse(pointer).ok()?).copied()
+    }
+
+    /// Every leaf with the index of its layer, in pointer order.
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonPointer, usize)> {
+        self.0.iter().map(|(pointer, &layer)| (pointer, layer))
+    }
+
+    /// The leaves at or inside `pointer`, e.g. the settings of a section.
+    pub fn within<'p>(
+        &'p self,
+        pointer: &'p JsonPointer,
+    ) -> impl Iterator<Item = (&'p JsonPointer, usize)> {
+        self.iter().filter(move |(leaf, _)| leaf.is_within(pointer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// update the leaves for `patch`, at `pointer` in the layer, now merged
+    /// into `merged`, the value at `pointer` of the result
+    fn record<S: Key + Borrow<str>, M: MapKind>(
+        &mut self,
+        patch: &JsonValue<S, M>,
+        merged: &JsonValue<S, M>,
+        pointer: &mut JsonPointer,
+        layer: usize,
+    ) {
+        match (patch, merged) {
+            (JsonValue::Object(patch), JsonValue::Object(merged)) => {
+                if merged.len() == 0 {
+                    self.0.insert(pointer.clone(), layer);
+                    return;
+                }
+                // a leaf replaced by an object
+                self.0.remove(pointer);
+                let len = pointer.len();
+                for (key, value) in patch.iter() {
+                    pointer.push(key.borrow());
+                    match merged.get(key.borrow()) {
+                        Some(merged) => self.record(value, merged, pointer, layer),
+                        None => self.0.retain(|leaf, _| !leaf.is_within(pointer)),
+                    }
+                    pointer.truncate(len);
+                }
+            }
+            _ => {
+                self.0.retain(|leaf, _| !leaf.is_within(pointer));
+                self.0.insert(pointer.clone(), layer);
+            }
+        }
+    }
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// Object members are merged recursively; other values, arrays included,
+/// are replaced as a whole. A value changed differently on both sides is a
+/// [`Conflict`], and the merge fails with every conflict.
+///
+/// Merged objects have the members of `ours` in their order, followed by
+/// those only `theirs` added.
+pub fn merge3<S: Key + Clone + Borrow<str>, M: MapKind>(
+    base: &JsonValue<S, M>,
+    ours: &JsonValue<S, M>,
+    theirs: &JsonValue<S, M>,
+) -> Result<JsonValue<S, M>, Vec<Conflict<S, M>>> {
+    let mut conflicts = Vec::new();
+    let merged = merge_members(
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &mut JsonPointer::root(),
+        &mut conflicts,
+    );
+    if conflicts.is_empty() {
+        Ok(merged.unwrap_or(JsonValue::Null))
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// `None` for a missing member.
+fn merge_members<S: Key + Clone + Borrow<str>, M: MapKind>(
+    base: Option<&JsonValue<S, M>>,
+    ours: Option<&JsonValue<S, M>>,
+    theirs: Option<&JsonValue<S, M>>,
+    pointer: &mut JsonPointer,
+    conflicts: &mut Vec<Conflict<S, M>>,
+) -> Option<JsonValue<S, M>> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+    match (ours, theirs) {
+        (Some(JsonValue::Object(ours)), Some(JsonValue::Object(theirs))) => {
+            let base = match base {
+                Some(JsonValue::Object(base)) => Some(base),
+                _ => None,
+            };
+            let mut merged = M::Map::default();
+            let len = pointer.len();
+            let keys = ours.iter().map(|(key, _)| key).chain(
+                theirs
+                    .iter()
+                    .map(|(key, _)| key)
+                    .filter(|key| ours.get::<S>(key).is_none()),
+            );
+            for key in keys {
+                pointer.push(key.borrow());
+                let member = merge_members(
+                    base.and_then(|base| base.get::<S>(key)),
+                    ours.get::<S>(key),
+                    theirs.get::<S>(key),
+                    pointer,
+                    conflicts,
+                );
+                pointer.truncate(len);
+                if let Some(member) = member {
+                    merged.insert(key.clone(), member);
+                }
+            }
+            Some(JsonValue::Object(merged))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                pointer: pointer.clone(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+/// A value changed differently by both sides of a [`merge3`].
+///
+/// `None` stands for a member that is missing on that side.
+pub struct Conflict<S: Key = String, M: MapKind = DefaultMapKind> {
+    /// JSON Pointer of the value.
+    pub pointer: JsonPointer,
+    pub base: Option<JsonValue<S, M>>,
+    pub ours: Option<JsonValue<S, M>>,
+    pub theirs: Option<JsonValue<S, M>>,
+}
+
+impl<S: Key + fmt::Debug, M: MapKind> fmt::Debug for Conflict<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Conflict")
+            .field("pointer", &self.pointer)
+            .field("base", &self.base)
+            .field("ours", &self.ours)
+            .field("theirs", &self.theirs)
+            .finish()
+    }
+}
+
+impl<S: Key, M: MapKind> PartialEq for Conflict<S, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pointer == other.pointer
+            && self.base == other.base
+            && self.ours == other.ours
+            && self.theirs == other.theirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    use super::{merge3, merge_layers, merge_patch, merge_patch_between};
+
+    #[test]
+    fn test_merge_patch() {
+        // RFC 7386, section 3
+        let mut target = parse(
+            r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"},
+                "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+        )
+        .unwrap();
+        let patch = parse(
+            r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890",
+                "author": {"familyName": null}, "tags": ["example"]}"#,
+        )
+        .unwrap();
+        merge_patch(&mut target, &patch);
+        let expect = parse(
+            r#"{"title": "Hello!", "author": {"givenName": "John"}, "tags": ["example"],
+                "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890"}"#,
+        )
+        .unwrap();
+        assert_eq!(target, expect);
+    }
+
+    #[test]
+    fn test_merge_patch_between() {
+        let from = parse(r#"{"a": 1, "b": {"c": [1], "d": true}, "e": {}}"#).unwrap();
+        let to = parse(r#"{"a": 1, "b": {"c": [2], "d": true}, "f": {"g": {}}}"#).unwrap();
+        let patch = merge_patch_between(&from, &to);
+        assert_eq!(
+            patch,
+            parse(r#"{"b": {"c": [2]}, "e": null, "f": {"g": {}}}"#).unwrap()
+        );
+        let mut patched = from.clone();
+        merge_patch(&mut patched, &patch);
+        assert_eq!(patched, to);
+
+        assert_eq!(merge_patch_between(&from, &from), parse("{}").unwrap());
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_member_order() {
+        let keys = |value: &JsonValue| match value {
+            JsonValue::Object(map) => map.keys().cloned().collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        let mut target = parse(r#"{"c": 1, "a": 2, "b": 3, "d": 4}"#).unwrap();
+        merge_patch(
+            &mut target,
+            &parse(r#"{"z": 0, "a": 5, "b": null, "y": 0}"#).unwrap(),
+        );
+        assert_eq!(keys(&target), ["c", "a", "d", "z", "y"]);
+
+        let base = parse(r#"{"a": 1, "b": 2}"#).unwrap();
+        let ours = parse(r#"{"b": 2, "x": 0, "a": 1}"#).unwrap();
+        let theirs = parse(r#"{"y": 0, "a": 3, "b": 2}"#).unwrap();
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(keys(&merged), ["b", "x", "a", "y"]);
+    }
+
+    #[test]
+    fn test_merge3() {
+        let base = parse(r#"{"a": 1, "b": {"c": 1, "d": 1}, "e": [1], "f": 1}"#).unwrap();
+        let ours = parse(r#"{"a": 2, "b": {"c": 2, "d": 1}, "e": [1], "f": 1, "g": 1}"#).unwrap();
+        let theirs = parse(r#"{"a": 1, "b": {"c": 1, "d": 2}, "e": [2]}"#).unwrap();
+        assert_eq!(
+            merge3(&base, &ours, &theirs).unwrap(),
+            parse(r#"{"a": 2, "b": {"c": 2, "d": 2}, "e": [2], "g": 1}"#).unwrap()
+        );
+
+        let theirs = parse(r#"{"a": 3, "b": {"c": 2, "d": 1}, "e": [1], "f": 1}"#).unwrap();
+        let ours = parse(r#"{"a": 2, "b": {"c": 2, "d": 1}, "e": [1]}"#).unwrap();
+        let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].pointer, "/a");
+        assert_eq!(conflicts[0].theirs, Some(JsonValue::Number(3u8.into())));
+
+        let theirs = parse(r#"{"a/b": {"c": 1}}"#).unwrap();
+        let ours = parse(r#"{"a/b": 2}"#).unwrap();
+        let conflicts = merge3(&parse("{}").unwrap(), &ours, &theirs).unwrap_err();
+        assert_eq!(conflicts[0].pointer, "/a~1b");
+        assert_eq!(conflicts[0].base, None);
+    }
+
+    #[test]
+    fn test_merge_layers() {
+        let layers = [
+            r#"{"server": {"host": "localhost", "port": 80, "tls": {"cert": "a"}}, "log": []}"#,
+            r#"{"server": {"port": 8080, "tls": null}, "log": {"level": "info"}}"#,
+            r#"{"server": {"host": "example.com"}, "log": {}, "extra": {}}"#,
+        ]
+        .map(|layer| parse(layer).unwrap());
+        let (merged, provenance) = merge_layers(&layers);
+        let expect = r#"{"server": {"host": "example.com", "port": 8080},
+            "log": {"level": "info"}, "extra": {}}"#;
+        assert_eq!(merged, parse(expect).unwrap());
+        let leaves: Vec<_> = provenance
+            .iter()
+            .map(|(pointer, layer)| (pointer.as_str(), layer))
+            .collect();
+        assert_eq!(
+            leaves,
+            [
+                ("/extra", 2),
+                ("/log/level", 1),
+                ("/server/host", 2),
+                ("/server/port", 1)
+            ]
+        );
+        assert_eq!(provenance.source("/server/port"), Some(1));
+        assert_eq!(provenance.source("/server"), None);
+        let server = "/server".parse().unwrap();
+        assert_eq!(provenance.within(&server).count(), 2);
+
+        // a layer replacing the whole document
+        let layers = [parse(r#"{"a": 1}"#).unwrap(), parse("[1]").unwrap()];
+        let (merged, provenance) = merge_layers(&layers);
+        assert_eq!(merged, parse("[1]").unwrap());
+        assert_eq!(
+            provenance.iter().collect::<Vec<_>>(),
+            [(&Default::default(), 1)]
+        );
+    }
+}
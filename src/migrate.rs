@@ -0,0 +1,336 @@
+//! Evolving stored documents: renaming members, changing their type and
+//! filling in defaults.
+
+use std::{error::Error, fmt};
+
+use crate::{
+    map::{Map, Object},
+    parser::{parse_number, ParseState},
+    InvalidPointer, JsonPointer, JsonValue, ParserOptions,
+};
+
+/// A scalar JSON type, the target of [`Migration::convert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonType {
+    /// Numbers and booleans are written out, e.g. `1.5` as `"1.5"`.
+    String,
+    /// Strings holding a JSON number are parsed.
+    Number,
+    /// The strings `"true"` and `"false"` are parsed.
+    Bool,
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JsonType::String => "string",
+            JsonType::Number => "number",
+            JsonType::Bool => "bool",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Rename {
+        from: JsonPointer,
+        to: JsonPointer,
+    },
+    Convert {
+        pointer: JsonPointer,
+        to: JsonType,
+    },
+    Default {
+        pointer: JsonPointer,
+        value: JsonValue,
+    },
+}
+
+/// Steps applied in turn to each document, built with their pointers
+/// checked like those of a [`Patch`](crate::Patch), e.g.
+/// `Migration::new().rename("/user", "/owner")?.insert_default("/version", 2)?`.
+///
+/// Steps about a value a document doesn't have are skipped for that
+/// document, so a migration can run again over documents it already
+/// migrated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Migration {
+    steps: Vec<Step>,
+}
+
+/// What [`Migration::run`] did to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Changed,
+    Unchanged,
+    /// The document was left as it was.
+    Failed(MigrationError),
+}
+
+impl Migration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the object member at `from` to `to`, in an object that must
+    /// exist and not have a member there already.
+    pub fn rename(self, from: &str, to: &str) -> Result<Self, InvalidPointer> {
+        let (from, to) = (member(from)?, member(to)?);
+        Ok(self.push(Step::Rename { from, to }))
+    }
+
+    /// Convert the value at `pointer` to the type `to`; see [`JsonType`] for
+    /// what converts.
+    pub fn convert(self, pointer: &str, to: JsonType) -> Result<Self, InvalidPointer> {
+        let pointer = member(pointer)?;
+        Ok(self.push(Step::Convert { pointer, to }))
+    }
+
+    /// Insert `value` at `pointer` if there's nothing there, in an object that
+    /// must exist.
+    pub fn insert_default(
+        self,
+        pointer: &str,
+        value: impl Into<JsonValue>,
+    ) -> Result<Self, InvalidPointer> {
+        let pointer = member(pointer)?;
+        Ok(self.push(Step::Default {
+            pointer,
+            value: value.into(),
+        }))
+    }
+
+    /// Migrate one document, returning whether it changed.
+    ///
+    /// On error the document is left as it was.
+    pub fn apply(&self, doc: &mut JsonValue) -> Result<bool, MigrationError> {
+        let mut migrated = doc.clone();
+        for (i, step) in self.steps.iter().enumerate() {
+            apply_step(&mut migrated, step).map_err(|kind| MigrationError {
+                step: i,
+                pointer: match step {
+                    Step::Rename { to, .. } => to.clone(),
+                    Step::Convert { pointer, .. } | Step::Default { pointer, .. } => {
+                        pointer.clone()
+                    }
+                },
+                kind,
+            })?;
+        }
+        let changed = migrated != *doc;
+        *doc = migrated;
+        Ok(changed)
+    }
+
+    /// Migrate every document, returning the outcome of each in order.
+    pub fn run<'d, I>(&self, docs: I) -> Vec<Outcome>
+    where
+        I: IntoIterator<Item = &'d mut JsonValue>,
+    {
+        docs.into_iter()
+            .map(|doc| match self.apply(doc) {
+                Ok(true) => Outcome::Changed,
+                Ok(false) => Outcome::Unchanged,
+                Err(err) => Outcome::Failed(err),
+            })
+            .collect()
+    }
+
+    fn push(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+}
+
+/// a pointer to an object member, which excludes the root
+fn member(pointer: &str) -> Result<JsonPointer, InvalidPointer> {
+    match JsonPointer::parse(pointer) {
+        Ok(pointer) if !pointer.is_root() => Ok(pointer),
+        _ => Err(InvalidPointer::new(pointer)),
+    }
+}
+
+fn apply_step(doc: &mut JsonValue, step: &Step) -> Result<(), MigrationErrorKind> {
+    match step {
+        Step::Rename { from, to } => {
+            let value = match parent(doc, from) {
+                Ok((map, key)) => match Object::remove(map, key.as_str()) {
+                    Some(value) => value,
+                    None => return Ok(()),
+                },
+                Err(_) => return Ok(()),
+            };
+            let (map, key) = parent(doc, to)?;
+            if map.get(key.as_str()).is_some() {
+                return Err(MigrationErrorKind::Exists);
+            }
+            map.insert(key, value);
+        }
+        Step::Convert { pointer, to } => {
+            if let Some(value) = doc.pointer_mut(pointer.as_str()) {
+                *value = convert(value, *to).ok_or(MigrationErrorKind::Unconvertible(*to))?;
+            }
+        }
+        Step::Default { pointer, value } => {
+            let (map, key) = parent(doc, pointer)?;
+            if map.get(key.as_str()).is_none() {
+                map.insert(key, value.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the object holding the member at `pointer`, and the member's key
+/// (called through [`Object`], as the maps' own `remove` differ)
+fn parent<'d>(
+    doc: &'d mut JsonValue,
+    pointer: &JsonPointer,
+) -> Result<(&'d mut Map<String, JsonValue>, String), MigrationErrorKind> {
+    let (parent, key) = pointer
+        .parent()
+        .zip(pointer.last())
+        .ok_or(MigrationErrorKind::NoObject)?;
+    match doc.pointer_mut(parent.as_str()) {
+        Some(JsonValue::Object(map)) => Ok((map, key)),
+        _ => Err(MigrationErrorKind::NoObject),
+    }
+}
+
+fn convert(value: &JsonValue, to: JsonType) -> Option<JsonValue> {
+    match (value, to) {
+        (JsonValue::String(_), JsonType::String)
+        | (JsonValue::Number(_), JsonType::Number)
+        | (JsonValue::Bool(_), JsonType::Bool) => Some(value.clone()),
+        (JsonValue::Number(n), JsonType::String) => Some(JsonValue::String(n.to_string())),
+        (JsonValue::Bool(b), JsonType::String) => Some(JsonValue::String(b.to_string())),
+        (JsonValue::String(s), JsonType::Number) => {
+            let options = ParserOptions::default();
+            match parse_number::<nom::error::Error<&str>>(s, &ParseState::new(&options)) {
+                Ok(("", n)) => Some(JsonValue::Number(n)),
+                _ => None,
+            }
+        }
+        (JsonValue::String(s), JsonType::Bool) => match s.as_str() {
+            "true" => Some(JsonValue::Bool(true)),
+            "false" => Some(JsonValue::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Why a [`Migration`] step failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MigrationErrorKind {
+    /// The member a step inserts is already there.
+    Exists,
+    /// The parent of the member a step inserts isn't an object.
+    NoObject,
+    /// The value can't be converted to the type.
+    Unconvertible(JsonType),
+}
+
+/// Error returned by [`Migration::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationError {
+    step: usize,
+    pointer: JsonPointer,
+    kind: MigrationErrorKind,
+}
+
+impl MigrationError {
+    /// Index of the failed step, in the order they were added.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// JSON Pointer of the value the step failed on.
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    pub fn kind(&self) -> MigrationErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {}: ", self.step)?;
+        match self.kind {
+            MigrationErrorKind::Exists => write!(f, "{:?} already exists", self.pointer.as_str()),
+            MigrationErrorKind::NoObject => {
+                write!(f, "no object to hold {:?}", self.pointer.as_str())
+            }
+            MigrationErrorKind::Unconvertible(to) => {
+                write!(f, "can't convert {:?} to {}", self.pointer.as_str(), to)
+            }
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::{JsonType, Migration, MigrationErrorKind, Outcome};
+
+    #[test]
+    fn test_migration() {
+        let migration = Migration::new()
+            .rename("/user", "/owner")
+            .and_then(|m| m.rename("/meta/ts", "/meta/timestamp"))
+            .and_then(|m| m.convert("/owner/id", JsonType::String))
+            .and_then(|m| m.convert("/meta/timestamp", JsonType::Number))
+            .and_then(|m| m.insert_default("/version", 2))
+            .unwrap();
+        let mut docs = [
+            r#"{"user": {"id": 7}, "meta": {"ts": "1700000000"}}"#,
+            r#"{"owner": {"id": "7"}, "meta": {}, "version": 2}"#,
+            r#"{"user": {"id": 1}, "meta": {"ts": "yesterday"}}"#,
+            r#"{"user": 1, "owner": 2}"#,
+            r#"[{"user": 1}]"#,
+        ]
+        .map(|doc| parse(doc).unwrap());
+        let original = docs.clone();
+        let outcomes = migration.run(&mut docs);
+
+        assert_eq!(outcomes[0], Outcome::Changed);
+        let expect = r#"{"owner": {"id": "7"}, "meta": {"timestamp": 1700000000}, "version": 2}"#;
+        assert_eq!(docs[0], parse(expect).unwrap());
+        assert_eq!(outcomes[1], Outcome::Unchanged);
+        // failed documents are left as they were
+        assert_eq!(docs[2..], original[2..]);
+        let errors: Vec<_> = outcomes[2..]
+            .iter()
+            .map(|outcome| match outcome {
+                Outcome::Failed(err) => (err.step(), err.pointer().as_str(), err.kind()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    3,
+                    "/meta/timestamp",
+                    MigrationErrorKind::Unconvertible(JsonType::Number)
+                ),
+                (0, "/owner", MigrationErrorKind::Exists),
+                (4, "/version", MigrationErrorKind::NoObject),
+            ]
+        );
+        match &outcomes[2] {
+            Outcome::Failed(err) => assert_eq!(
+                err.to_string(),
+                r#"step 3: can't convert "/meta/timestamp" to number"#
+            ),
+            _ => unreachable!(),
+        }
+        assert!(Migration::new().rename("", "/a").is_err());
+    }
+}
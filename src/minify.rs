@@ -0,0 +1,441 @@
+//! Removing the whitespace of a document as it's read, without building it.
+
+use std::io::{self, BufRead, Write};
+
+/// Copy the document of `reader` to `writer` without the whitespace between
+/// its tokens, returning the number of bytes written.
+///
+/// The input is tokenized as it's read, one buffer of `reader` at a time,
+/// so memory use only grows with the nesting of the document. Strings are
+/// copied as they are, escapes included. Input that isn't a single JSON
+/// document, strings in UTF-8, is an [`InvalidData`](io::ErrorKind::InvalidData)
+/// error, or [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if it ends
+/// too early; the output written before the error was found is left in
+/// `writer`. A byte order mark at the start is copied. Writes are as small
+/// as the tokens, so `writer` should be buffered.
+///
+/// ```
+/// use nom_json_parser::minify;
+///
+/// let input = "{\n  \"a b\": [1, true],\n  \"c\": \"\\u00e9\"\n}\n";
+/// let mut out = Vec::new();
+/// let written = minify(input.as_bytes(), &mut out)?;
+/// assert_eq!(out, br#"{"a b":[1,true],"c":"\u00e9"}"#);
+/// assert_eq!(written, 29);
+///
+/// let err = minify(&b"[1 2]"[..], Vec::new()).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn minify<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> io::Result<u64> {
+    let mut written = 0;
+    let mut tokenizer = Tokenizer::new();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        // start of the bytes to copy
+        let mut start = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            if !tokenizer.byte(b)? {
+                writer.write_all(&buf[start..i])?;
+                written += i - start;
+                start = i + 1;
+            }
+        }
+        writer.write_all(&buf[start..])?;
+        written += buf.len() - start;
+        let len = buf.len();
+        reader.consume(len);
+    }
+    tokenizer.end()?;
+    Ok(written as u64)
+}
+
+/// What the next token may be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// the root, an element after a `,`, or a member value after a `:`
+    Value,
+    /// the first element of an array, or `]`
+    FirstElement,
+    /// the first key of an object, or `}`
+    FirstKey,
+    /// a key after a `,`
+    Key,
+    Colon,
+    /// a `,` or the end of the innermost container
+    CommaOrEnd,
+    /// the end of the input
+    End,
+}
+
+/// The token being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// between tokens
+    None,
+    String {
+        key: bool,
+        state: InString,
+    },
+    Number(Num),
+    /// the rest of `true`, `false` or `null`
+    Literal(&'static [u8]),
+    /// the rest of a byte order mark
+    Bom(&'static [u8]),
+}
+
+/// Where in a string the tokenizer is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InString {
+    Plain,
+    /// after a `\`
+    Escape,
+    /// the hex digits of a `\u` escape left
+    Hex(u8),
+    /// the continuation bytes of a UTF-8 sequence left, the first of them
+    /// in `lo..=hi`
+    Utf8 {
+        left: u8,
+        lo: u8,
+        hi: u8,
+    },
+}
+
+/// Where in a number the tokenizer is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Num {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl Num {
+    fn next(self, b: u8) -> Option<Num> {
+        Some(match (self, b) {
+            (Num::Minus, b'0') => Num::Zero,
+            (Num::Minus | Num::Int, b'0'..=b'9') => Num::Int,
+            (Num::Zero | Num::Int, b'.') => Num::Dot,
+            (Num::Dot | Num::Fraction, b'0'..=b'9') => Num::Fraction,
+            (Num::Zero | Num::Int | Num::Fraction, b'e' | b'E') => Num::Exponent,
+            (Num::Exponent, b'+' | b'-') => Num::ExponentSign,
+            (Num::Exponent | Num::ExponentSign | Num::ExponentDigits, b'0'..=b'9') => {
+                Num::ExponentDigits
+            }
+            _ => return None,
+        })
+    }
+
+    /// whether the number may end here
+    fn complete(self) -> bool {
+        matches!(
+            self,
+            Num::Zero | Num::Int | Num::Fraction | Num::ExponentDigits
+        )
+    }
+}
+
+/// Checks the bytes of a document one at a time.
+#[derive(Debug)]
+struct Tokenizer {
+    /// the open containers, `true` for an object
+    stack: Vec<bool>,
+    expect: Expect,
+    token: Token,
+    /// bytes read
+    offset: u64,
+}
+
+impl Tokenizer {
+    fn new() -> Self {
+        Tokenizer {
+            stack: Vec::new(),
+            expect: Expect::Value,
+            token: Token::None,
+            offset: 0,
+        }
+    }
+
+    /// Read `b`: whether it's copied, or whitespace between tokens.
+    fn byte(&mut self, b: u8) -> io::Result<bool> {
+        self.offset += 1;
+        match self.token {
+            Token::None => {}
+            Token::String { key, state } => {
+                let state = match (state, b) {
+                    (InString::Plain, b'"') => {
+                        self.token = Token::None;
+                        self.expect = if key {
+                            Expect::Colon
+                        } else {
+                            self.after_value()
+                        };
+                        return Ok(true);
+                    }
+                    (InString::Plain, b'\\') => InString::Escape,
+                    (InString::Plain, 0x20..=0x7F) => InString::Plain,
+                    (InString::Plain, _) => utf8_lead(b).ok_or_else(|| self.invalid())?,
+                    (InString::Escape, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {
+                        InString::Plain
+                    }
+                    (InString::Escape, b'u') => InString::Hex(4),
+                    (InString::Hex(left), _) if b.is_ascii_hexdigit() => match left {
+                        1 => InString::Plain,
+                        left => InString::Hex(left - 1),
+                    },
+                    (InString::Utf8 { left, lo, hi }, _) if (lo..=hi).contains(&b) => match left {
+                        1 => InString::Plain,
+                        left => InString::Utf8 {
+                            left: left - 1,
+                            lo: 0x80,
+                            hi: 0xBF,
+                        },
+                    },
+                    _ => return Err(self.invalid()),
+                };
+                self.token = Token::String { key, state };
+                return Ok(true);
+            }
+            Token::Number(num) => match num.next(b) {
+                Some(num) => {
+                    self.token = Token::Number(num);
+                    return Ok(true);
+                }
+                None if num.complete() => {
+                    self.token = Token::None;
+                    self.expect = self.after_value();
+                }
+                None => return Err(self.invalid()),
+            },
+            Token::Literal(rest) | Token::Bom(rest) => {
+                let [first, rest @ ..] = rest else {
+                    unreachable!("no token is left empty");
+                };
+                if b != *first {
+                    return Err(self.invalid());
+                }
+                self.token = match (self.token, rest) {
+                    (Token::Literal(_), []) => {
+                        self.expect = self.after_value();
+                        Token::None
+                    }
+                    (_, []) => Token::None,
+                    (Token::Literal(_), rest) => Token::Literal(rest),
+                    (_, rest) => Token::Bom(rest),
+                };
+                return Ok(true);
+            }
+        }
+        self.between_tokens(b)
+    }
+
+    /// `b` outside any token
+    fn between_tokens(&mut self, b: u8) -> io::Result<bool> {
+        let in_object = self.stack.last().copied();
+        match (self.expect, b) {
+            (_, b' ' | b'\t' | b'\n' | b'\r') => return Ok(false),
+            (Expect::FirstElement, b']') | (Expect::CommaOrEnd, b']')
+                if in_object == Some(false) =>
+            {
+                self.stack.pop();
+                self.expect = self.after_value();
+            }
+            (Expect::FirstKey, b'}') | (Expect::CommaOrEnd, b'}') if in_object == Some(true) => {
+                self.stack.pop();
+                self.expect = self.after_value();
+            }
+            (Expect::CommaOrEnd, b',') if in_object == Some(true) => self.expect = Expect::Key,
+            (Expect::CommaOrEnd, b',') => self.expect = Expect::Value,
+            (Expect::Colon, b':') => self.expect = Expect::Value,
+            (Expect::FirstKey | Expect::Key, b'"') => {
+                self.token = Token::String {
+                    key: true,
+                    state: InString::Plain,
+                };
+            }
+            (Expect::Value | Expect::FirstElement, _) => self.value(b)?,
+            _ => return Err(self.invalid()),
+        }
+        Ok(true)
+    }
+
+    /// the first byte of a value
+    fn value(&mut self, b: u8) -> io::Result<()> {
+        match b {
+            b'{' => {
+                self.stack.push(true);
+                self.expect = Expect::FirstKey;
+            }
+            b'[' => {
+                self.stack.push(false);
+                self.expect = Expect::FirstElement;
+            }
+            b'"' => {
+                self.token = Token::String {
+                    key: false,
+                    state: InString::Plain,
+                };
+            }
+            b'-' => self.token = Token::Number(Num::Minus),
+            b'0' => self.token = Token::Number(Num::Zero),
+            b'1'..=b'9' => self.token = Token::Number(Num::Int),
+            b't' => self.token = Token::Literal(b"rue"),
+            b'f' => self.token = Token::Literal(b"alse"),
+            b'n' => self.token = Token::Literal(b"ull"),
+            0xEF if self.offset == 1 => self.token = Token::Bom(b"\xBB\xBF"),
+            _ => return Err(self.invalid()),
+        }
+        Ok(())
+    }
+
+    /// what may follow a complete value
+    fn after_value(&self) -> Expect {
+        if self.stack.is_empty() {
+            Expect::End
+        } else {
+            Expect::CommaOrEnd
+        }
+    }
+
+    /// The end of the input.
+    fn end(&mut self) -> io::Result<()> {
+        match self.token {
+            Token::Number(num) if num.complete() => {
+                self.token = Token::None;
+                self.expect = self.after_value();
+            }
+            Token::String { .. } => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "unterminated string",
+                ))
+            }
+            _ => {}
+        }
+        if self.token == Token::None && self.expect == Expect::End {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of input",
+            ))
+        }
+    }
+
+    /// the error of the byte just read
+    fn invalid(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid JSON at byte {}", self.offset - 1),
+        )
+    }
+}
+
+/// the state after the first byte of a UTF-8 sequence of more than one byte,
+/// `None` if it can't start one
+fn utf8_lead(b: u8) -> Option<InString> {
+    let (left, lo, hi) = match b {
+        0xC2..=0xDF => (1, 0x80, 0xBF),
+        0xE0 => (2, 0xA0, 0xBF),
+        0xE1..=0xEC | 0xEE..=0xEF => (2, 0x80, 0xBF),
+        // not the surrogates
+        0xED => (2, 0x80, 0x9F),
+        0xF0 => (3, 0x90, 0xBF),
+        0xF1..=0xF3 => (3, 0x80, 0xBF),
+        0xF4 => (3, 0x80, 0x8F),
+        _ => return None,
+    };
+    Some(InString::Utf8 { left, lo, hi })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader};
+
+    use super::minify;
+
+    fn minified(input: &str) -> io::Result<String> {
+        let mut out = Vec::new();
+        let written = minify(input.as_bytes(), &mut out)?;
+        assert_eq!(written, out.len() as u64);
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_minify() {
+        let input =
+            "\u{FEFF} {\r\n\t\"k \\\" \\\\\": [ -1.5e3 , null,\n\"\\u00e9 é\" ],\n \"\": {} }\n";
+        let output = "\u{FEFF}{\"k \\\" \\\\\":[-1.5e3,null,\"\\u00e9 é\"],\"\":{}}";
+        assert_eq!(minified(input).unwrap(), output);
+        assert_eq!(
+            crate::parse(&output[3..]).unwrap(),
+            crate::parse(&input[3..]).unwrap()
+        );
+
+        // any split of the input into buffers
+        for capacity in 1..8 {
+            let mut out = Vec::new();
+            minify(
+                BufReader::with_capacity(capacity, input.as_bytes()),
+                &mut out,
+            )
+            .unwrap();
+            assert_eq!(out, output.as_bytes(), "{}", capacity);
+        }
+
+        assert_eq!(minified(" 42 ").unwrap(), "42");
+        assert_eq!(minified("[0, -0.5E+2, 1e1]").unwrap(), "[0,-0.5E+2,1e1]");
+        assert_eq!(
+            minified("\"😀\\ud83d\\ude00\"").unwrap(),
+            "\"😀\\ud83d\\ude00\""
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // the inputs the parser rejects, but for the checks on values
+        for (input, offset) in [
+            ("[1 2, tru e]", 3),
+            ("[1, tru e]", 7),
+            ("[01]", 2),
+            ("[1.]", 3),
+            ("[-]", 2),
+            ("[1e+]", 4),
+            ("{\"a\" 1}", 5),
+            ("{\"a\": 1 \"b\": 2}", 8),
+            ("{\"a\": 1,}", 8),
+            ("[1,]", 3),
+            ("[1}", 2),
+            ("{1: 2}", 1),
+            ("[\"\\x\"]", 3),
+            ("[\"\\u12g4\"]", 6),
+            ("[\"a\nb\"]", 3),
+            ("[] []", 3),
+            ("nulL", 3),
+            ("truex", 4),
+            (" \u{FEFF}[]", 1),
+        ] {
+            let err = minified(input).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", input);
+            let expect = format!("invalid JSON at byte {}", offset);
+            assert_eq!(err.to_string(), expect, "{:?}", input);
+            assert!(crate::parse(input).is_err(), "{:?}", input);
+        }
+        for input in [&b"[\"\xFF\"]"[..], b"[\"\xED\xA0\x80\"]", b"[\"\xC3\"]"] {
+            let err = minify(input, Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", input);
+        }
+
+        for input in ["", " ", "[1,", "{\"a\":", "-", "[\"a\\\"]", "tr", "nul"] {
+            let err = minified(input).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Objects that keep every member, duplicate keys included.
+//!
+//! [`parse_multi`](crate::parse_multi) builds a [`MultiValue`], whose objects
+//! are [`MultiMap`]s listing their members in document order. That's enough
+//! to audit duplicate keys or reproduce the document's member order; collapse
+//! it into a [`JsonValue`] with [`MultiValue::into_value`] once a
+//! [`DuplicateKeys`] policy has been chosen.
+
+use std::{borrow::Cow, error::Error, fmt, slice};
+
+use crate::{
+    error::ErrorKind,
+    map::Map,
+    number::Number,
+    options::DuplicateKeys,
+    parser::{Dom, ParseState},
+    JsonPointer, JsonValue,
+};
+
+/// A JSON value whose objects keep duplicate keys.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultiValue {
+    String(String),
+    Bool(bool),
+    Null,
+    Number(Number),
+    Object(MultiMap),
+    Array(Vec<MultiValue>),
+}
+
+impl MultiValue {
+    /// Collapse every object into a [`Map`], resolving duplicate keys with
+    /// `policy`.
+    pub fn into_value(self, policy: DuplicateKeys) -> Result<JsonValue, DuplicateKeyError> {
+        Ok(match self {
+            MultiValue::String(s) => JsonValue::String(s),
+            MultiValue::Bool(b) => JsonValue::Bool(b),
+            MultiValue::Null => JsonValue::Null,
+            MultiValue::Number(n) => JsonValue::Number(n),
+            MultiValue::Object(object) => JsonValue::Object(object.into_map(policy)?),
+            MultiValue::Array(items) => JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(|item| item.into_value(policy))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    /// Every duplicate key in the tree, in document order, as
+    /// `(pointer, key, occurrences)` where `pointer` is the JSON Pointer of
+    /// the object the key is duplicated in.
+    pub fn duplicate_keys(&self) -> Vec<(JsonPointer, String, usize)> {
+        let mut found = Vec::new();
+        self.find_duplicate_keys(&mut JsonPointer::root(), &mut found);
+        found
+    }
+
+    fn find_duplicate_keys(
+        &self,
+        pointer: &mut JsonPointer,
+        found: &mut Vec<(JsonPointer, String, usize)>,
+    ) {
+        let len = pointer.len();
+        match self {
+            MultiValue::Object(object) => {
+                for key in object.duplicate_keys() {
+                    found.push((pointer.clone(), key.to_owned(), object.get_all(key).count()));
+                }
+                for (key, value) in object {
+                    pointer.push(key);
+                    value.find_duplicate_keys(pointer, found);
+                    pointer.truncate(len);
+                }
+            }
+            MultiValue::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    pointer.push(&i.to_string());
+                    item.find_duplicate_keys(pointer, found);
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Object members in document order, duplicates included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MultiMap(Vec<(String, MultiValue)>);
+
+impl MultiMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of members, counting every duplicate.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, key: String, value: MultiValue) {
+        self.0.push((key, value));
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, (String, MultiValue)> {
+        self.0.iter()
+    }
+
+    /// Values of every member named `key`, in document order.
+    pub fn get_all<'s>(&'s self, key: &'s str) -> impl Iterator<Item = &'s MultiValue> + 's {
+        self.0.iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Keys occurring more than once, each listed once, in order of their
+    /// first occurrence.
+    pub fn duplicate_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (i, (key, _)) in self.0.iter().enumerate() {
+            if !keys.contains(&key.as_str()) && self.0[i + 1..].iter().any(|(k, _)| k == key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    pub fn into_vec(self) -> Vec<(String, MultiValue)> {
+        self.0
+    }
+
+    /// Collapse into a [`Map`], resolving duplicate keys with `policy`.
+    pub fn into_map(
+        self,
+        policy: DuplicateKeys,
+    ) -> Result<Map<String, JsonValue>, DuplicateKeyError> {
+        let mut map = Map::new();
+        for (key, value) in self.0 {
+            let value = value.into_value(policy)?;
+            match policy {
+                DuplicateKeys::LastWins => {
+                    map.insert(key, value);
+                }
+                DuplicateKeys::FirstWins => {
+                    map.entry(key).or_insert(value);
+                }
+                DuplicateKeys::Error if map.contains_key(&key) => {
+                    return Err(DuplicateKeyError { key });
+                }
+                DuplicateKeys::Error => {
+                    map.insert(key, value);
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl From<Vec<(String, MultiValue)>> for MultiMap {
+    fn from(members: Vec<(String, MultiValue)>) -> Self {
+        MultiMap(members)
+    }
+}
+
+impl<'m> IntoIterator for &'m MultiMap {
+    type Item = &'m (String, MultiValue);
+    type IntoIter = slice::Iter<'m, (String, MultiValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for MultiMap {
+    type Item = (String, MultiValue);
+    type IntoIter = std::vec::IntoIter<(String, MultiValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Error returned when collapsing a [`MultiMap`] with
+/// [`DuplicateKeys::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    key: String,
+}
+
+impl DuplicateKeyError {
+    /// The key occurring more than once.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}", ErrorKind::DuplicateKey, self.key)
+    }
+}
+
+impl Error for DuplicateKeyError {}
+
+/// every member is kept, whatever the `duplicate_keys` option
+impl<'a> Dom<'a> for MultiValue {
+    type Object = MultiMap;
+
+    fn string(s: Cow<'a, str>) -> Self {
+        MultiValue::String(s.into_owned())
+    }
+
+    fn bool(b: bool) -> Self {
+        MultiValue::Bool(b)
+    }
+
+    fn null() -> Self {
+        MultiValue::Null
+    }
+
+    fn number(n: Number) -> Self {
+        MultiValue::Number(n)
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        MultiValue::Array(items)
+    }
+
+    fn object(object: MultiMap) -> Self {
+        MultiValue::Object(object)
+    }
+
+    fn members(
+        members: Vec<(&'a str, Cow<'a, str>, Self)>,
+        _: &ParseState,
+    ) -> Result<MultiMap, (&'a str, ErrorKind)> {
+        Ok(MultiMap(
+            members
+                .into_iter()
+                .map(|(_, key, value)| (key.into_owned(), value))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{find_duplicate_keys, parse_multi, DuplicateKeys, JsonValue, Map};
+
+    use super::{MultiMap, MultiValue};
+
+    #[test]
+    fn test_parse_multi() {
+        let value = parse_multi(r#"{"b": 1, "a": 2, "b": {"c": 3, "c": 4}}"#).unwrap();
+        let object = match &value {
+            MultiValue::Object(object) => object,
+            _ => unreachable!(),
+        };
+        let keys: Vec<_> = object.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["b", "a", "b"]);
+        assert_eq!(object.duplicate_keys(), ["b"]);
+        assert_eq!(
+            object.get_all("b").next(),
+            Some(&MultiValue::Number(1u8.into()))
+        );
+        assert_eq!(
+            object.get_all("b").nth(1),
+            Some(&MultiValue::Object(MultiMap::from(vec![
+                ("c".to_owned(), MultiValue::Number(3u8.into())),
+                ("c".to_owned(), MultiValue::Number(4u8.into())),
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_into_value() {
+        let value = parse_multi(r#"[{"a": 1, "a": 2}]"#).unwrap();
+        let expect = |n: u8| {
+            JsonValue::Array(vec![JsonValue::Object(Map::from([(
+                "a".to_owned(),
+                JsonValue::Number(n.into()),
+            )]))])
+        };
+        assert_eq!(
+            value.clone().into_value(DuplicateKeys::LastWins),
+            Ok(expect(2))
+        );
+        assert_eq!(
+            value.clone().into_value(DuplicateKeys::FirstWins),
+            Ok(expect(1))
+        );
+        let err = value.into_value(DuplicateKeys::Error).unwrap_err();
+        assert_eq!(err.key(), "a");
+        assert_eq!(err.to_string(), r#"duplicate key "a""#);
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        let input = r#"{"a": 1, "a/b~": [{}, {"c": 1, "c": 2, "c": 3}], "a": {"d": 1, "d": 2}}"#;
+        let found: Vec<_> = find_duplicate_keys(input)
+            .unwrap()
+            .into_iter()
+            .map(|(pointer, key, n)| (pointer.to_string(), key, n))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (String::new(), "a".to_owned(), 2),
+                ("/a~1b~0/1".to_owned(), "c".to_owned(), 3),
+                ("/a".to_owned(), "d".to_owned(), 2),
+            ]
+        );
+        assert_eq!(find_duplicate_keys(r#"[{"a": 1}]"#).unwrap(), []);
+    }
+}
@@ -0,0 +1,448 @@
+//! JSON numbers.
+
+use std::{cmp::Ordering, fmt};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+/// A JSON number: an integer when it fits in `u64` or `i64` (`u128` or `i128`
+/// with the `i128` feature), an `f64` otherwise. With the `decimal` feature,
+/// numbers with a fraction or an exponent can also be parsed to a
+/// `rust_decimal::Decimal`, see `ParserOptions::decimal_numbers`.
+///
+/// Numbers compare by value, whatever their representation, so `1` and
+/// `1.0` are equal.
+///
+/// With [`ParserOptions::keep_number_text`](crate::ParserOptions::keep_number_text)
+/// a parsed number also keeps its source text, which its `Display` impl then
+/// writes verbatim.
+#[derive(Clone)]
+pub struct Number {
+    n: N,
+    text: Option<Box<str>>,
+}
+
+#[cfg(not(feature = "i128"))]
+type UInt = u64;
+#[cfg(not(feature = "i128"))]
+type SInt = i64;
+
+#[cfg(feature = "i128")]
+type UInt = u128;
+#[cfg(feature = "i128")]
+type SInt = i128;
+
+#[derive(Clone, Copy)]
+enum N {
+    PosInt(UInt),
+    /// always less than zero
+    NegInt(SInt),
+    Float(f64),
+    #[cfg(feature = "decimal")]
+    Decimal(Decimal),
+}
+
+impl Number {
+    /// `None` for NaN.
+    ///
+    /// Infinities are accepted, as the parser produces them for overflowing
+    /// numbers by default.
+    pub fn from_f64(f: f64) -> Option<Number> {
+        if f.is_nan() {
+            None
+        } else {
+            Some(Number::float(f))
+        }
+    }
+
+    pub(crate) fn float(f: f64) -> Number {
+        Number {
+            n: N::Float(f),
+            text: None,
+        }
+    }
+
+    /// The integer spelled by `text`, if it is in range.
+    pub(crate) fn integer(text: &str) -> Option<Number> {
+        let n = match text.parse::<UInt>() {
+            Ok(n) => N::PosInt(n),
+            // `-0` is left to `f64`, to keep its sign
+            Err(_) => match text.parse::<SInt>() {
+                Ok(n) if n < 0 => N::NegInt(n),
+                _ => return None,
+            },
+        };
+        Some(Number { n, text: None })
+    }
+
+    /// The decimal spelled by `text`, if it is representable exactly.
+    #[cfg(feature = "decimal")]
+    pub(crate) fn decimal(text: &str) -> Option<Number> {
+        let d = match text.find(['e', 'E']) {
+            None => Decimal::from_str_exact(text),
+            // `from_scientific` rounds the mantissa
+            Some(i) => {
+                Decimal::from_str_exact(&text[..i]).and_then(|_| Decimal::from_scientific(text))
+            }
+        };
+        d.ok().map(|d| Number {
+            n: N::Decimal(d),
+            text: None,
+        })
+    }
+
+    pub(crate) fn with_text(self, text: &str) -> Number {
+        Number {
+            text: Some(text.into()),
+            ..self
+        }
+    }
+
+    /// The text the number was parsed from, if it was kept.
+    pub fn as_str(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Whether the number is an integer representable as `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Whether the number is an integer representable as `u64`.
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Whether the number is stored as `f64`, i.e. it has a fraction or an
+    /// exponent, or is out of range for the integer types, and isn't a
+    /// `Decimal`.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            // the same type without the `i128` feature
+            #[allow(clippy::useless_conversion)]
+            N::NegInt(n) => i64::try_from(n).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            // the same type without the `i128` feature
+            #[allow(clippy::useless_conversion)]
+            N::PosInt(n) => u64::try_from(n).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the number is an integer representable as `i128`.
+    #[cfg(feature = "i128")]
+    pub fn is_i128(&self) -> bool {
+        self.as_i128().is_some()
+    }
+
+    /// Whether the number is an integer representable as `u128`.
+    #[cfg(feature = "i128")]
+    pub fn is_u128(&self) -> bool {
+        self.as_u128().is_some()
+    }
+
+    #[cfg(feature = "i128")]
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.n {
+            N::PosInt(n) => i128::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "i128")]
+    pub fn as_u128(&self) -> Option<u128> {
+        match self.n {
+            N::PosInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The nearest `f64`; always `Some`.
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match self.n {
+            N::PosInt(n) => n as f64,
+            N::NegInt(n) => n as f64,
+            N::Float(f) => f,
+            #[cfg(feature = "decimal")]
+            N::Decimal(d) => d.to_f64()?,
+        })
+    }
+
+    /// Whether the number is stored as a [`Decimal`].
+    #[cfg(feature = "decimal")]
+    pub fn is_decimal(&self) -> bool {
+        matches!(self.n, N::Decimal(_))
+    }
+
+    /// The number as a [`Decimal`], if it is one or an integer in its range.
+    #[cfg(feature = "decimal")]
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self.n {
+            N::Decimal(d) => Some(d),
+            N::Float(_) => None,
+            int => int_decimal(int),
+        }
+    }
+}
+
+#[cfg(feature = "decimal")]
+// the same types with the `i128` feature
+#[allow(clippy::unnecessary_cast)]
+fn int_decimal(int: N) -> Option<Decimal> {
+    match int {
+        N::PosInt(n) => Decimal::from_u128(n as u128),
+        N::NegInt(n) => Decimal::from_i128(n as i128),
+        _ => None,
+    }
+}
+
+/// Compares an integer with a float exactly, without rounding the integer.
+fn cmp_int_float(int: N, f: f64) -> Option<Ordering> {
+    if f.is_nan() {
+        return None;
+    }
+    // once in range, truncating `f` is exact for its integral part
+    let trunc = f.trunc();
+    let ord = match int {
+        N::PosInt(_) if f < 0. => Ordering::Greater,
+        // `MAX as f64` rounds up to the next power of two
+        N::PosInt(_) if trunc >= UInt::MAX as f64 => Ordering::Less,
+        N::PosInt(n) => n.cmp(&(trunc as UInt)),
+        N::NegInt(_) if f >= 0. => Ordering::Less,
+        N::NegInt(_) if trunc < SInt::MIN as f64 => Ordering::Greater,
+        N::NegInt(n) => n.cmp(&(trunc as SInt)),
+        // only integers are compared with floats
+        _ => return None,
+    };
+    match ord {
+        Ordering::Equal if f > trunc => Some(Ordering::Less),
+        Ordering::Equal if f < trunc => Some(Ordering::Greater),
+        ord => Some(ord),
+    }
+}
+
+/// Integers out of the range of [`Decimal`] are larger in magnitude than any.
+#[cfg(feature = "decimal")]
+fn cmp_int_decimal(int: N, d: Decimal) -> Ordering {
+    match (int_decimal(int), int) {
+        (Some(i), _) => i.cmp(&d),
+        (None, N::NegInt(_)) => Ordering::Less,
+        (None, _) => Ordering::Greater,
+    }
+}
+
+/// Compares by value, whatever the representations.
+///
+/// Decimals are compared exactly with integers and decimals, and as their
+/// nearest `f64` with floats.
+fn cmp(a: N, b: N) -> Option<Ordering> {
+    match (a, b) {
+        (N::Float(a), N::Float(b)) => a.partial_cmp(&b),
+        #[cfg(feature = "decimal")]
+        (N::Decimal(a), N::Decimal(b)) => Some(a.cmp(&b)),
+        #[cfg(feature = "decimal")]
+        (N::Decimal(d), N::Float(f)) => d.to_f64()?.partial_cmp(&f),
+        #[cfg(feature = "decimal")]
+        (N::Float(f), N::Decimal(d)) => f.partial_cmp(&d.to_f64()?),
+        #[cfg(feature = "decimal")]
+        (N::Decimal(d), int) => Some(cmp_int_decimal(int, d).reverse()),
+        #[cfg(feature = "decimal")]
+        (int, N::Decimal(d)) => Some(cmp_int_decimal(int, d)),
+        (int, N::Float(f)) => cmp_int_float(int, f),
+        (N::Float(f), int) => cmp_int_float(int, f).map(Ordering::reverse),
+        (N::PosInt(a), N::PosInt(b)) => Some(a.cmp(&b)),
+        (N::NegInt(a), N::NegInt(b)) => Some(a.cmp(&b)),
+        (N::PosInt(_), N::NegInt(_)) => Some(Ordering::Greater),
+        (N::NegInt(_), N::PosInt(_)) => Some(Ordering::Less),
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        cmp(self.n, other.n) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        cmp(self.n, other.n)
+    }
+}
+
+impl PartialEq<i64> for Number {
+    fn eq(&self, other: &i64) -> bool {
+        cmp(self.n, Number::from(*other).n) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialEq<u64> for Number {
+    fn eq(&self, other: &u64) -> bool {
+        cmp(self.n, Number::from(*other).n) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialEq<f64> for Number {
+    fn eq(&self, other: &f64) -> bool {
+        cmp(self.n, N::Float(*other)) == Some(Ordering::Equal)
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($ty:ty)*) => {$(
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Self {
+                Number { n: N::PosInt(n as UInt), text: None }
+            }
+        }
+    )*};
+}
+
+macro_rules! from_signed {
+    ($($ty:ty)*) => {$(
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Self {
+                let n = if n < 0 { N::NegInt(n as SInt) } else { N::PosInt(n as UInt) };
+                Number { n, text: None }
+            }
+        }
+    )*};
+}
+
+from_unsigned!(u8 u16 u32 u64 usize);
+from_signed!(i8 i16 i32 i64 isize);
+#[cfg(feature = "i128")]
+from_unsigned!(u128);
+#[cfg(feature = "i128")]
+from_signed!(i128);
+
+/// The source text if it was kept. Otherwise integers as written and floats
+/// in the shortest form that reads back the same `f64`, always with a fraction
+/// or exponent.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(text) = &self.text {
+            return f.write_str(text);
+        }
+        match self.n {
+            N::PosInt(n) => write!(f, "{}", n),
+            N::NegInt(n) => write!(f, "{}", n),
+            N::Float(n) => write!(f, "{:?}", n),
+            #[cfg(feature = "decimal")]
+            N::Decimal(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Number({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Number;
+
+    #[test]
+    fn test_accessors() {
+        let n = Number::from(-3i32);
+        assert!(n.is_i64() && !n.is_u64() && !n.is_f64());
+        assert_eq!(
+            (n.as_i64(), n.as_u64(), n.as_f64()),
+            (Some(-3), None, Some(-3.))
+        );
+
+        let n = Number::from(u64::MAX);
+        assert!(!n.is_i64() && n.is_u64());
+        assert_eq!((n.as_i64(), n.as_u64()), (None, Some(u64::MAX)));
+
+        let n = Number::from_f64(0.5).unwrap();
+        assert!(n.is_f64());
+        assert_eq!((n.as_i64(), n.as_f64()), (None, Some(0.5)));
+
+        assert_eq!(Number::from_f64(f64::NAN), None);
+        assert!(Number::from_f64(f64::INFINITY).is_some());
+    }
+
+    #[test]
+    fn test_cmp() {
+        let f = |n| Number::from_f64(n).unwrap();
+        assert_eq!(Number::from(1u8), f(1.));
+        assert_eq!(f(-2.), Number::from(-2i8));
+        assert_ne!(Number::from(1u8), f(1.5));
+        assert!(Number::from(1u8) < f(1.5));
+        assert!(f(-1.5) < Number::from(-1i8));
+        // 2^53 + 1 isn't an f64, but compares exactly
+        let (above, max, min) = (
+            Number::from(9_007_199_254_740_993u64),
+            Number::from(u64::MAX),
+            Number::from(i64::MIN),
+        );
+        assert!(above > f(9_007_199_254_740_992.));
+        assert!(max < f(1e20));
+        assert!(min > f(-1e19));
+        assert_eq!(Number::from(7u8), 7i64);
+        assert_eq!(Number::from(7u8), 7.);
+    }
+
+    #[cfg(feature = "i128")]
+    #[test]
+    fn test_i128() {
+        let n = Number::integer("340282366920938463463374607431768211455").unwrap();
+        assert!(n.is_u128() && !n.is_i128() && !n.is_u64() && !n.is_f64());
+        assert_eq!(n.as_u128(), Some(u128::MAX));
+        assert_eq!(n.to_string(), "340282366920938463463374607431768211455");
+        assert_eq!(n, Number::from(u128::MAX));
+        assert!(n < Number::from_f64(2f64.powi(128)).unwrap());
+
+        let n = Number::integer("-170141183460469231731687303715884105728").unwrap();
+        assert_eq!(n.as_i128(), Some(i128::MIN));
+        assert_eq!(n, Number::from_f64(-(2f64.powi(127))).unwrap());
+        assert!(Number::integer("-170141183460469231731687303715884105729").is_none());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal() {
+        let n = Number::decimal("0.10").unwrap();
+        assert!(n.is_decimal() && !n.is_f64() && !n.is_i64());
+        assert_eq!(n.to_string(), "0.10");
+        assert_eq!(n.as_f64(), Some(0.1));
+        assert_eq!(n, Number::from_f64(0.1).unwrap());
+        assert_eq!(n.as_decimal(), Some(rust_decimal::Decimal::new(1, 1)));
+        assert_eq!(Number::from(7u8).as_decimal(), Some(7.into()));
+
+        assert_eq!(Number::decimal("1.5e3").unwrap(), Number::from(1500u16));
+        assert!(Number::decimal("-2.5").unwrap() < Number::from(-2i8));
+        assert!(Number::decimal("1e-30").is_none());
+        assert!(Number::decimal("1e30").is_none());
+        assert!(Number::decimal("0.00000000000000000000000000001").is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Number::from(-12i64).to_string(), "-12");
+        assert_eq!(Number::from_f64(1.).unwrap().to_string(), "1.0");
+        assert_eq!(Number::from_f64(1e300).unwrap().to_string(), "1e300");
+        assert_eq!(format!("{:?}", Number::from(3u8)), "Number(3)");
+
+        let n = Number::from(100u8).with_text("1.0e+2");
+        assert_eq!(n.as_str(), Some("1.0e+2"));
+        assert_eq!(n.to_string(), "1.0e+2");
+        assert_eq!(n, Number::from(100u8));
+    }
+}
@@ -0,0 +1,818 @@
+//! Parser configuration.
+//!
+//! RFC 8259 leaves a handful of behaviors up to the implementation; the
+//! [JSONTestSuite](https://github.com/nst/JSONTestSuite) collects them as its
+//! `i_*` test files. Each of them is an individual flag on [`ParserOptions`],
+//! and [`ParserOptions::profile`] / [`ParserOptions::accepts`] report how the
+//! parser is configured.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::JsonValue;
+
+/// Numbers whose magnitude is too large for `f64`, e.g. `1e400`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberOverflow {
+    /// Parse to `f64::INFINITY` / `f64::NEG_INFINITY`.
+    Infinity,
+    /// Parse to `f64::INFINITY` / `f64::NEG_INFINITY`, keeping the text of
+    /// the number, see [`Number::as_str`](crate::Number::as_str).
+    KeepText,
+    /// Reject the document.
+    Error,
+}
+
+/// Non-zero numbers too small for `f64`, e.g. `1e-400`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberUnderflow {
+    /// Parse to `0.0`.
+    Zero,
+    /// Reject the document.
+    Error,
+}
+
+/// `-0` and other negative numbers that parse to zero, like `-0.0` and
+/// `-1e-400`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeZero {
+    /// Parse to `-0.0`.
+    Keep,
+    /// Parse to `0`, or `0.0` for numbers with a fraction or an exponent.
+    Normalize,
+}
+
+/// Integers out of range for [`Number`](crate::Number)'s integer types, which
+/// are stored as `f64` and so can't always be represented exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeIntegers {
+    /// Round to the nearest `f64`, logging a warning with the `log` feature.
+    Lossy,
+    /// Reject the document.
+    Error,
+}
+
+/// `\u` escapes naming a UTF-16 surrogate, which is not a valid `char`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoneSurrogates {
+    /// Reject the document.
+    Error,
+    /// Substitute U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+/// `\u` escapes not followed by four hex digits, e.g. `\u12G4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidUnicodeEscapes {
+    /// Reject the document.
+    Error,
+    /// Substitute U+FFFD REPLACEMENT CHARACTER for the `\u` and the hex
+    /// digits before the first non-hex character.
+    Replace,
+}
+
+/// Input bytes that aren't valid UTF-8, where a document is read from bytes,
+/// e.g. by [`parse_bytes_with`](crate::parse_bytes_with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Reject the document.
+    Error,
+    /// Substitute U+FFFD REPLACEMENT CHARACTER for each invalid sequence, as
+    /// [`String::from_utf8_lossy`] does; positions are then those in the
+    /// decoded text.
+    Replace,
+}
+
+/// Objects with the same key more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep the last occurrence.
+    LastWins,
+    /// Keep the first occurrence.
+    FirstWins,
+    /// Reject the document.
+    Error,
+}
+
+/// Object members without their `:`, or without the `,` before the next
+/// member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingSeparators {
+    /// Reject the document.
+    Error,
+    /// Drop the text from the missing separator to the next `,` or `}` of the
+    /// same object: the member without its `:`, or the member after the
+    /// missing `,`.
+    Skip,
+}
+
+/// Unicode normalization of object keys.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyNormalization {
+    /// Keep keys as written.
+    None,
+    /// Normalize keys to NFC, so keys that only differ by normalization form
+    /// are duplicates.
+    Nfc,
+    /// Normalize keys to NFC and reject objects with keys that only differ by
+    /// normalization form.
+    Strict,
+}
+
+/// `NaN`, `Infinity` and `-Infinity` tokens, which are not JSON but are
+/// written by e.g. Python's `json` module; also how
+/// [`WriteOptions::non_finite_numbers`](crate::WriteOptions::non_finite_numbers)
+/// writes the numbers they stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFiniteNumbers {
+    /// Reject the document; fail to write it.
+    Error,
+    /// Parse to `f64::NAN`, `f64::INFINITY` and `f64::NEG_INFINITY`; write
+    /// the tokens.
+    Accept,
+    /// Parse to `null`; write `null`.
+    Null,
+}
+
+/// Decoder for escapes JSON doesn't define, like `\x41` or `\0`, see
+/// [`ParserOptions::escape_decoder`].
+///
+/// The JSON escapes are decoded by the parser before it's called, and a
+/// malformed `\u` escape is an error whatever the decoder. Closures taking
+/// the input and returning what `decode` returns are decoders.
+pub trait EscapeDecoder: Send + Sync {
+    /// Decode the escape at the start of `input`, the text after a `\`: the
+    /// character and the number of bytes of `input` it takes, or `None` to
+    /// reject the document with an invalid escape.
+    fn decode(&self, input: &str) -> Option<(char, usize)>;
+}
+
+impl<F: Fn(&str) -> Option<(char, usize)> + Send + Sync> EscapeDecoder for F {
+    fn decode(&self, input: &str) -> Option<(char, usize)> {
+        self(input)
+    }
+}
+
+impl fmt::Debug for dyn EscapeDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EscapeDecoder")
+    }
+}
+
+/// Parser for tokens JSON doesn't define where a value is expected, like
+/// `undefined` or `@date(2024-01-01)`, see [`ParserOptions::literal_parser`].
+///
+/// Literal parsers are tried before the JSON values, in the order they were
+/// added. Closures taking the input and returning what `parse` returns are
+/// literal parsers.
+pub trait LiteralParser: Send + Sync {
+    /// Parse the token at the start of `input`: its value and the number of
+    /// bytes of `input` it takes, or `None` if `input` doesn't start with one.
+    /// A token of no bytes is ignored, as `None` is.
+    fn parse(&self, input: &str) -> Option<(JsonValue, usize)>;
+}
+
+impl<F: Fn(&str) -> Option<(JsonValue, usize)> + Send + Sync> LiteralParser for F {
+    fn parse(&self, input: &str) -> Option<(JsonValue, usize)> {
+        self(input)
+    }
+}
+
+impl fmt::Debug for dyn LiteralParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LiteralParser")
+    }
+}
+
+/// The escapes of C string literals that JSON lacks: `\0`, `\a`, `\v`,
+/// `\'`, `\?` and `\x` followed by two hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CEscapes;
+
+impl EscapeDecoder for CEscapes {
+    fn decode(&self, input: &str) -> Option<(char, usize)> {
+        let ch = match input.as_bytes().first()? {
+            b'0' => '\0',
+            b'a' => '\u{0007}',
+            b'v' => '\u{000B}',
+            b'\'' => '\'',
+            b'?' => '?',
+            b'x' => {
+                let hex = input
+                    .get(1..3)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+                return Some((char::from(u8::from_str_radix(hex, 16).ok()?), 3));
+            }
+            _ => return None,
+        };
+        Some((ch, 1))
+    }
+}
+
+/// Which object members to keep, see [`ParserOptions::key_filter`]; the
+/// values of the others are checked but not built.
+#[derive(Clone)]
+pub enum KeyFilter {
+    /// Keep only members with one of these keys.
+    Allow(Vec<String>),
+    /// Keep every member but those with one of these keys.
+    Deny(Vec<String>),
+    /// Keep the members whose key the predicate accepts.
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl KeyFilter {
+    pub fn allow<K: Into<String>>(keys: impl IntoIterator<Item = K>) -> Self {
+        KeyFilter::Allow(keys.into_iter().map(Into::into).collect())
+    }
+
+    pub fn deny<K: Into<String>>(keys: impl IntoIterator<Item = K>) -> Self {
+        KeyFilter::Deny(keys.into_iter().map(Into::into).collect())
+    }
+
+    pub fn predicate(predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        KeyFilter::Predicate(Arc::new(predicate))
+    }
+
+    /// Whether members with `key` are kept.
+    pub fn keeps(&self, key: &str) -> bool {
+        match self {
+            KeyFilter::Allow(keys) => keys.iter().any(|k| k == key),
+            KeyFilter::Deny(keys) => !keys.iter().any(|k| k == key),
+            KeyFilter::Predicate(predicate) => predicate(key),
+        }
+    }
+}
+
+impl fmt::Debug for KeyFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFilter::Allow(keys) => f.debug_tuple("Allow").field(keys).finish(),
+            KeyFilter::Deny(keys) => f.debug_tuple("Deny").field(keys).finish(),
+            KeyFilter::Predicate(_) => f.write_str("Predicate"),
+        }
+    }
+}
+
+/// Numbers with a fraction or an exponent.
+#[cfg(feature = "decimal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalNumbers {
+    /// Parse to `f64`.
+    Float,
+    /// Parse to [`Decimal`](rust_decimal::Decimal), falling back to `f64` for
+    /// numbers it can't represent exactly.
+    Decimal,
+    /// Parse to [`Decimal`](rust_decimal::Decimal), rejecting numbers it can't
+    /// represent exactly.
+    Strict,
+}
+
+/// Implementation-defined cases from the JSONTestSuite taxonomy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// `i_number_huge_exp`, `i_number_real_pos_overflow`, ...
+    NumberOverflow,
+    /// `i_number_real_underflow`
+    NumberUnderflow,
+    /// `i_number_too_big_pos_int`, `i_number_very_big_negative_int`, ...
+    LargeInteger,
+    /// `i_string_invalid_lonely_surrogate`, `i_object_key_lone_2nd_surrogate`, ...
+    LoneSurrogate,
+    /// `i_structure_UTF-8_BOM_empty_object`
+    ByteOrderMark,
+    /// `i_structure_500_nested_arrays`
+    DeepNesting,
+    /// `y_object_duplicated_key` (valid JSON, but the result is implementation-defined)
+    DuplicateKey,
+    /// `i_string_invalid_utf-8`, `i_string_iso_latin_1`, `i_string_overlong_sequence_2_bytes`, ...
+    InvalidUtf8,
+    /// `i_string_UTF-16LE_with_BOM`, `i_string_utf16BE_no_BOM`, `i_string_utf16LE_no_BOM`
+    Utf16,
+}
+
+impl Case {
+    pub const ALL: [Case; 9] = [
+        Case::NumberOverflow,
+        Case::NumberUnderflow,
+        Case::LargeInteger,
+        Case::LoneSurrogate,
+        Case::ByteOrderMark,
+        Case::DeepNesting,
+        Case::DuplicateKey,
+        Case::InvalidUtf8,
+        Case::Utf16,
+    ];
+}
+
+/// Named configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// [`ParserOptions::default`]: accept whatever can be given a reasonable
+    /// meaning.
+    Default,
+    /// [`ParserOptions::strict`]: reject every implementation-defined case.
+    Strict,
+    /// [`ParserOptions::untrusted`]: strict, with resource limits.
+    Untrusted,
+    /// Any other combination of flags.
+    Custom,
+}
+
+/// Options for [`parse_with`](crate::parse_with).
+///
+/// Options are `Send + Sync` and cheap to clone: the hooks and filters are
+/// shared, not copied, so a server can build its options once at startup and
+/// share them between all its request handlers.
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_array_len: Option<usize>,
+    pub(crate) max_string_len: Option<usize>,
+    pub(crate) max_values: Option<usize>,
+    pub(crate) max_duration: Option<Duration>,
+    pub(crate) number_overflow: NumberOverflow,
+    pub(crate) number_underflow: NumberUnderflow,
+    pub(crate) negative_zero: NegativeZero,
+    pub(crate) large_integers: LargeIntegers,
+    pub(crate) lone_surrogates: LoneSurrogates,
+    pub(crate) invalid_unicode_escapes: InvalidUnicodeEscapes,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) missing_separators: MissingSeparators,
+    pub(crate) allow_bom: bool,
+    pub(crate) invalid_utf8: InvalidUtf8,
+    pub(crate) allow_utf16: bool,
+    pub(crate) scalar_roots: bool,
+    pub(crate) keep_number_text: bool,
+    pub(crate) non_finite_numbers: NonFiniteNumbers,
+    pub(crate) escape_decoder: Option<Arc<dyn EscapeDecoder>>,
+    pub(crate) literals: Arc<Vec<Arc<dyn LiteralParser>>>,
+    pub(crate) key_filter: Option<Arc<KeyFilter>>,
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) key_normalization: KeyNormalization,
+    #[cfg(feature = "decimal")]
+    pub(crate) decimal_numbers: DecimalNumbers,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_depth: Some(ParserOptions::DEFAULT_MAX_DEPTH),
+            max_array_len: None,
+            max_string_len: None,
+            max_values: None,
+            max_duration: None,
+            number_overflow: NumberOverflow::Infinity,
+            number_underflow: NumberUnderflow::Zero,
+            negative_zero: NegativeZero::Keep,
+            large_integers: LargeIntegers::Lossy,
+            lone_surrogates: LoneSurrogates::Error,
+            invalid_unicode_escapes: InvalidUnicodeEscapes::Error,
+            duplicate_keys: DuplicateKeys::LastWins,
+            missing_separators: MissingSeparators::Error,
+            allow_bom: false,
+            invalid_utf8: InvalidUtf8::Error,
+            allow_utf16: false,
+            scalar_roots: true,
+            keep_number_text: false,
+            non_finite_numbers: NonFiniteNumbers::Error,
+            escape_decoder: None,
+            literals: Arc::default(),
+            key_filter: None,
+            #[cfg(feature = "unicode-normalization")]
+            key_normalization: KeyNormalization::None,
+            #[cfg(feature = "decimal")]
+            decimal_numbers: DecimalNumbers::Float,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Nesting depth limit used by the presets.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects every implementation-defined case.
+    pub fn strict() -> Self {
+        ParserOptions {
+            max_depth: Some(ParserOptions::DEFAULT_MAX_DEPTH),
+            max_array_len: None,
+            max_string_len: None,
+            max_values: None,
+            max_duration: None,
+            number_overflow: NumberOverflow::Error,
+            number_underflow: NumberUnderflow::Error,
+            negative_zero: NegativeZero::Keep,
+            large_integers: LargeIntegers::Error,
+            lone_surrogates: LoneSurrogates::Error,
+            invalid_unicode_escapes: InvalidUnicodeEscapes::Error,
+            duplicate_keys: DuplicateKeys::Error,
+            missing_separators: MissingSeparators::Error,
+            allow_bom: false,
+            invalid_utf8: InvalidUtf8::Error,
+            allow_utf16: false,
+            scalar_roots: true,
+            keep_number_text: false,
+            non_finite_numbers: NonFiniteNumbers::Error,
+            escape_decoder: None,
+            literals: Arc::default(),
+            key_filter: None,
+            #[cfg(feature = "unicode-normalization")]
+            key_normalization: KeyNormalization::None,
+            #[cfg(feature = "decimal")]
+            decimal_numbers: DecimalNumbers::Float,
+        }
+    }
+
+    /// Rejects every implementation-defined case and bounds the resources a
+    /// document can use: nesting depth 64, strings of 10 MiB, 1,000,000
+    /// values.
+    pub fn untrusted() -> Self {
+        ParserOptions {
+            max_depth: Some(64),
+            max_string_len: Some(10 << 20),
+            max_values: Some(1_000_000),
+            ..ParserOptions::strict()
+        }
+    }
+
+    /// Maximum nesting of arrays and objects, `None` for unlimited.
+    ///
+    /// Without a limit, deep enough input overflows the stack unless the
+    /// `stacker` feature is enabled, which has no effect within the limit:
+    /// deeply nested documents are parsed with both the feature and
+    /// `max_depth(None)`. Dropping such a value still recurses, see
+    /// [`JsonValue`](crate::JsonValue).
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Keep only the first `max_array_len` elements of each array, `None`
+    /// to keep them all.
+    ///
+    /// Later elements are still checked, but not built; see
+    /// [`parse_preview`](crate::parse_preview) for how many were dropped.
+    pub fn max_array_len(mut self, max_array_len: Option<usize>) -> Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    /// Maximum length in bytes of a string or key, once unescaped, `None`
+    /// for unlimited.
+    pub fn max_string_len(mut self, max_string_len: Option<usize>) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Maximum number of values in a document, counting every array element
+    /// and object member, `None` for unlimited.
+    pub fn max_values(mut self, max_values: Option<usize>) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    /// Wall-clock time a parse may take, `None` for unlimited.
+    ///
+    /// The clock is checked as each array or object starts and every 1024
+    /// elements or members within one, so a parse fails with
+    /// [`ErrorKind::Timeout`](crate::ErrorKind::Timeout) at the first of
+    /// those after the budget is spent. A string is read without checking the
+    /// clock: bound the time one takes with [`max_string_len`](Self::max_string_len).
+    pub fn max_duration(mut self, max_duration: Option<Duration>) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    pub fn number_overflow(mut self, policy: NumberOverflow) -> Self {
+        self.number_overflow = policy;
+        self
+    }
+
+    pub fn number_underflow(mut self, policy: NumberUnderflow) -> Self {
+        self.number_underflow = policy;
+        self
+    }
+
+    pub fn negative_zero(mut self, policy: NegativeZero) -> Self {
+        self.negative_zero = policy;
+        self
+    }
+
+    pub fn large_integers(mut self, policy: LargeIntegers) -> Self {
+        self.large_integers = policy;
+        self
+    }
+
+    pub fn lone_surrogates(mut self, policy: LoneSurrogates) -> Self {
+        self.lone_surrogates = policy;
+        self
+    }
+
+    pub fn invalid_unicode_escapes(mut self, policy: InvalidUnicodeEscapes) -> Self {
+        self.invalid_unicode_escapes = policy;
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn missing_separators(mut self, policy: MissingSeparators) -> Self {
+        self.missing_separators = policy;
+        self
+    }
+
+    /// Skip a leading U+FEFF BYTE ORDER MARK instead of rejecting it.
+    pub fn allow_bom(mut self, allow: bool) -> Self {
+        self.allow_bom = allow;
+        self
+    }
+
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8) -> Self {
+        self.invalid_utf8 = policy;
+        self
+    }
+
+    /// Read bytes in UTF-16 instead of rejecting them: bytes starting with a
+    /// UTF-16 byte order mark, which is dropped, or without one, with the
+    /// zero bytes of two ASCII chars, as RFC 4627 detected the encoding.
+    pub fn allow_utf16(mut self, allow: bool) -> Self {
+        self.allow_utf16 = allow;
+        self
+    }
+
+    /// Accept any value as the document, as RFC 8259 does, rather than only
+    /// an object or an array as RFC 4627 did.
+    pub fn scalar_roots(mut self, allow: bool) -> Self {
+        self.scalar_roots = allow;
+        self
+    }
+
+    pub fn non_finite_numbers(mut self, policy: NonFiniteNumbers) -> Self {
+        self.non_finite_numbers = policy;
+        self
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    pub fn key_normalization(mut self, policy: KeyNormalization) -> Self {
+        self.key_normalization = policy;
+        self
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn decimal_numbers(mut self, policy: DecimalNumbers) -> Self {
+        self.decimal_numbers = policy;
+        self
+    }
+
+    /// Decode escapes JSON doesn't define with `decoder` instead of rejecting
+    /// them, e.g. `ParserOptions::new().escape_decoder(CEscapes)`.
+    pub fn escape_decoder(mut self, decoder: impl EscapeDecoder + 'static) -> Self {
+        self.escape_decoder = Some(Arc::new(decoder));
+        self
+    }
+
+    /// Parse the token `token` where a value is expected to `value`, e.g.
+    /// `ParserOptions::new().literal("undefined", JsonValue::Null)`.
+    pub fn literal(self, token: impl Into<String>, value: JsonValue) -> Self {
+        let token = token.into();
+        self.literal_parser(move |input: &str| {
+            input
+                .starts_with(token.as_str())
+                .then(|| (value.clone(), token.len()))
+        })
+    }
+
+    /// Parse the tokens JSON doesn't define that `parser` recognizes where a
+    /// value is expected, instead of rejecting them.
+    pub fn literal_parser(mut self, parser: impl LiteralParser + 'static) -> Self {
+        Arc::make_mut(&mut self.literals).push(Arc::new(parser));
+        self
+    }
+
+    /// Skip the members of every object, at any depth, whose key `filter`
+    /// doesn't keep: their values are checked without being built, which is
+    /// cheaper than pruning the parsed document.
+    pub fn key_filter(mut self, filter: KeyFilter) -> Self {
+        self.key_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Keep the source text of every number, see [`Number::as_str`].
+    ///
+    /// [`Number::as_str`]: crate::Number::as_str
+    pub fn keep_number_text(mut self, keep: bool) -> Self {
+        self.keep_number_text = keep;
+        self
+    }
+
+    /// Whether documents exercising `case` are accepted.
+    pub fn accepts(&self, case: Case) -> bool {
+        match case {
+            Case::NumberOverflow => self.number_overflow != NumberOverflow::Error,
+            Case::NumberUnderflow => self.number_underflow != NumberUnderflow::Error,
+            Case::LargeInteger => self.large_integers != LargeIntegers::Error,
+            Case::LoneSurrogate => self.lone_surrogates != LoneSurrogates::Error,
+            Case::ByteOrderMark => self.allow_bom,
+            Case::DeepNesting => self.max_depth.is_none(),
+            Case::DuplicateKey => self.duplicate_keys != DuplicateKeys::Error,
+            Case::InvalidUtf8 => self.invalid_utf8 != InvalidUtf8::Error,
+            Case::Utf16 => self.allow_utf16,
+        }
+    }
+
+    /// The named configuration these options match, if any.
+    pub fn profile(&self) -> Profile {
+        // destructured, so that no option can be left out of the comparison
+        let ParserOptions {
+            max_depth,
+            max_array_len,
+            max_string_len,
+            max_values,
+            max_duration,
+            number_overflow,
+            number_underflow,
+            negative_zero,
+            large_integers,
+            lone_surrogates,
+            invalid_unicode_escapes,
+            duplicate_keys,
+            missing_separators,
+            allow_bom,
+            invalid_utf8,
+            allow_utf16,
+            scalar_roots,
+            keep_number_text,
+            non_finite_numbers,
+            escape_decoder,
+            literals,
+            key_filter,
+            #[cfg(feature = "unicode-normalization")]
+            key_normalization,
+            #[cfg(feature = "decimal")]
+            decimal_numbers,
+        } = self;
+        if escape_decoder.is_some() || !literals.is_empty() || key_filter.is_some() {
+            return Profile::Custom;
+        }
+        #[cfg(feature = "unicode-normalization")]
+        if *key_normalization != KeyNormalization::None {
+            return Profile::Custom;
+        }
+        #[cfg(feature = "decimal")]
+        if *decimal_numbers != DecimalNumbers::Float {
+            return Profile::Custom;
+        }
+        let matches = |preset: &ParserOptions| {
+            *max_depth == preset.max_depth
+                && *max_array_len == preset.max_array_len
+                && *max_string_len == preset.max_string_len
+                && *max_values == preset.max_values
+                && *max_duration == preset.max_duration
+                && *number_overflow == preset.number_overflow
+                && *number_underflow == preset.number_underflow
+                && *negative_zero == preset.negative_zero
+                && *large_integers == preset.large_integers
+                && *lone_surrogates == preset.lone_surrogates
+                && *invalid_unicode_escapes == preset.invalid_unicode_escapes
+                && *duplicate_keys == preset.duplicate_keys
+                && *missing_separators == preset.missing_separators
+                && *allow_bom == preset.allow_bom
+                && *invalid_utf8 == preset.invalid_utf8
+                && *allow_utf16 == preset.allow_utf16
+                && *scalar_roots == preset.scalar_roots
+                && *keep_number_text == preset.keep_number_text
+                && *non_finite_numbers == preset.non_finite_numbers
+        };
+        if matches(&ParserOptions::default()) {
+            Profile::Default
+        } else if matches(&ParserOptions::strict()) {
+            Profile::Strict
+        } else if matches(&ParserOptions::untrusted()) {
+            Profile::Untrusted
+        } else {
+            Profile::Custom
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{
+        CEscapes, Case, DuplicateKeys, InvalidUnicodeEscapes, InvalidUtf8, KeyFilter,
+        LargeIntegers, LoneSurrogates, MissingSeparators, NegativeZero, NonFiniteNumbers,
+        NumberOverflow, NumberUnderflow, ParserOptions, Profile,
+    };
+
+    #[test]
+    fn test_shared() {
+        fn send_sync<T: Send + Sync>(_: &T) {}
+
+        let options = ParserOptions::untrusted()
+            .key_filter(KeyFilter::deny(["password"]))
+            .literal("undefined", crate::JsonValue::Null);
+        send_sync(&options);
+        // clones share the filter and hooks
+        let clone = options.clone();
+        assert!(Arc::ptr_eq(&options.literals, &clone.literals));
+        assert!(Arc::ptr_eq(
+            options.key_filter.as_ref().unwrap(),
+            clone.key_filter.as_ref().unwrap()
+        ));
+        // adding a hook to a clone doesn't change the original
+        let clone = clone.literal("nil", crate::JsonValue::Null);
+        assert_eq!((options.literals.len(), clone.literals.len()), (1, 2));
+
+        let options = &options;
+        std::thread::scope(|scope| {
+            for input in [r#"{"a": undefined}"#, r#"{"password": 1}"#] {
+                scope.spawn(move || assert!(crate::parse_with(input, options).is_ok()));
+            }
+        });
+    }
+
+    #[test]
+    fn test_profile() {
+        assert_eq!(ParserOptions::default().profile(), Profile::Default);
+        assert_eq!(ParserOptions::strict().profile(), Profile::Strict);
+        assert_eq!(ParserOptions::untrusted().profile(), Profile::Untrusted);
+        assert_eq!(
+            ParserOptions::strict()
+                .duplicate_keys(DuplicateKeys::FirstWins)
+                .profile(),
+            Profile::Custom
+        );
+        assert_eq!(
+            ParserOptions::default()
+                .non_finite_numbers(NonFiniteNumbers::Accept)
+                .profile(),
+            Profile::Custom
+        );
+
+        // every option away from its default
+        let toggles: [fn(ParserOptions) -> ParserOptions; 22] = [
+            |o| o.max_depth(Some(3)),
+            |o| o.max_array_len(Some(3)),
+            |o| o.max_string_len(Some(3)),
+            |o| o.max_values(Some(3)),
+            |o| o.max_duration(Some(Duration::from_secs(3))),
+            |o| o.number_overflow(NumberOverflow::KeepText),
+            |o| o.number_underflow(NumberUnderflow::Error),
+            |o| o.negative_zero(NegativeZero::Normalize),
+            |o| o.large_integers(LargeIntegers::Error),
+            |o| o.lone_surrogates(LoneSurrogates::Replace),
+            |o| o.invalid_unicode_escapes(InvalidUnicodeEscapes::Replace),
+            |o| o.duplicate_keys(DuplicateKeys::FirstWins),
+            |o| o.missing_separators(MissingSeparators::Skip),
+            |o| o.allow_bom(true),
+            |o| o.invalid_utf8(InvalidUtf8::Replace),
+            |o| o.allow_utf16(true),
+            |o| o.scalar_roots(false),
+            |o| o.keep_number_text(true),
+            |o| o.non_finite_numbers(NonFiniteNumbers::Null),
+            |o| o.escape_decoder(CEscapes),
+            |o| o.literal("undefined", crate::JsonValue::Null),
+            |o| o.key_filter(KeyFilter::deny(["password"])),
+        ];
+        for toggle in toggles {
+            let options = toggle(ParserOptions::default());
+            assert_eq!(options.profile(), Profile::Custom, "{:?}", options);
+        }
+        #[cfg(feature = "unicode-normalization")]
+        assert_eq!(
+            ParserOptions::default()
+                .key_normalization(super::KeyNormalization::Nfc)
+                .profile(),
+            Profile::Custom
+        );
+        #[cfg(feature = "decimal")]
+        assert_eq!(
+            ParserOptions::default()
+                .decimal_numbers(super::DecimalNumbers::Decimal)
+                .profile(),
+            Profile::Custom
+        );
+    }
+
+    #[test]
+    fn test_accepts() {
+        let strict = ParserOptions::strict();
+        assert!(Case::ALL.iter().all(|&case| !strict.accepts(case)));
+        assert!(ParserOptions::default()
+            .max_depth(None)
+            .accepts(Case::DeepNesting));
+        assert!(ParserOptions::default()
+            .invalid_utf8(InvalidUtf8::Replace)
+            .accepts(Case::InvalidUtf8));
+        assert!(ParserOptions::default()
+            .allow_utf16(true)
+            .accepts(Case::Utf16));
+    }
+}
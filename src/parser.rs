@@ -1,65 +1,792 @@
+#[cfg(feature = "unicode-normalization")]
 use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    fmt,
+    str::FromStr,
+    time::Instant,
+};
 
 use nom::{
     branch::alt,
-    bytes::complete::{escaped_transform, tag, take_till1, take_while, take_while_m_n},
-    character::complete::char,
-    combinator::map,
+    bytes::complete::{escaped, escaped_transform, tag, take_while, take_while_m_n},
+    character::complete::{char, digit0, digit1, one_of, satisfy},
+    combinator::{consumed, cut, map, opt, recognize},
     error::{context, ContextError, ParseError},
     multi::separated_list0,
-    number::complete::double,
-    sequence::{delimited, preceded, separated_pair},
-    IResult,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    Err, IResult,
+};
+
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+#[cfg(feature = "decimal")]
+use crate::options::DecimalNumbers;
+#[cfg(feature = "unicode-normalization")]
+use crate::options::KeyNormalization;
+#[cfg(feature = "stats")]
+use crate::stats::ParseStats;
+use crate::{
+    error::ErrorKind,
+    map::{DefaultMapKind, Key, Map, MapKind, Object},
+    number::Number,
+    options::{
+        DuplicateKeys, InvalidUnicodeEscapes, LargeIntegers, LoneSurrogates, MissingSeparators,
+        NegativeZero, NonFiniteNumbers, NumberOverflow, NumberUnderflow, ParserOptions,
+    },
+    pointer::JsonPointer,
 };
 
-#[derive(Debug, PartialEq)]
-pub enum JsonValue {
-    String(String),
+/// A JSON value, with strings of type `S` and objects from the [`MapKind`] `M`.
+///
+/// The defaults, `String` and [`DefaultMapKind`], are what [`parse`](crate::parse)
+/// builds; [`parse_generic`](crate::parse_generic) builds any other combination.
+///
+/// With the `stacker` feature, cloning, comparing and writing a value grow
+/// the stack as parsing does. Dropping one can't: it takes a few hundred
+/// bytes of stack per level of nesting, so a value nested tens of
+/// thousands deep must be dropped on a thread with a large enough stack.
+/// An iterative `Drop` would stop values from being moved out of by
+/// pattern, as in `if let JsonValue::String(s) = value`.
+pub enum JsonValue<S: Key = String, M: MapKind = DefaultMapKind> {
+    String(S),
     Bool(bool),
     Null,
-    Number(f64), // All parsed to floating point numbers
-    Object(HashMap<String, JsonValue>),
-    Array(Vec<JsonValue>),
+    Number(Number),
+    Object(M::Map<S, JsonValue<S, M>>),
+    Array(Vec<JsonValue<S, M>>),
+}
+
+// Implemented by hand: derived impls would require `M::Map<S, JsonValue<S, M>>`
+// to implement the trait, which recursively requires `JsonValue<S, M>` to.
+
+impl<S: Key + Clone, M: MapKind> Clone for JsonValue<S, M> {
+    fn clone(&self) -> Self {
+        grow_stack(|| match self {
+            JsonValue::String(s) => JsonValue::String(s.clone()),
+            JsonValue::Bool(b) => JsonValue::Bool(*b),
+            JsonValue::Null => JsonValue::Null,
+            JsonValue::Number(n) => JsonValue::Number(n.clone()),
+            JsonValue::Object(map) => {
+                let mut clone = M::Map::default();
+                for (key, val) in map.iter() {
+                    clone.insert(key.clone(), val.clone());
+                }
+                JsonValue::Object(clone)
+            }
+            JsonValue::Array(items) => JsonValue::Array(items.clone()),
+        })
+    }
+}
+
+impl<S: Key + fmt::Debug, M: MapKind> fmt::Debug for JsonValue<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Entries<'m, S: Key, M: MapKind>(&'m M::Map<S, JsonValue<S, M>>);
+
+        impl<S: Key + fmt::Debug, M: MapKind> fmt::Debug for Entries<'_, S, M> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_map().entries(self.0.iter()).finish()
+            }
+        }
+
+        grow_stack(|| match self {
+            JsonValue::String(s) => f.debug_tuple("String").field(s).finish(),
+            JsonValue::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            JsonValue::Null => f.write_str("Null"),
+            // `Number`'s own `Debug` already reads `Number(..)`
+            JsonValue::Number(n) => fmt::Debug::fmt(n, f),
+            JsonValue::Object(map) => f
+                .debug_tuple("Object")
+                .field(&Entries::<S, M>(map))
+                .finish(),
+            JsonValue::Array(items) => f.debug_tuple("Array").field(items).finish(),
+        })
+    }
+}
+
+impl<S: Key, M: MapKind> PartialEq for JsonValue<S, M> {
+    fn eq(&self, other: &Self) -> bool {
+        grow_stack(|| match (self, other) {
+            (JsonValue::String(a), JsonValue::String(b)) => a == b,
+            (JsonValue::Bool(a), JsonValue::Bool(b)) => a == b,
+            (JsonValue::Null, JsonValue::Null) => true,
+            (JsonValue::Number(a), JsonValue::Number(b)) => a == b,
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(key, val)| b.get(key) == Some(val))
+            }
+            (JsonValue::Array(a), JsonValue::Array(b)) => a == b,
+            _ => false,
+        })
+    }
+}
+
+impl<S: Key, M: MapKind> From<bool> for JsonValue<S, M> {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl<S: Key, M: MapKind> From<Number> for JsonValue<S, M> {
+    fn from(n: Number) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+/// `Null` for NaN, as it isn't a JSON number.
+impl<S: Key, M: MapKind> From<f64> for JsonValue<S, M> {
+    fn from(n: f64) -> Self {
+        Number::from_f64(n).map_or(JsonValue::Null, JsonValue::Number)
+    }
+}
+
+macro_rules! from_integer {
+    ($($ty:ty)*) => {$(
+        impl<S: Key, M: MapKind> From<$ty> for JsonValue<S, M> {
+            fn from(n: $ty) -> Self {
+                JsonValue::Number(n.into())
+            }
+        }
+    )*};
+}
+
+from_integer!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+impl<'s, S: Key + From<&'s str>, M: MapKind> From<&'s str> for JsonValue<S, M> {
+    fn from(s: &'s str) -> Self {
+        JsonValue::String(s.into())
+    }
+}
+
+impl<M: MapKind> From<String> for JsonValue<String, M> {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl<S: Key, M: MapKind> From<Vec<JsonValue<S, M>>> for JsonValue<S, M> {
+    fn from(items: Vec<JsonValue<S, M>>) -> Self {
+        JsonValue::Array(items)
+    }
+}
+
+/// [`parse_generic`](crate::parse_generic) with the default options.
+impl<S: Key + From<String>, M: MapKind> FromStr for JsonValue<S, M> {
+    type Err = crate::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, crate::ParseError> {
+        crate::parse_generic(s, &ParserOptions::default())
+    }
+}
+
+/// a value type the parser can build, from input borrowed for `'a`
+pub(crate) trait Dom<'a>: Sized {
+    type Object;
+
+    fn string(s: Cow<'a, str>) -> Self;
+    fn bool(b: bool) -> Self;
+    fn null() -> Self;
+    fn number(n: Number) -> Self;
+    fn array(items: Vec<Self>) -> Self;
+    fn object(object: Self::Object) -> Self;
+
+    /// collect the `(raw key, key, value)` members of an object in document
+    /// order, or fail at a raw key
+    fn members(
+        members: Vec<(&'a str, Cow<'a, str>, Self)>,
+        st: &ParseState,
+    ) -> Result<Self::Object, (&'a str, ErrorKind)>;
+
+    /// a value built outside the parser, such as a custom literal; `None` if
+    /// its members can't be collected
+    fn value(value: JsonValue, st: &ParseState) -> Option<Self> {
+        Some(match value {
+            JsonValue::Null => Self::null(),
+            JsonValue::Bool(b) => Self::bool(b),
+            JsonValue::Number(n) => Self::number(n),
+            JsonValue::String(s) => Self::string(Cow::Owned(s)),
+            JsonValue::Array(items) => Self::array(
+                items
+                    .into_iter()
+                    .map(|item| Self::value(item, st))
+                    .collect::<Option<_>>()?,
+            ),
+            JsonValue::Object(map) => {
+                let members = map
+                    .into_iter()
+                    .map(|(key, value)| Some(("", Cow::Owned(key), Self::value(value, st)?)))
+                    .collect::<Option<_>>()?;
+                Self::object(Self::members(members, st).ok()?)
+            }
+        })
+    }
+
+    /// the value in place of a completed one, `None` to drop it
+    fn transformed(self, _st: &ParseState) -> Option<Self> {
+        Some(self)
+    }
+}
+
+/// only checks the input
+impl Dom<'_> for () {
+    type Object = ();
+
+    fn string(_: Cow<str>) -> Self {}
+
+    fn bool(_: bool) -> Self {}
+
+    fn null() -> Self {}
+
+    fn number(_: Number) -> Self {}
+
+    fn array(_: Vec<Self>) -> Self {}
+
+    fn object(_: ()) -> Self {}
+
+    fn members<'a>(
+        _: Vec<(&'a str, Cow<str>, Self)>,
+        _: &ParseState,
+    ) -> Result<(), (&'a str, ErrorKind)> {
+        Ok(())
+    }
+}
+
+impl<'a, S: Key + From<String>, M: MapKind> Dom<'a> for JsonValue<S, M> {
+    type Object = M::Map<S, JsonValue<S, M>>;
+
+    fn string(s: Cow<'a, str>) -> Self {
+        JsonValue::String(S::from(s.into_owned()))
+    }
+
+    fn bool(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+
+    fn null() -> Self {
+        JsonValue::Null
+    }
+
+    fn number(n: Number) -> Self {
+        JsonValue::Number(n)
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        JsonValue::Array(items)
+    }
+
+    fn object(object: M::Map<S, JsonValue<S, M>>) -> Self {
+        JsonValue::Object(object)
+    }
+
+    fn members(
+        members: Vec<(&'a str, Cow<'a, str>, Self)>,
+        st: &ParseState,
+    ) -> Result<M::Map<S, JsonValue<S, M>>, (&'a str, ErrorKind)> {
+        let members = members
+            .into_iter()
+            .map(|(raw, key, val)| (raw, S::from(key.into_owned()), val));
+        collect_members(members, st)
+    }
+}
+
+/// the map of `members`, duplicate keys handled according to the options
+#[allow(clippy::type_complexity)]
+fn collect_members<'a, S: Key, M: MapKind>(
+    members: impl ExactSizeIterator<Item = (&'a str, S, JsonValue<S, M>)>,
+    st: &ParseState,
+) -> Result<M::Map<S, JsonValue<S, M>>, (&'a str, ErrorKind)> {
+    let mut map = M::Map::with_capacity(members.len());
+    for (raw, key, val) in members {
+        match st.options.duplicate_keys {
+            DuplicateKeys::LastWins => {
+                map.insert(key, val);
+            }
+            DuplicateKeys::FirstWins if map.get(&key).is_some() => {}
+            DuplicateKeys::FirstWins => {
+                map.insert(key, val);
+            }
+            DuplicateKeys::Error if map.get(&key).is_some() => {
+                return Err((raw, ErrorKind::DuplicateKey));
+            }
+            DuplicateKeys::Error => {
+                map.insert(key, val);
+            }
+        }
+    }
+    Ok(map)
+}
+
+/// a [`JsonValue`] whose strings and keys borrow the input unless they have
+/// escapes, see [`parse_borrowed`](crate::parse_borrowed)
+pub(crate) struct Borrowed<'a>(pub(crate) JsonValue<Cow<'a, str>>);
+
+impl<'a> Dom<'a> for Borrowed<'a> {
+    type Object = Map<Cow<'a, str>, JsonValue<Cow<'a, str>>>;
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Borrowed(JsonValue::String(s))
+    }
+
+    fn bool(b: bool) -> Self {
+        Borrowed(JsonValue::Bool(b))
+    }
+
+    fn null() -> Self {
+        Borrowed(JsonValue::Null)
+    }
+
+    fn number(n: Number) -> Self {
+        Borrowed(JsonValue::Number(n))
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        Borrowed(JsonValue::Array(
+            items.into_iter().map(|item| item.0).collect(),
+        ))
+    }
+
+    fn object(object: Self::Object) -> Self {
+        Borrowed(JsonValue::Object(object))
+    }
+
+    fn members(
+        members: Vec<(&'a str, Cow<'a, str>, Self)>,
+        st: &ParseState,
+    ) -> Result<Self::Object, (&'a str, ErrorKind)> {
+        let members = members.into_iter().map(|(raw, key, val)| (raw, key, val.0));
+        collect_members(members, st)
+    }
+}
+
+/// a [`JsonValue`] passed through the transform of the parse as it is built
+pub(crate) struct Transformed(pub(crate) JsonValue);
+
+impl<'a> Dom<'a> for Transformed {
+    type Object = <JsonValue as Dom<'a>>::Object;
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Transformed(JsonValue::string(s))
+    }
+
+    fn bool(b: bool) -> Self {
+        Transformed(JsonValue::bool(b))
+    }
+
+    fn null() -> Self {
+        Transformed(JsonValue::null())
+    }
+
+    fn number(n: Number) -> Self {
+        Transformed(JsonValue::number(n))
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        Transformed(JsonValue::array(
+            items.into_iter().map(|item| item.0).collect(),
+        ))
+    }
+
+    fn object(object: Self::Object) -> Self {
+        Transformed(JsonValue::object(object))
+    }
+
+    fn members(
+        members: Vec<(&'a str, Cow<'a, str>, Self)>,
+        st: &ParseState,
+    ) -> Result<Self::Object, (&'a str, ErrorKind)> {
+        let members = members
+            .into_iter()
+            .map(|(raw, key, val)| (raw, key, val.0))
+            .collect();
+        JsonValue::members(members, st)
+    }
+
+    fn transformed(self, st: &ParseState) -> Option<Self> {
+        match st.transform {
+            Some(transform) => {
+                (transform.borrow_mut())(&st.pointer.borrow(), self.0).map(Transformed)
+            }
+            None => Some(self),
+        }
+    }
+}
+
+/// callback of [`parse_with_transform`](crate::parse_with_transform)
+pub(crate) type Transform<'o> = dyn FnMut(&JsonPointer, JsonValue) -> Option<JsonValue> + 'o;
+
+/// array elements or object members between checks of `max_duration`,
+/// besides the check as each array or object starts
+const CLOCK_INTERVAL: usize = 1024;
+
+/// Largest integer magnitude an `f64` can't confuse with a neighbour
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0; // 2^53 - 1
+
+/// state of a single parse: the options and the current nesting depth
+pub(crate) struct ParseState<'o> {
+    options: &'o ParserOptions,
+    depth: Cell<usize>,
+    /// array elements dropped by `max_array_len`
+    skipped: Cell<usize>,
+    /// values parsed so far, for `max_values`
+    values: Cell<usize>,
+    /// when `max_duration` runs out
+    deadline: Option<Instant>,
+    /// called on each completed value
+    transform: Option<&'o RefCell<Transform<'o>>>,
+    /// pointer of the value being parsed, only kept up with a `transform`
+    pointer: RefCell<JsonPointer>,
+    #[cfg(feature = "stats")]
+    stats: Cell<ParseStats>,
+    /// log records held until `release_logs`
+    #[cfg(feature = "log")]
+    held_logs: Option<RefCell<Vec<(log::Level, String)>>>,
+}
+
+impl<'o> ParseState<'o> {
+    pub(crate) fn new(options: &'o ParserOptions) -> Self {
+        ParseState {
+            options,
+            depth: Cell::new(0),
+            skipped: Cell::new(0),
+            values: Cell::new(0),
+            deadline: options
+                .max_duration
+                .and_then(|max| Instant::now().checked_add(max)),
+            transform: None,
+            pointer: RefCell::new(JsonPointer::root()),
+            #[cfg(feature = "stats")]
+            stats: Cell::default(),
+            #[cfg(feature = "log")]
+            held_logs: None,
+        }
+    }
+
+    pub(crate) fn with_transform(
+        options: &'o ParserOptions,
+        transform: &'o RefCell<Transform<'o>>,
+    ) -> Self {
+        ParseState {
+            transform: Some(transform),
+            ..ParseState::new(options)
+        }
+    }
+
+    /// fresh state to parse the input again, within the same time budget
+    pub(crate) fn restart(&self) -> Self {
+        ParseState {
+            deadline: self.deadline,
+            transform: self.transform,
+            ..ParseState::new(self.options)
+        }
+    }
+
+    /// hold the log records of the parse until `release_logs`, for a pass
+    /// whose result may be thrown away
+    pub(crate) fn holding_logs(self) -> Self {
+        ParseState {
+            #[cfg(feature = "log")]
+            held_logs: Some(RefCell::default()),
+            ..self
+        }
+    }
+
+    /// log the records held since `holding_logs`
+    pub(crate) fn release_logs(&self) {
+        #[cfg(feature = "log")]
+        if let Some(held) = &self.held_logs {
+            for (level, record) in held.take() {
+                log::log!(target: "nom_json_parser", level, "{}", record);
+            }
+        }
+    }
+
+    #[cfg(feature = "log")]
+    pub(crate) fn log(&self, level: log::Level, args: fmt::Arguments<'_>) {
+        match &self.held_logs {
+            Some(held) if log::log_enabled!(target: "nom_json_parser", level) => {
+                held.borrow_mut().push((level, args.to_string()));
+            }
+            Some(_) => {}
+            None => log::log!(target: "nom_json_parser", level, "{}", args),
+        }
+    }
+
+    pub(crate) fn skipped(&self) -> usize {
+        self.skipped.get()
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn stats(&self) -> ParseStats {
+        self.stats.get()
+    }
+
+    /// update the counters of the `stats` feature
+    #[cfg(feature = "stats")]
+    fn record(&self, f: impl FnOnce(&mut ParseStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    /// run `f` one nesting level deeper, failing once `max_depth` is exceeded
+    /// or `max_duration` has passed
+    fn nested<'a, O, E: ParseError<&'a str> + ContextError<&'a str>>(
+        &self,
+        input: &'a str,
+        f: impl FnOnce(&'a str) -> IResult<&'a str, O, E>,
+    ) -> IResult<&'a str, O, E> {
+        let depth = self.depth.get() + 1;
+        if let Some(max) = self.options.max_depth {
+            if depth > max {
+                debug!(self => "depth limit {} exceeded", max);
+                return failure(input, ErrorKind::DepthLimitExceeded);
+            }
+        }
+        self.check_clock(input)?;
+        self.depth.set(depth);
+        let rst = f(input);
+        self.depth.set(depth - 1);
+        rst
+    }
+
+    /// fail once `max_duration` has passed
+    fn check_clock<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> IResult<&'a str, (), E> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            debug!(self => "time budget exceeded");
+            return failure(input, ErrorKind::Timeout);
+        }
+        Ok((input, ()))
+    }
+
+    /// run `f` on the member or element `token` of the current value
+    fn child<R>(&self, token: impl fmt::Display, f: impl FnOnce() -> R) -> R {
+        if self.transform.is_none() {
+            return f();
+        }
+        let len = self.pointer.borrow().len();
+        self.pointer.borrow_mut().push(&token.to_string());
+        let rst = f();
+        self.pointer.borrow_mut().truncate(len);
+        rst
+    }
+}
+
+/// unrecoverable error, labelled so that it can be classified as `kind`
+fn failure<'a, O, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    kind: ErrorKind,
+) -> IResult<&'a str, O, E> {
+    Err(Err::Failure(E::add_context(
+        input,
+        kind.label(),
+        E::from_error_kind(input, nom::error::ErrorKind::Verify),
+    )))
 }
 
 /// whitespace
 /// nom::character::complete::multispace0
-fn parse_whitespace<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
-    take_while(|ch| ch == ' ' || ch == '\n' || ch == '\r' || ch == '\t')(input)
+/// scanned as bytes, like [`normal_str`]; most calls are between tokens with
+/// no whitespace at all, which the first byte settles
+pub(crate) fn parse_whitespace<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let is_space = |b: &u8| matches!(b, b' ' | b'\n' | b'\r' | b'\t');
+    if !input.as_bytes().first().is_some_and(is_space) {
+        return Ok((input, ""));
+    }
+    let end = input
+        .bytes()
+        .position(|b| !is_space(&b))
+        .unwrap_or(input.len());
+    Ok((&input[end..], &input[..end]))
+}
+
+/// number literal
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+/// recognize：返回匹配到的原始文本
+fn number_literal<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        opt(char('-')),
+        alt((
+            tag("0"),
+            recognize(pair(satisfy(|ch| ('1'..='9').contains(&ch)), digit0)),
+        )),
+        opt(pair(char('.'), digit1)),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    )))(input)
+}
+
+/// `NaN`, `Infinity`, `-Infinity`
+fn non_finite<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Number, E> {
+    alt((
+        map(tag("NaN"), |_| Number::float(f64::NAN)),
+        map(tag("Infinity"), |_| Number::float(f64::INFINITY)),
+        map(tag("-Infinity"), |_| Number::float(f64::NEG_INFINITY)),
+    ))(input)
 }
 
 /// number
-/// nom::number::complete::double
-fn parse_number<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, f64, E> {
-    double(input)
+/// consumed：同时返回消耗的原始文本，按选项保留
+pub(crate) fn parse_number<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, Number, E> {
+    let (rest, (text, n)) = consumed(|i| number_value(i, st))(input)?;
+    if st.options.keep_number_text {
+        Ok((rest, n.with_text(text)))
+    } else {
+        Ok((rest, n))
+    }
+}
+
+/// integers in range are kept exact, other numbers become `f64`
+/// out-of-range values and non-finite tokens are handled according to the options
+fn number_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, Number, E> {
+    if st.options.non_finite_numbers == NonFiniteNumbers::Accept {
+        if let Ok((rest, n)) = non_finite::<E>(input) {
+            return Ok((rest, n));
+        }
+    }
+    let (rest, text) = number_literal(input)?;
+    let integer = !text.contains(['.', 'e', 'E']);
+    if let Some(n) = Number::integer(text).filter(|_| integer) {
+        return Ok((rest, n));
+    }
+    #[cfg(feature = "decimal")]
+    if !integer && st.options.decimal_numbers != DecimalNumbers::Float {
+        match Number::decimal(text) {
+            Some(n) => return Ok((rest, n)),
+            None if st.options.decimal_numbers == DecimalNumbers::Strict => {
+                return failure(input, ErrorKind::NumberOutOfRange);
+            }
+            None => {}
+        }
+    }
+    let n: f64 = match text.parse() {
+        Ok(n) => n,
+        Err(_) => {
+            return Err(Err::Error(E::from_error_kind(
+                input,
+                nom::error::ErrorKind::Float,
+            )))
+        }
+    };
+    let options = st.options;
+    if n.is_infinite() {
+        match options.number_overflow {
+            NumberOverflow::Infinity => {}
+            NumberOverflow::KeepText => return Ok((rest, Number::float(n).with_text(text))),
+            NumberOverflow::Error => return failure(input, ErrorKind::NumberOutOfRange),
+        }
+    }
+    let mantissa = text.find(['e', 'E']).map_or(text, |i| &text[..i]);
+    if n == 0.0
+        && mantissa.bytes().any(|b| (b'1'..=b'9').contains(&b))
+        && options.number_underflow == NumberUnderflow::Error
+    {
+        return failure(input, ErrorKind::NumberOutOfRange);
+    }
+    if n == 0.0 && n.is_sign_negative() && options.negative_zero == NegativeZero::Normalize {
+        return Ok((
+            rest,
+            Number::integer("0")
+                .filter(|_| integer)
+                .unwrap_or(Number::float(0.0)),
+        ));
+    }
+    if integer && n.abs() > MAX_SAFE_INTEGER {
+        if options.large_integers == LargeIntegers::Error {
+            return failure(input, ErrorKind::IntegerTooLarge);
+        }
+        warn!(st => "integer {} rounded to {:?}", text, n);
+    }
+    Ok((rest, Number::float(n)))
 }
 
 /// string
 /// alt：空字符，非空字符
-fn parse_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// cut：开引号之后不再回溯，错误停留在字符串内部
+pub(crate) fn parse_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, String, E> {
-    context(
+    st: &ParseState,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    let (rest, s) = context(
         "string",
         alt((
-            map(tag("\"\""), |_| "".to_owned()),
-            delimited(tag("\""), parse_str, tag("\"")),
+            map(tag("\"\""), |_| Cow::Borrowed("")),
+            preceded(tag("\""), cut(terminated(|i| parse_str(i, st), tag("\"")))),
         )),
-    )(input)
+    )(input)?;
+    if st.options.max_string_len.is_some_and(|max| s.len() > max) {
+        return failure(input, ErrorKind::StringTooLong);
+    }
+    #[cfg(feature = "stats")]
+    st.record(|stats| stats.string_bytes += s.len());
+    Ok((rest, s))
+}
+
+/// a string, checked as [`parse_string`] checks it without decoding it: the
+/// text between its quotes
+pub(crate) fn skip_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, &'a str, E> {
+    let (rest, raw) = context(
+        "string",
+        alt((
+            map(tag("\"\""), |_| ""),
+            preceded(
+                tag("\""),
+                cut(terminated(
+                    escaped(normal_str, '\\', |i| escapable(i, st)),
+                    tag("\""),
+                )),
+            ),
+        )),
+    )(input)?;
+    // no longer than its text once decoded, so only a long one is decoded
+    if st.options.max_string_len.is_some_and(|max| raw.len() > max) {
+        let (rest, _) = parse_string(input, st)?;
+        return Ok((rest, raw));
+    }
+    Ok((rest, raw))
 }
 
 /// normal string value
 /// take_till：与take_while相反，take_til是截止条件
 /// 必须使用take_till1，至少有一个
-fn normal_str<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
-    take_till1(|ch: char| ch == '\\' || ch == '"' || ch.is_ascii_control())(input)
+/// the stop characters are ASCII, so bytes are scanned instead of decoding
+/// `char`s: an ASCII byte of a `&str` is always on a char boundary
+fn normal_str<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    let end = input
+        .bytes()
+        .position(|b| b == b'\\' || b == b'"' || b.is_ascii_control())
+        .unwrap_or(input.len());
+    if end == 0 {
+        return Err(Err::Error(E::from_error_kind(
+            input,
+            nom::error::ErrorKind::TakeTill1,
+        )));
+    }
+    Ok((&input[end..], &input[..end]))
 }
 
 /// escapable characters
 /// 4 hex digits 是 &str，其他的也只能用tag了，不能用char
 fn escapable<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, char, E> {
+    st: &ParseState,
+) -> IResult<&'a str, char, E> {
     context(
         "escape",
         alt((
@@ -71,144 +798,478 @@ fn escapable<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
             map(char('n'), |_| '\n'),
             map(char('r'), |_| '\r'),
             map(char('t'), |_| '\t'),
-            hex_char,
+            |i| hex_char(i, st),
+            |i| custom_escape(i, st),
         )),
     )(input)
 }
 
+/// escapes JSON doesn't define, left to the options' decoder
+fn custom_escape<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, char, E> {
+    let decoded = st
+        .options
+        .escape_decoder
+        .as_ref()
+        .and_then(|decoder| decoder.decode(input));
+    match decoded {
+        Some((ch, len)) if len > 0 => match input.get(len..) {
+            Some(rest) => Ok((rest, ch)),
+            None => Err(Err::Error(E::from_error_kind(
+                input,
+                nom::error::ErrorKind::Escaped,
+            ))),
+        },
+        _ => Err(Err::Error(E::from_error_kind(
+            input,
+            nom::error::ErrorKind::Escaped,
+        ))),
+    }
+}
+
 /// 4 hex digits
 /// preceded：
 /// peek：不消耗输入
+/// a high surrogate followed by a `\u` escape of a low one is a UTF-16 pair,
+/// combined into one `char`; other surrogates are not `char`s, they are
+/// handled according to the options, as are escapes with fewer than 4 hex
+/// digits
 fn hex_char<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, char, E> {
+    st: &ParseState,
+) -> IResult<&'a str, char, E> {
     context(
         "hex_char",
-        preceded(
-            tag("u"),
-            map(
-                take_while_m_n(4, 4, |ch: char| ch.is_ascii_hexdigit() || ch == 'u'),
-                |s: &str| std::char::from_u32(u32::from_str_radix(s, 16).unwrap()).unwrap(),
-            ),
-        ),
+        preceded(tag("u"), |i| {
+            let (rest, s) =
+                match take_while_m_n::<_, _, E>(4, 4, |ch: char| ch.is_ascii_hexdigit())(i) {
+                    Ok(ok) => ok,
+                    Err(_) => {
+                        return match st.options.invalid_unicode_escapes {
+                            InvalidUnicodeEscapes::Replace => {
+                                let (rest, _) = take_while(|ch: char| ch.is_ascii_hexdigit())(i)?;
+                                trace!(st => "replaced invalid unicode escape");
+                                Ok((rest, '\u{FFFD}'))
+                            }
+                            InvalidUnicodeEscapes::Error => {
+                                failure(i, ErrorKind::InvalidUnicodeEscape)
+                            }
+                        }
+                    }
+                };
+            let code = u32::from_str_radix(s, 16).unwrap_or(0xFFFD);
+            let (rest, code) = match (code, low_surrogate(rest)) {
+                (0xD800..=0xDBFF, Some((rest, low))) => {
+                    (rest, 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00))
+                }
+                _ => (rest, code),
+            };
+            match std::char::from_u32(code) {
+                Some(ch) => Ok((rest, ch)),
+                None => match st.options.lone_surrogates {
+                    LoneSurrogates::Replace => {
+                        trace!(st => "replaced lone surrogate \\u{}", s);
+                        Ok((rest, '\u{FFFD}'))
+                    }
+                    LoneSurrogates::Error => failure(i, ErrorKind::LoneSurrogate),
+                },
+            }
+        }),
     )(input)
 }
 
+/// the code unit of a `\uXXXX` escape of a low surrogate, and the input
+/// after it
+fn low_surrogate(input: &str) -> Option<(&str, u32)> {
+    let rest = input.strip_prefix("\\u")?;
+    let hex = rest.get(..4)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let code = u32::from_str_radix(hex, 16).ok()?;
+    (0xDC00..=0xDFFF)
+        .contains(&code)
+        .then_some((&rest[4..], code))
+}
+
 /// str
 /// escaped：
 /// - normal 常规字符判断函数
 /// - control 控制字符
 /// - escapade 可转义字符
+///
+/// the content is borrowed when it has no escapes
 fn parse_str<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, String, E> {
-    escaped_transform(normal_str, '\\', escapable)(input)
+    st: &ParseState,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    if let Ok((rest, s)) = normal_str::<()>(input) {
+        if rest.starts_with('"') {
+            return Ok((rest, Cow::Borrowed(s)));
+        }
+    }
+    map(
+        escaped_transform(normal_str, '\\', |i| escapable(i, st)),
+        Cow::Owned,
+    )(input)
 }
 
 /// bool
 /// map 映射结果，总固定一个结果，可以使用 `value(true, tag("true"))` 简写
 /// map 实际是 `Result::map` 的函数包装，延迟parse
 /// value 也是，返回的值有value clone而来
-fn parse_bool<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, bool, E> {
+pub(crate) fn parse_bool<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, bool, E> {
     alt((map(tag("false"), |_| false), map(tag("true"), |_| true)))(input)
 }
 
-fn parse_null<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, JsonValue, E> {
-    map(tag("null"), |_| JsonValue::Null)(input)
+fn parse_null<'a, V: Dom<'a>, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, V, E> {
+    map(tag("null"), |_| V::null())(input)
 }
 
-fn parse_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// a token of one of the `literals` options
+fn parse_literal<'a, V: Dom<'a>, E: ParseError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, JsonValue, E> {
-    context(
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    for literal in st.options.literals.iter() {
+        let Some((value, len)) = literal.parse(input).filter(|&(_, len)| len > 0) else {
+            continue;
+        };
+        if let (Some(rest), Some(value)) = (input.get(len..), V::value(value, st)) {
+            return Ok((rest, value));
+        }
+    }
+    Err(Err::Error(E::from_error_kind(
+        input,
+        nom::error::ErrorKind::Tag,
+    )))
+}
+
+/// Bytes of stack that must remain before recursing into another value.
+#[cfg(feature = "stacker")]
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// Size of each new stack segment allocated when the red zone is reached.
+#[cfg(feature = "stacker")]
+const STACK_SEGMENT_SIZE: usize = 1024 * 1024;
+
+/// `f`, one level into a value; with the `stacker` feature the stack grows
+/// on demand, so deeply nested values don't overflow it
+#[inline]
+pub(crate) fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "stacker")]
+    return stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, f);
+    #[cfg(not(feature = "stacker"))]
+    f()
+}
+
+/// value
+fn parse_value<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    grow_stack(|| parse_value_inner(input, st))
+}
+
+fn parse_value_inner<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    let rst = context(
         "value",
         delimited(
             parse_whitespace,
             alt((
-                map(parse_string, JsonValue::String),
-                map(parse_bool, JsonValue::Bool),
-                map(parse_number, JsonValue::Number),
-                parse_null,
-                map(parse_object, JsonValue::Object),
-                map(parse_array, JsonValue::Array),
+                |i| parse_scalar(i, st),
+                map(|i| parse_object::<V, E>(i, st), V::object),
+                map(|i| parse_array(i, st), V::array),
             )),
             parse_whitespace,
         ),
-    )(input)
+    )(input)?;
+    let values = st.values.get() + 1;
+    if st.options.max_values.is_some_and(|max| values > max) {
+        return failure(
+            input.trim_start_matches([' ', '\n', '\r', '\t']),
+            ErrorKind::TooManyValues,
+        );
+    }
+    st.values.set(values);
+    Ok(rst)
 }
 
-fn parse_array<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// any value but an array or object
+fn parse_scalar<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, Vec<JsonValue>, E> {
-    context(
-        "array",
-        delimited(
-            char('['),
-            separated_list0(
-                char(','),
-                delimited(parse_whitespace, parse_value, parse_whitespace),
-            ),
-            char(']'),
-        ),
-    )(input)
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    alt((
+        |i| parse_literal(i, st),
+        map(|i| parse_string(i, st), V::string),
+        map(parse_bool, V::bool),
+        |i| parse_non_finite_null(i, st),
+        map(|i| parse_number(i, st), V::number),
+        parse_null,
+    ))(input)
 }
 
-fn parse_object<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// a non-finite token, with [`NonFiniteNumbers::Null`]
+pub(crate) fn parse_non_finite_null<'a, V: Dom<'a>, E: ParseError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, HashMap<String, JsonValue>, E> {
-    context(
-        "object",
-        delimited(
-            char('{'),
-            map(
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    if st.options.non_finite_numbers != NonFiniteNumbers::Null {
+        return Err(Err::Error(E::from_error_kind(
+            input,
+            nom::error::ErrorKind::Tag,
+        )));
+    }
+    map(non_finite, |_| V::null())(input)
+}
+
+fn parse_array<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, Vec<V>, E> {
+    context("array", |input| {
+        let (input, _) = char('[')(input)?;
+        // elements past `max_array_len` are only checked, building `()`
+        let len = Cell::new(0);
+        let element = |i| {
+            len.set(len.get() + 1);
+            if len.get() % CLOCK_INTERVAL == 0 {
+                st.check_clock(i)?;
+            }
+            match st.options.max_array_len {
+                Some(max) if len.get() > max => {
+                    let (i, ()) = parse_value(i, st)?;
+                    st.skipped.set(st.skipped.get() + 1);
+                    Ok((i, None))
+                }
+                _ => st.child(len.get() - 1, || {
+                    parse_value(i, st).map(|(i, value)| (i, V::transformed(value, st)))
+                }),
+            }
+        };
+        let (input, items) = st.nested(
+            input,
+            cut(terminated(
                 separated_list0(
-                    tag(","),
-                    separated_pair(
-                        delimited(parse_whitespace, parse_string, parse_whitespace), // key
-                        char(':'),                                                   // :
-                        parse_value,                                                 // value
+                    char(','),
+                    delimited(
+                        parse_whitespace,
+                        context("array element", element),
+                        parse_whitespace,
                     ),
                 ),
-                |list| {
-                    list.into_iter()
-                        .map(|(key, val)| (key.to_owned(), val))
-                        .collect()
-                },
-            ),
-            char('}'),
-        ),
-    )(input)
+                char(']'),
+            )),
+        )?;
+        // `flatten` has no exact size hint, so reserve the length up front
+        let mut values = Vec::with_capacity(items.len());
+        values.extend(items.into_iter().flatten());
+        #[cfg(feature = "stats")]
+        st.record(|stats| stats.max_array_len = stats.max_array_len.max(values.len()));
+        Ok((input, values))
+    })(input)
+}
+
+/// object members and the closing brace, as `(raw key, key, value)`
+/// a missing `:` or `,` is reported after the key of its member, or skipped
+/// up to the next `,` or `}` according to the options
+#[allow(clippy::type_complexity)]
+fn parse_members<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, Vec<(&'a str, Cow<'a, str>, V)>, E> {
+    let mut members = Vec::new();
+    let (mut input, _) = parse_whitespace(input)?;
+    if let Some(rest) = input.strip_prefix('}') {
+        return Ok((rest, members));
+    }
+    // members so far, to check the clock every `CLOCK_INTERVAL` of them
+    let mut count = 0usize;
+    loop {
+        count += 1;
+        let (i, _) = parse_whitespace(input)?;
+        if count % CLOCK_INTERVAL == 0 {
+            st.check_clock(i)?;
+        }
+        let (i, (raw, key)) = context("object key", consumed(|i| parse_string(i, st)))(i)?;
+        let (i, _) = parse_whitespace(i)?;
+        let i = match i.strip_prefix(':') {
+            Some(i) => {
+                let keep = st
+                    .options
+                    .key_filter
+                    .as_ref()
+                    .map_or(true, |f| f.keeps(&key));
+                let i = if keep {
+                    let (i, val) = st.child(&key, || {
+                        cut(context("member value", |i| parse_value(i, st)))(i)
+                            .map(|(i, val)| (i, V::transformed(val, st)))
+                    })?;
+                    if let Some(val) = val {
+                        members.push((raw, key, val));
+                    }
+                    i
+                } else {
+                    // filtered out: only checked, building `()`
+                    let (i, ()) = cut(context("member value", |i| parse_value(i, st)))(i)?;
+                    i
+                };
+                if i.starts_with('"') {
+                    missing_separator(raw, i, ErrorKind::MissingComma, st)?
+                } else {
+                    i
+                }
+            }
+            None => missing_separator(raw, i, ErrorKind::MissingColon, st)?,
+        };
+        match i.chars().next() {
+            Some(',') => input = &i[1..],
+            Some('}') => return Ok((&i[1..], members)),
+            _ => {
+                return Err(Err::Error(E::from_error_kind(
+                    i,
+                    nom::error::ErrorKind::Char,
+                )))
+            }
+        }
+    }
+}
+
+/// fail with `kind` after the key `raw`, or skip to the next `,` or `}` of
+/// the object
+fn missing_separator<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    raw: &'a str,
+    input: &'a str,
+    kind: ErrorKind,
+    st: &ParseState,
+) -> Result<&'a str, Err<E>> {
+    if st.options.missing_separators == MissingSeparators::Error {
+        let err = E::from_error_kind(input, nom::error::ErrorKind::Verify);
+        let err = E::add_context(input, kind.label(), err);
+        return Err(Err::Failure(E::add_context(raw, ErrorKind::KEY_LABEL, err)));
+    }
+    warn!(st => "{} after key {}, skipped to the next member", kind, raw);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, b) in input.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => {}
+            b'[' | b'{' => depth += 1,
+            b',' | b']' | b'}' if depth == 0 => return Ok(&input[i..]),
+            b']' | b'}' => depth -= 1,
+            _ => {}
+        }
+    }
+    Ok(&input[input.len()..])
+}
+
+fn parse_object<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+    st: &ParseState,
+) -> IResult<&'a str, V::Object, E> {
+    context("object", |input| {
+        let (input, _) = char('{')(input)?;
+        let (input, list) = st.nested(input, cut(|i| parse_members(i, st)))?;
+        #[cfg(feature = "stats")]
+        st.record(|stats| stats.max_object_len = stats.max_object_len.max(list.len()));
+        let mut members = Vec::with_capacity(list.len());
+        #[cfg(feature = "unicode-normalization")]
+        let mut spellings = HashMap::new();
+        for (raw, key, val) in list {
+            #[cfg(feature = "unicode-normalization")]
+            let key = match st.options.key_normalization {
+                KeyNormalization::None => key,
+                KeyNormalization::Nfc => Cow::Owned(key.nfc().collect()),
+                KeyNormalization::Strict => {
+                    let nfc: String = key.nfc().collect();
+                    match spellings.insert(nfc.clone(), key.clone()) {
+                        Some(prev) if prev != key => {
+                            return failure(raw, ErrorKind::KeyNormalizationConflict)
+                        }
+                        _ => Cow::Owned(nfc),
+                    }
+                }
+            };
+            members.push((raw, key, val));
+        }
+        match V::members(members, st) {
+            Ok(object) => Ok((input, object)),
+            Err((raw, kind)) => failure(raw, kind),
+        }
+    })(input)
 }
 
 /// JSON root element
-/// only object or array
-pub fn parse_root<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+/// only object or array, unless the options allow scalars
+pub(crate) fn parse_root_dom<'a, V: Dom<'a>, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
-) -> IResult<&str, JsonValue, E> {
-    delimited(
+    st: &ParseState,
+) -> IResult<&'a str, V, E> {
+    let input = match input.strip_prefix('\u{FEFF}') {
+        Some(rest) if st.options.allow_bom => {
+            trace!(st => "skipped byte order mark");
+            rest
+        }
+        _ => input,
+    };
+    let (input, root) = delimited(
         parse_whitespace,
-        alt((
-            map(parse_object, JsonValue::Object),
-            map(parse_array, JsonValue::Array),
-        )),
+        |i| {
+            let mut container = alt((
+                map(|i| parse_object::<V, E>(i, st), V::object),
+                map(|i| parse_array(i, st), V::array),
+            ));
+            if st.options.scalar_roots {
+                alt((container, |i| parse_scalar(i, st)))(i)
+            } else {
+                container(i)
+            }
+        },
         parse_whitespace,
-    )(input)
+    )(input)?;
+    Ok((input, root.transformed(st).unwrap_or_else(V::null)))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::borrow::Cow;
 
     use nom::{
         error::{convert_error, Error},
         Finish,
     };
 
-    use crate::parser::{
-        hex_char, normal_str, parse_array, parse_bool, parse_object, parse_str, parse_string,
-        parse_value, JsonValue,
+    use crate::{
+        error::ErrorKind,
+        map::Map,
+        number::Number,
+        options::{
+            CEscapes, DuplicateKeys, InvalidUnicodeEscapes, LargeIntegers, LoneSurrogates,
+            MissingSeparators, NegativeZero, NonFiniteNumbers, NumberOverflow, NumberUnderflow,
+            ParserOptions, Profile,
+        },
+        parse_borrowed, parse_with, parse_with_transform,
+        parser::{
+            hex_char, normal_str, parse_array, parse_bool, parse_number, parse_object,
+            parse_root_dom, parse_str, parse_string, parse_value, JsonValue, ParseState,
+        },
+        KeyFilter,
     };
 
+    fn num(n: f64) -> Number {
+        Number::from_f64(n).unwrap()
+    }
+
     #[test]
     fn test_normal_str() {
         assert_eq!(normal_str::<Error<&str>>("abc\n"), Ok(("\n", "abc")));
@@ -217,26 +1278,455 @@ mod tests {
 
     #[test]
     fn test_hex_char() {
-        assert_eq!(hex_char::<Error<&str>>("u1234abc"), Ok(("abc", '\u{1234}')));
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert_eq!(
+            hex_char::<Error<&str>>("u1234abc", &st),
+            Ok(("abc", '\u{1234}'))
+        );
+        assert!(hex_char::<Error<&str>>("uD800", &st).is_err());
+
+        let options = ParserOptions::default().lone_surrogates(LoneSurrogates::Replace);
+        let st = ParseState::new(&options);
+        assert_eq!(hex_char::<Error<&str>>("uDC00", &st), Ok(("", '\u{FFFD}')));
+        // a high surrogate not followed by a low one
+        assert_eq!(
+            hex_char::<Error<&str>>("uD83D\\u0041", &st),
+            Ok(("\\u0041", '\u{FFFD}'))
+        );
+        assert_eq!(
+            hex_char::<Error<&str>>("uDE00\\uD83D", &st),
+            Ok(("\\uD83D", '\u{FFFD}'))
+        );
+    }
+
+    #[test]
+    fn test_surrogate_pair() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert_eq!(
+            hex_char::<Error<&str>>("uD83D\\uDE00x", &st),
+            Ok(("x", '\u{1F600}'))
+        );
+        assert_eq!(
+            hex_char::<Error<&str>>("udbff\\udfff", &st),
+            Ok(("", '\u{10FFFF}'))
+        );
+        assert_eq!(
+            crate::parse(r#"["a\ud83d\ude00b", "\uD834\uDD1E"]"#).unwrap(),
+            JsonValue::Array(vec![
+                JsonValue::String("a\u{1F600}b".to_owned()),
+                JsonValue::String("\u{1D11E}".to_owned()),
+            ])
+        );
+
+        for (input, offset) in [
+            (r#"["\ud83d"]"#, 4),
+            (r#"["\ud83d\u0041"]"#, 4),
+            (r#"["\ud83d\ud83d"]"#, 4),
+            (r#"["\ude00"]"#, 4),
+        ] {
+            let err = crate::parse(input).unwrap_err();
+            assert_eq!(
+                (err.kind(), err.offset()),
+                (ErrorKind::LoneSurrogate, offset),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_hex_char() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert!(hex_char::<Error<&str>>("uuuuu", &st).is_err());
+        assert!(hex_char::<Error<&str>>("u12G4", &st).is_err());
+        assert!(hex_char::<Error<&str>>("u12", &st).is_err());
+
+        let options =
+            ParserOptions::default().invalid_unicode_escapes(InvalidUnicodeEscapes::Replace);
+        let st = ParseState::new(&options);
+        assert_eq!(
+            hex_char::<Error<&str>>("u12G4", &st),
+            Ok(("G4", '\u{FFFD}'))
+        );
+        assert_eq!(
+            hex_char::<Error<&str>>("uuuuu", &st),
+            Ok(("uuuu", '\u{FFFD}'))
+        );
+        assert_eq!(hex_char::<Error<&str>>("u00e9", &st), Ok(("", 'é')));
     }
 
     #[test]
     fn test_str() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
         assert_eq!(
-            parse_str::<Error<&str>>(r#"abc\n \u1234"#),
-            Ok(("", "abc\n \u{1234}".to_owned()))
+            parse_str::<Error<&str>>(r#"abc\n \u1234"#, &st),
+            Ok(("", "abc\n \u{1234}".into()))
         )
     }
 
+    #[test]
+    fn test_escape_decoder() {
+        let input = r#"["\x41\0\'", "\n\u00e9"]"#;
+        assert_eq!(
+            parse_with(input, &ParserOptions::default())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidEscape
+        );
+        let options = ParserOptions::default().escape_decoder(CEscapes);
+        assert_eq!(options.profile(), Profile::Custom);
+        assert_eq!(
+            parse_with(input, &options).unwrap(),
+            JsonValue::Array(vec!["A\0'".into(), "\né".into()])
+        );
+        for input in [r#"["\x4"]"#, r#"["\xZZ"]"#, r#"["\q"]"#, r#"["\u12"]"#] {
+            let err = parse_with(input, &options).unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                ErrorKind::InvalidEscape | ErrorKind::InvalidUnicodeEscape
+            ));
+        }
+
+        // any closure; JSON's own escapes aren't passed to it
+        let options =
+            ParserOptions::default().escape_decoder(|input: &str| match input.strip_prefix('e') {
+                Some(_) => Some(('\u{1B}', 1)),
+                None => Some(('?', 0)),
+            });
+        assert_eq!(
+            parse_with(r#"["\e\t"]"#, &options).unwrap(),
+            JsonValue::Array(vec!["\u{1B}\t".into()])
+        );
+        assert!(parse_with(r#"["\z"]"#, &options).is_err());
+    }
+
+    #[test]
+    fn test_literals() {
+        let input = r#"{"a": undefined, "b": [@date(2024-01-02), null]}"#;
+        assert!(parse_with(input, &ParserOptions::default()).is_err());
+        let options = ParserOptions::default()
+            .literal("undefined", JsonValue::Null)
+            .literal_parser(|input: &str| {
+                let rest = input.strip_prefix("@date(")?;
+                let end = rest.find(')')?;
+                let tagged = Map::from([("$date".to_owned(), rest[..end].into())]);
+                Some((JsonValue::Object(tagged), "@date()".len() + end))
+            });
+        assert_eq!(options.profile(), Profile::Custom);
+        assert_eq!(
+            parse_with(input, &options).unwrap(),
+            crate::parse(r#"{"a": null, "b": [{"$date": "2024-01-02"}, null]}"#).unwrap()
+        );
+        assert_eq!(
+            crate::parse_multi_with("[undefined]", &options).unwrap(),
+            crate::parse_multi("[null]").unwrap()
+        );
+        let err = parse_with("[@date(2024]", &options).unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (ErrorKind::UnexpectedToken, 1));
+
+        // matches of no bytes are ignored
+        let options = ParserOptions::default()
+            .literal("", JsonValue::Null)
+            .literal_parser(|_: &str| Some((JsonValue::Bool(true), 0)));
+        assert_eq!(
+            parse_with("[1, x]", &options).unwrap_err().kind(),
+            ErrorKind::UnexpectedToken
+        );
+        assert_eq!(
+            parse_with("[1]", &options).unwrap(),
+            crate::parse("[1]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_key_filter() {
+        let input = r#"{"id": 1, "blob": {"data": [1, 2, 3]}, "tags": [{"id": 2, "x": "y"}]}"#;
+        let parse_filtered =
+            |filter| parse_with(input, &ParserOptions::default().key_filter(filter)).unwrap();
+        assert_eq!(
+            parse_filtered(KeyFilter::allow(["id", "tags"])),
+            crate::parse(r#"{"id": 1, "tags": [{"id": 2}]}"#).unwrap()
+        );
+        assert_eq!(
+            parse_filtered(KeyFilter::deny(["blob", "x"])),
+            crate::parse(r#"{"id": 1, "tags": [{"id": 2}]}"#).unwrap()
+        );
+        assert_eq!(
+            parse_filtered(KeyFilter::predicate(|key| key.len() > 2)),
+            crate::parse(r#"{"blob": {"data": [1, 2, 3]}, "tags": [{}]}"#).unwrap()
+        );
+        let options = ParserOptions::default().key_filter(KeyFilter::deny(["blob"]));
+        assert_eq!(options.profile(), Profile::Custom);
+        // skipped values must still be valid
+        let input = r#"{"blob": [1,], "id": 1}"#;
+        let err = parse_with(input, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            crate::parse(input).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let input = r#"{"user": {"name": "a", "password": "x"}, "n": "3", "samples": [1, 2, 3, 4, 5], "debug": {}}"#;
+        let mut seen = Vec::new();
+        let value = parse_with_transform(input, &ParserOptions::default(), |pointer, value| {
+            seen.push(pointer.to_string());
+            match (pointer.as_str(), value) {
+                ("/user/password", _) => Some("***".into()),
+                ("/n", JsonValue::String(n)) => Some(n.parse::<u64>().unwrap().into()),
+                (p, _) if p.starts_with("/samples/") && p.ends_with(['1', '3']) => None,
+                ("/debug", _) => None,
+                (_, value) => Some(value),
+            }
+        })
+        .unwrap();
+        let expect = crate::parse(
+            r#"{"user": {"name": "a", "password": "***"}, "n": 3, "samples": [1, 3, 5]}"#,
+        );
+        assert_eq!(value, expect.unwrap());
+        // children before their parents, in document order
+        assert_eq!(
+            seen,
+            [
+                "/user/name",
+                "/user/password",
+                "/user",
+                "/n",
+                "/samples/0",
+                "/samples/1",
+                "/samples/2",
+                "/samples/3",
+                "/samples/4",
+                "/samples",
+                "/debug",
+                ""
+            ]
+        );
+
+        let value = parse_with_transform("[[1]]", &ParserOptions::default(), |pointer, value| {
+            (!pointer.is_root()).then_some(value)
+        });
+        assert_eq!(value.unwrap(), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let input = r#"{"a": ["x", "y\u0021"], "b\n": {"c": "z"}}"#;
+        let value = parse_borrowed(input, &ParserOptions::default()).unwrap();
+        let JsonValue::Object(map) = &value else {
+            panic!("not an object");
+        };
+        let keys: Vec<_> = map.keys().collect();
+        assert!(keys.iter().any(|key| matches!(key, Cow::Borrowed("a"))));
+        assert!(keys
+            .iter()
+            .any(|key| matches!(key, Cow::Owned(key) if key == "b\n")));
+        assert!(matches!(
+            value.pointer("/a/0"),
+            Some(JsonValue::String(Cow::Borrowed("x")))
+        ));
+        assert!(
+            matches!(value.pointer("/a/1"), Some(JsonValue::String(Cow::Owned(s))) if s == "y!")
+        );
+        assert_eq!(value.pointer("/b\n/c").unwrap().to_string(), r#""z""#);
+
+        let options = ParserOptions::default().duplicate_keys(DuplicateKeys::Error);
+        let err = parse_borrowed(r#"{"a": 1, "a": 2}"#, &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let value: JsonValue = r#"{"a": [1]}"#.parse().unwrap();
+        assert_eq!(value, crate::parse(r#"{"a": [1]}"#).unwrap());
+        let err = "[1,]".parse::<JsonValue>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+        assert_eq!(err.position(), crate::parse("[1,]").unwrap_err().position());
+    }
+
     #[test]
     fn test_string() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
         assert_eq!(
-            parse_string::<Error<&str>>(r#""""#),
-            Ok(("", "".to_owned()))
+            parse_string::<Error<&str>>(r#""""#, &st),
+            Ok(("", "".into()))
         );
         assert_eq!(
-            parse_string::<Error<&str>>(r#""abc\n \u1234""#),
-            Ok(("", "abc\n \u{1234}".to_owned()))
+            parse_string::<Error<&str>>(r#""abc\n \u1234""#, &st),
+            Ok(("", "abc\n \u{1234}".into()))
+        );
+        // borrowed unless escapes are decoded
+        let (_, s) = parse_string::<Error<&str>>(r#""abc é""#, &st).unwrap();
+        assert!(matches!(s, Cow::Borrowed("abc é")));
+        let (_, s) = parse_string::<Error<&str>>(r#""abc\"""#, &st).unwrap();
+        assert!(matches!(s, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_number() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert_eq!(
+            parse_number::<Error<&str>>("-0.5e1", &st),
+            Ok(("", num(-5.)))
+        );
+        assert_eq!(parse_number::<Error<&str>>("012", &st), Ok(("12", num(0.))));
+        assert_eq!(
+            parse_number::<Error<&str>>("1e400", &st),
+            Ok(("", num(f64::INFINITY)))
+        );
+        assert_eq!(
+            parse_number::<Error<&str>>("1e-400", &st),
+            Ok(("", num(0.)))
+        );
+        assert!(parse_number::<Error<&str>>("+1", &st).is_err());
+        assert!(parse_number::<Error<&str>>(".5", &st).is_err());
+        assert!(parse_number::<Error<&str>>("nan", &st).is_err());
+
+        let options = ParserOptions::default()
+            .number_overflow(NumberOverflow::Error)
+            .number_underflow(NumberUnderflow::Error)
+            .large_integers(LargeIntegers::Error);
+        let st = ParseState::new(&options);
+        assert!(parse_number::<Error<&str>>("-1e400", &st).is_err());
+        assert!(parse_number::<Error<&str>>("1e-400", &st).is_err());
+        assert_eq!(
+            parse_number::<Error<&str>>("0e-400", &st),
+            Ok(("", num(0.)))
+        );
+        assert!(
+            parse_number::<Error<&str>>("1000000000000000000000000000000000000000", &st).is_err()
+        );
+        assert!(
+            parse_number::<Error<&str>>("-1000000000000000000000000000000000000000", &st).is_err()
+        );
+        assert_eq!(
+            parse_number::<Error<&str>>("9007199254740993", &st),
+            Ok(("", Number::from(9007199254740993u64)))
+        );
+        assert_eq!(
+            parse_number::<Error<&str>>("-9007199254740991", &st),
+            Ok(("", num(-9007199254740991.)))
+        );
+    }
+
+    #[test]
+    fn test_number_policies() {
+        let parse = |s, options: &ParserOptions| {
+            let st = ParseState::new(options);
+            parse_number::<Error<&str>>(s, &st).unwrap().1
+        };
+        let options = ParserOptions::default();
+        assert!(parse("-0", &options).as_f64().unwrap().is_sign_negative());
+        assert!(parse("-1e-400", &options)
+            .as_f64()
+            .unwrap()
+            .is_sign_negative());
+        assert_eq!(parse("1e400", &options).as_str(), None);
+
+        let options = ParserOptions::default()
+            .negative_zero(NegativeZero::Normalize)
+            .number_overflow(NumberOverflow::KeepText);
+        assert_eq!(parse("-0", &options).as_u64(), Some(0));
+        for zero in ["-0.0", "-0e5", "-1e-400"] {
+            let n = parse(zero, &options);
+            assert!(n.is_f64() && n.as_f64().unwrap().is_sign_positive());
+        }
+        let n = parse("-1e400", &options);
+        assert_eq!(
+            (n.as_f64(), n.as_str()),
+            (Some(f64::NEG_INFINITY), Some("-1e400"))
+        );
+        assert_eq!(n.to_string(), "-1e400");
+        assert_eq!(options.profile(), crate::Profile::Custom);
+    }
+
+    #[cfg(feature = "i128")]
+    #[test]
+    fn test_i128() {
+        let options = ParserOptions::default().large_integers(LargeIntegers::Error);
+        let st = ParseState::new(&options);
+        let (_, n) = parse_number::<Error<&str>>("18446744073709551616", &st).unwrap();
+        assert_eq!(n.as_u128(), Some(1 << 64));
+        let (_, n) = parse_number::<Error<&str>>("-9223372036854775809", &st).unwrap();
+        assert_eq!(n.as_i128(), Some(-(1 << 63) - 1));
+        assert!(
+            parse_number::<Error<&str>>("340282366920938463463374607431768211456", &st).is_err()
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_numbers() {
+        use crate::DecimalNumbers;
+
+        let options = ParserOptions::default().decimal_numbers(DecimalNumbers::Decimal);
+        let st = ParseState::new(&options);
+        let (_, n) = parse_number::<Error<&str>>("19.90", &st).unwrap();
+        assert!(n.is_decimal());
+        assert_eq!(n.to_string(), "19.90");
+        let (_, n) = parse_number::<Error<&str>>("1e-40", &st).unwrap();
+        assert!(n.is_f64());
+        let (_, n) = parse_number::<Error<&str>>("7", &st).unwrap();
+        assert!(n.is_u64());
+
+        let options = ParserOptions::default().decimal_numbers(DecimalNumbers::Strict);
+        let st = ParseState::new(&options);
+        assert!(parse_number::<Error<&str>>("1e-40", &st).is_err());
+    }
+
+    #[test]
+    fn test_keep_number_text() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        let (_, n) = parse_number::<Error<&str>>("1.0e+2", &st).unwrap();
+        assert_eq!(n.as_str(), None);
+        assert_eq!(n.to_string(), "100.0");
+
+        let options = ParserOptions::default().keep_number_text(true);
+        let st = ParseState::new(&options);
+        let (rest, n) = parse_number::<Error<&str>>("1.0e+2,", &st).unwrap();
+        assert_eq!(rest, ",");
+        assert_eq!(n.as_str(), Some("1.0e+2"));
+        assert_eq!(n.to_string(), "1.0e+2");
+        assert_eq!(n, num(100.));
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert!(parse_number::<Error<&str>>("NaN", &st).is_err());
+        assert!(parse_number::<Error<&str>>("-Infinity", &st).is_err());
+
+        let options = ParserOptions::default().non_finite_numbers(NonFiniteNumbers::Accept);
+        let st = ParseState::new(&options);
+        let (_, n) = parse_number::<Error<&str>>("NaN", &st).unwrap();
+        assert!(n.as_f64().unwrap().is_nan());
+        assert_eq!(
+            parse_number::<Error<&str>>("Infinity]", &st),
+            Ok(("]", num(f64::INFINITY)))
+        );
+        assert_eq!(
+            parse_number::<Error<&str>>("-Infinity", &st),
+            Ok(("", num(f64::NEG_INFINITY)))
+        );
+        assert_eq!(parse_number::<Error<&str>>("-1", &st), Ok(("", num(-1.))));
+
+        let options = ParserOptions::default().non_finite_numbers(NonFiniteNumbers::Null);
+        assert_eq!(
+            parse_with("[NaN, -Infinity, 1]", &options).unwrap(),
+            JsonValue::Array(vec![
+                JsonValue::Null,
+                JsonValue::Null,
+                JsonValue::Number(num(1.))
+            ])
         );
     }
 
@@ -248,38 +1738,48 @@ mod tests {
 
     #[test]
     fn test_value() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
         assert_eq!(
-            parse_value::<Error<&str>>("null"),
+            parse_value::<JsonValue, Error<&str>>("null", &st),
             Ok(("", JsonValue::Null))
         );
         assert_eq!(
-            parse_value::<Error<&str>>("true"),
+            parse_value::<JsonValue, Error<&str>>("true", &st),
             Ok(("", JsonValue::Bool(true)))
         );
         assert_eq!(
-            parse_value::<Error<&str>>("false"),
+            parse_value::<JsonValue, Error<&str>>("false", &st),
             Ok(("", JsonValue::Bool(false)))
         );
         assert_eq!(
-            parse_value::<Error<&str>>("\"\""),
+            parse_value::<JsonValue, Error<&str>>("\"\"", &st),
             Ok(("", JsonValue::String("".to_owned())))
         );
     }
 
     #[test]
     fn test_array() {
-        assert_eq!(parse_array::<Error<&str>>(r#"[]"#), Ok(("", vec![])));
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
         assert_eq!(
-            parse_array::<Error<&str>>(r#"["string",   null, 0 , false, [  null]    ,{}]"#),
+            parse_array::<JsonValue, Error<&str>>(r#"[]"#, &st),
+            Ok(("", vec![]))
+        );
+        assert_eq!(
+            parse_array::<JsonValue, Error<&str>>(
+                r#"["string",   null, 0 , false, [  null]    ,{}]"#,
+                &st
+            ),
             Ok((
                 "",
                 vec![
                     JsonValue::String("string".to_owned()),
                     JsonValue::Null,
-                    JsonValue::Number(0.),
+                    JsonValue::Number(num(0.)),
                     JsonValue::Bool(false),
                     JsonValue::Array(vec![JsonValue::Null]),
-                    JsonValue::Object(HashMap::new())
+                    JsonValue::Object(Map::new())
                 ],
             ))
         );
@@ -287,21 +1787,226 @@ mod tests {
 
     #[test]
     fn test_object() {
-        let mut rst = HashMap::new();
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        let mut rst = Map::new();
         rst.insert("a".to_owned(), JsonValue::Null);
         rst.insert("b".to_owned(), JsonValue::Array(vec![]));
-        rst.insert("c".to_owned(), JsonValue::Object(HashMap::new()));
+        rst.insert("c".to_owned(), JsonValue::Object(Map::new()));
         assert_eq!(
-            parse_object::<Error<&str>>(r#"{"a": null, "b": [] , "c" :{} }"#),
+            parse_object::<JsonValue, Error<&str>>(r#"{"a": null, "b": [] , "c" :{} }"#, &st),
             Ok(("", rst))
         );
     }
 
+    #[test]
+    fn test_missing_separators() {
+        let options = ParserOptions::default().missing_separators(MissingSeparators::Skip);
+        let parse = |s| crate::parse_with(s, &options).map_err(|err| err.kind());
+        assert_eq!(
+            parse(r#"{"a" 1, "b": 2, "c" {"d": [3, "}"]}, "e": 4}"#),
+            crate::parse(r#"{"b": 2, "e": 4}"#).map_err(|err| err.kind())
+        );
+        assert_eq!(
+            parse(r#"[{"a": 1 "b": 2, "c": 3}, {"d": 4 "e" 5}]"#),
+            crate::parse(r#"[{"a": 1, "c": 3}, {"d": 4}]"#).map_err(|err| err.kind())
+        );
+        assert_eq!(parse(r#"{"a" 1"#), Err(ErrorKind::UnexpectedEof));
+        assert_eq!(parse(r#"{"a": 1, 2}"#), Err(ErrorKind::UnexpectedToken));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = r#"{"a": 1, "a": 2}"#;
+        let expect = |n: f64| Ok(("", Map::from([("a".to_owned(), JsonValue::Number(num(n)))])));
+
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert_eq!(
+            parse_object::<JsonValue, Error<&str>>(input, &st),
+            expect(2.)
+        );
+
+        let options = ParserOptions::default().duplicate_keys(DuplicateKeys::FirstWins);
+        let st = ParseState::new(&options);
+        assert_eq!(
+            parse_object::<JsonValue, Error<&str>>(input, &st),
+            expect(1.)
+        );
+
+        let options = ParserOptions::default().duplicate_keys(DuplicateKeys::Error);
+        let st = ParseState::new(&options);
+        assert!(parse_object::<JsonValue, Error<&str>>(input, &st).is_err());
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_key_normalization() {
+        use crate::options::KeyNormalization;
+
+        // "é" precomposed and as `e` + combining acute accent
+        let input = r#"{"\u00e9": 1, "e\u0301": 2}"#;
+        let keys = |options: &ParserOptions| {
+            let st = ParseState::new(options);
+            parse_object::<JsonValue, Error<&str>>(input, &st)
+                .map(|(_, map)| map.len())
+                .ok()
+        };
+
+        assert_eq!(keys(&ParserOptions::default()), Some(2));
+        let options = ParserOptions::default().key_normalization(KeyNormalization::Nfc);
+        assert_eq!(keys(&options), Some(1));
+        let options = ParserOptions::default().key_normalization(KeyNormalization::Strict);
+        assert_eq!(keys(&options), None);
+
+        // exact duplicates are left to `duplicate_keys`
+        let options = ParserOptions::default().key_normalization(KeyNormalization::Strict);
+        let st = ParseState::new(&options);
+        assert!(parse_object::<JsonValue, Error<&str>>(r#"{"a": 1, "a": 2}"#, &st).is_ok());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let options = ParserOptions::default().max_depth(Some(2));
+        let st = ParseState::new(&options);
+        assert!(parse_root_dom::<JsonValue, Error<&str>>("[[]]", &st).is_ok());
+        assert!(parse_root_dom::<JsonValue, Error<&str>>(r#"[{"a": []}]"#, &st).is_err());
+        // the depth is restored when a branch backtracks
+        assert!(parse_root_dom::<JsonValue, Error<&str>>("[[], [], []]", &st).is_ok());
+    }
+
+    #[test]
+    fn test_max_array_len() {
+        let options = ParserOptions::default().max_array_len(Some(2));
+        let st = ParseState::new(&options);
+        let (_, items) =
+            parse_array::<JsonValue, Error<&str>>("[1, [2, 3, 4], {}, 5]", &st).unwrap();
+        assert_eq!(
+            items,
+            [
+                JsonValue::Number(1u8.into()),
+                JsonValue::Array(vec![
+                    JsonValue::Number(2u8.into()),
+                    JsonValue::Number(3u8.into())
+                ])
+            ]
+        );
+        assert_eq!(st.skipped(), 3);
+        // skipped elements are still checked
+        assert!(parse_array::<JsonValue, Error<&str>>("[1, 2, [}]", &st).is_err());
+    }
+
+    #[test]
+    fn test_bom() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
+        assert!(parse_root_dom::<JsonValue, Error<&str>>("\u{FEFF}{}", &st).is_err());
+
+        let options = ParserOptions::default().allow_bom(true);
+        let st = ParseState::new(&options);
+        assert!(parse_root_dom::<JsonValue, Error<&str>>("\u{FEFF}{}", &st).is_ok());
+    }
+
+    #[test]
+    fn test_public_parse_root() {
+        use nom::{
+            branch::alt, bytes::complete::tag, combinator::map, multi::separated_list1, Err,
+        };
+
+        use crate::{parse_root, parse_root_with};
+
+        let (rest, docs) =
+            separated_list1(tag(";"), parse_root::<Error<&str>>)(" [1] ;{\"a\": 2}\n x").unwrap();
+        assert_eq!(rest, "x");
+        assert_eq!(
+            docs,
+            [crate::parse("[1]"), crate::parse(r#"{"a": 2}"#)].map(Result::unwrap)
+        );
+
+        // not a document: recoverable, so `alt` tries the next branch
+        assert!(matches!(
+            parse_root::<Error<&str>>(" undefined"),
+            Err(Err::Error(_))
+        ));
+        let mut value = alt((
+            parse_root::<Error<&str>>,
+            map(tag("undefined"), |_| JsonValue::Null),
+        ));
+        assert_eq!(value("undefined;"), Ok((";", JsonValue::Null)));
+        assert_eq!(value("null ;"), Ok((";", JsonValue::Null)));
+        assert!(matches!(value("[1,]"), Err(Err::Failure(_))));
+
+        let options = ParserOptions::default().max_depth(Some(1));
+        let mut root = parse_root_with::<Error<&str>>(&options);
+        assert!(root("[1]").is_ok());
+        assert!(root("[1]").is_ok());
+        assert!(matches!(root("[[1]]"), Err(Err::Failure(_))));
+
+        let options = ParserOptions::default().scalar_roots(false);
+        let mut root = parse_root_with::<Error<&str>>(&options);
+        assert!(matches!(root(" null"), Err(Err::Error(_))));
+    }
+
+    #[test]
+    fn test_scalar_roots() {
+        let parse = |s| crate::parse(s).unwrap();
+        assert_eq!(parse("42"), JsonValue::Number(Number::from(42)));
+        assert_eq!(parse(" \"hi\"\n"), JsonValue::String("hi".to_owned()));
+        assert_eq!(parse("null"), JsonValue::Null);
+        assert_eq!(
+            parse_with("1 2", &ParserOptions::default())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::TrailingCharacters
+        );
+
+        let options = ParserOptions::default().scalar_roots(false);
+        assert_eq!(options.profile(), Profile::Custom);
+        assert_eq!(
+            parse_with("42", &options).unwrap_err().kind(),
+            ErrorKind::UnexpectedToken
+        );
+        assert!(parse_with("[42]", &options).is_ok());
+    }
+
     #[test]
     fn test_unclosed_array() {
+        let options = ParserOptions::default();
+        let st = ParseState::new(&options);
         println!(
             "{}",
-            convert_error("[,]", parse_array("[,]").finish().err().unwrap())
+            convert_error(
+                "[,]",
+                parse_array::<JsonValue, _>("[,]", &st)
+                    .finish()
+                    .err()
+                    .unwrap()
+            )
         );
     }
+
+    #[cfg(feature = "stacker")]
+    #[test]
+    fn test_deep_nesting_grows_stack() {
+        // parsing takes kilobytes of stack per level, dropping and the
+        // other recursive impls much less
+        const DEPTH: usize = 10_000;
+        let input = "[".repeat(DEPTH) + &"]".repeat(DEPTH);
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(move || {
+                let options = ParserOptions::default().max_depth(None);
+                let st = ParseState::new(&options);
+                let (rest, value) = parse_value::<JsonValue, Error<&str>>(&input, &st).unwrap();
+                assert_eq!(rest, "");
+                assert_eq!(value.to_string(), input);
+                let pretty = value.to_string_with(&crate::WriteOptions::pretty());
+                assert_eq!(pretty.matches(']').count(), DEPTH);
+                assert_eq!(value.clone(), value);
+                assert!(format!("{:?}", value).starts_with("Array([Array(["));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
 }
@@ -0,0 +1,405 @@
+//! Completing truncated documents, such as the structured output of a model
+//! still being streamed.
+
+use crate::{
+    parse_with,
+    parser::{parse_string, ParseState},
+    JsonPointer, JsonValue, ParseError, ParserOptions,
+};
+
+/// A truncated document completed by [`complete_partial`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partial {
+    /// The document.
+    pub value: JsonValue,
+    /// The text closing the part of the input that was kept: the rest of a
+    /// literal, a `"`, the `]` and `}` of the open arrays and objects.
+    pub suffix: String,
+    /// Bytes of the input that were kept; a member without a value, a
+    /// trailing `,`, an incomplete escape or number exponent are dropped.
+    pub kept: usize,
+    /// The values the input ends in the middle of, outermost first: the open
+    /// arrays and objects, then the string, number or literal that was cut.
+    pub incomplete: Vec<JsonPointer>,
+}
+
+impl Partial {
+    /// Whether the input was a whole document.
+    pub fn is_complete(&self) -> bool {
+        self.incomplete.is_empty()
+    }
+}
+
+/// Parse a prefix of a document with the default options, closing what the
+/// input ends in the middle of.
+///
+/// Strings are closed and literals finished, e.g. `tr` becomes `true`, then
+/// the open arrays and objects are closed. Whatever can't be completed
+/// without guessing a value is dropped: an object member whose key or value
+/// hasn't started, a `-` or exponent marker without digits. Everything else
+/// in the input must be valid; errors are positioned in the input.
+///
+/// ```
+/// use nom_json_parser::{complete_partial, parse};
+///
+/// let partial = complete_partial(r#"{"items": [{"name": "ab"#).unwrap();
+/// assert_eq!(partial.value, parse(r#"{"items": [{"name": "ab"}]}"#).unwrap());
+/// assert_eq!(partial.suffix, "\"}]}");
+/// assert_eq!(partial.incomplete.last().unwrap().as_str(), "/items/0/name");
+/// ```
+pub fn complete_partial(input: &str) -> Result<Partial, ParseError> {
+    complete_partial_with(input, &ParserOptions::default())
+}
+
+/// Like [`complete_partial`], with `options`.
+pub fn complete_partial_with(input: &str, options: &ParserOptions) -> Result<Partial, ParseError> {
+    let Some(cut) = Scanner::new(options).run(input) else {
+        // invalid before the end, for the parser to report
+        return Ok(Partial {
+            value: parse_with(input, options)?,
+            suffix: String::new(),
+            kept: input.len(),
+            incomplete: Vec::new(),
+        });
+    };
+    let mut text = String::with_capacity(cut.kept + cut.suffix.len());
+    text.push_str(&input[..cut.kept]);
+    text.push_str(&cut.suffix);
+    let value = parse_with(&text, options)?;
+    Ok(Partial {
+        value,
+        suffix: cut.suffix,
+        kept: cut.kept,
+        incomplete: cut.incomplete,
+    })
+}
+
+/// Where to cut the input and how to close it.
+struct Cut {
+    kept: usize,
+    suffix: String,
+    incomplete: Vec<JsonPointer>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    /// a value, or the end of an empty array or object
+    First,
+    Value,
+    Key,
+    Colon,
+    /// a `,` or the end
+    Next,
+}
+
+struct Frame {
+    close: char,
+    /// the pointer of the array or object
+    pointer: JsonPointer,
+    /// elements of an array so far
+    len: usize,
+    /// key of the current member of an object
+    key: String,
+    expect: Expect,
+}
+
+struct Scanner<'o> {
+    options: &'o ParserOptions,
+    st: ParseState<'o>,
+    stack: Vec<Frame>,
+    /// the input can be closed right after this offset
+    safe: usize,
+    /// whether the root value is complete
+    done: bool,
+}
+
+impl<'o> Scanner<'o> {
+    fn new(options: &'o ParserOptions) -> Self {
+        Scanner {
+            options,
+            st: ParseState::new(options),
+            stack: Vec::new(),
+            safe: 0,
+            done: false,
+        }
+    }
+
+    /// `None` when the input is invalid before its end.
+    fn run(mut self, input: &str) -> Option<Cut> {
+        let mut rest = input;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+            let at = input.len() - rest.len();
+            let Some(c) = rest.chars().next() else {
+                return Some(self.close(self.safe, String::new(), false));
+            };
+            let expect = self.stack.last().map(|frame| frame.expect);
+            match (c, expect) {
+                (',', Some(Expect::Next)) => {
+                    let frame = self.stack.last_mut()?;
+                    frame.expect = if frame.close == ']' {
+                        Expect::Value
+                    } else {
+                        Expect::Key
+                    };
+                    rest = &rest[1..];
+                }
+                (':', Some(Expect::Colon)) => {
+                    self.stack.last_mut()?.expect = Expect::Value;
+                    rest = &rest[1..];
+                }
+                (']' | '}', Some(Expect::First | Expect::Next))
+                    if self.stack.last()?.close == c =>
+                {
+                    self.stack.pop();
+                    self.value_end(at + 1);
+                    rest = &rest[1..];
+                }
+                ('"', Some(Expect::First | Expect::Key)) if self.stack.last()?.close == '}' => {
+                    match parse_string::<()>(rest, &self.st) {
+                        Ok((after, key)) => {
+                            let frame = self.stack.last_mut()?;
+                            frame.key = key.into_owned();
+                            frame.expect = Expect::Colon;
+                            rest = after;
+                        }
+                        Err(_) if is_partial_string(rest) => {
+                            return Some(self.close(self.safe, String::new(), false))
+                        }
+                        Err(_) => return None,
+                    }
+                }
+                _ if self.value_starts() => {
+                    let pointer = self.next_pointer();
+                    match c {
+                        '[' | '{' => {
+                            if self
+                                .options
+                                .max_depth
+                                .is_some_and(|max| self.stack.len() >= max)
+                            {
+                                return None;
+                            }
+                            self.stack.push(Frame {
+                                close: if c == '[' { ']' } else { '}' },
+                                pointer,
+                                len: 0,
+                                key: String::new(),
+                                expect: Expect::First,
+                            });
+                            self.safe = at + 1;
+                            rest = &rest[1..];
+                        }
+                        '"' => match parse_string::<()>(rest, &self.st) {
+                            Ok((after, _)) => {
+                                rest = after;
+                                self.value_end(input.len() - rest.len());
+                            }
+                            Err(_) if is_partial_string(rest) => {
+                                let kept = at + string_prefix(rest);
+                                return Some(self.close(kept, "\"".to_owned(), true));
+                            }
+                            Err(_) => return None,
+                        },
+                        '-' | '0'..='9' => {
+                            let len = rest
+                                .find(|c: char| {
+                                    !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')
+                                })
+                                .unwrap_or(rest.len());
+                            if len < rest.len() {
+                                rest = &rest[len..];
+                                self.value_end(at + len);
+                                continue;
+                            }
+                            // cut at the end of the input
+                            let digits = rest.trim_end_matches(|c: char| !c.is_ascii_digit());
+                            if digits.is_empty() {
+                                return Some(self.close(self.safe, String::new(), false));
+                            }
+                            // a number ending the input is only whole as the root
+                            let cut = digits.len() < rest.len() || !self.stack.is_empty();
+                            return Some(self.close(at + digits.len(), String::new(), cut));
+                        }
+                        _ => {
+                            let len = rest
+                                .find(|c: char| !c.is_ascii_alphabetic())
+                                .unwrap_or(rest.len());
+                            if len < rest.len() {
+                                rest = &rest[len..];
+                                self.value_end(at + len);
+                                continue;
+                            }
+                            let literal = ["true", "false", "null"]
+                                .into_iter()
+                                .find(|literal| literal.starts_with(rest))?;
+                            let suffix = literal[rest.len()..].to_owned();
+                            let cut = !suffix.is_empty();
+                            return Some(self.close(input.len(), suffix, cut));
+                        }
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Whether a value can start next.
+    fn value_starts(&self) -> bool {
+        match self.stack.last() {
+            None => !self.done,
+            Some(frame) => {
+                frame.expect == Expect::Value
+                    || (frame.expect == Expect::First && frame.close == ']')
+            }
+        }
+    }
+
+    /// The pointer of the value starting next.
+    fn next_pointer(&self) -> JsonPointer {
+        match self.stack.last() {
+            Some(frame) if frame.close == ']' => frame.pointer.child(&frame.len.to_string()),
+            Some(frame) => frame.pointer.child(&frame.key),
+            None => JsonPointer::root(),
+        }
+    }
+
+    /// A value ended at `end`.
+    fn value_end(&mut self, end: usize) {
+        match self.stack.last_mut() {
+            Some(frame) => {
+                frame.len += 1;
+                frame.expect = Expect::Next;
+            }
+            None => self.done = true,
+        }
+        self.safe = end;
+    }
+
+    /// Keep `kept` bytes, followed by `suffix` and the closing brackets;
+    /// `cut_value` if the input ends in a scalar.
+    fn close(&self, kept: usize, mut suffix: String, cut_value: bool) -> Cut {
+        let mut incomplete: Vec<JsonPointer> = self
+            .stack
+            .iter()
+            .map(|frame| frame.pointer.clone())
+            .collect();
+        if cut_value {
+            incomplete.push(self.next_pointer());
+        }
+        suffix.extend(self.stack.iter().rev().map(|frame| frame.close));
+        Cut {
+            kept,
+            suffix,
+            incomplete,
+        }
+    }
+}
+
+/// Whether the string starting at `s` is only cut short: no `"` closes it
+/// and there is no control character.
+fn is_partial_string(s: &str) -> bool {
+    let mut escaped = false;
+    for c in s[1..].chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return false,
+            c if c < ' ' => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Length of the unterminated string `s` without an incomplete escape at its
+/// end, nor the `\u` escape of a high surrogate the low one would follow.
+fn string_prefix(s: &str) -> usize {
+    let backslashes = s.len() - s.trim_end_matches('\\').len();
+    let mut end = s.len() - backslashes % 2;
+    if let Some((i, hex)) = unicode_escape_at_end(&s[..end]) {
+        if hex.len() < 4 {
+            end = i;
+        }
+    }
+    if let Some((i, hex)) = unicode_escape_at_end(&s[..end]) {
+        if hex.len() == 4 && (0xD800..0xDC00).contains(&u32::from_str_radix(hex, 16).unwrap_or(0)) {
+            end = i;
+        }
+    }
+    end
+}
+
+/// The offset and hex digits of the `\u` escape `s` ends in, if it does.
+fn unicode_escape_at_end(s: &str) -> Option<(usize, &str)> {
+    let i = s.rfind("\\u")?;
+    let hex = &s[i + 2..];
+    let escaped = s[..i].len() - s[..i].trim_end_matches('\\').len();
+    (escaped % 2 == 0 && hex.len() <= 4 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then_some((i, hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, ErrorKind};
+
+    use super::complete_partial;
+
+    #[test]
+    fn test_complete_partial() {
+        for (input, expect, suffix, incomplete) in [
+            (
+                r#"{"a": [1, 2"#,
+                r#"{"a": [1, 2]}"#,
+                "]}",
+                &["", "/a", "/a/1"][..],
+            ),
+            (r#"{"a": [1, 2,"#, r#"{"a": [1, 2]}"#, "]}", &["", "/a"]),
+            (r#"{"a": "x\"y"#, r#"{"a": "x\"y"}"#, "\"}", &["", "/a"]),
+            (r#"{"a": "x\"#, r#"{"a": "x"}"#, "\"}", &["", "/a"]),
+            (r#"{"a": "x\u00e"#, r#"{"a": "x"}"#, "\"}", &["", "/a"]),
+            (r#"["\ud83d"#, r#"[""]"#, "\"]", &["", "/0"]),
+            (r#"["\ud83d\ude"#, r#"[""]"#, "\"]", &["", "/0"]),
+            (r#"["a\ud83d\"#, r#"["a"]"#, "\"]", &["", "/0"]),
+            (r#"["\ud83d\ude00\u00"#, r#"["😀"]"#, "\"]", &["", "/0"]),
+            (r#"["é"#, r#"["é"]"#, "\"]", &["", "/0"]),
+            (r#"{"a": 1, "b"#, r#"{"a": 1}"#, "}", &[""]),
+            (r#"{"a": 1, "b":"#, r#"{"a": 1}"#, "}", &[""]),
+            (r#"{"a": {"#, r#"{"a": {}}"#, "}}", &["", "/a"]),
+            (r#"[1.5e"#, "[1.5]", "]", &["", "/0"]),
+            (r#"[1, -"#, "[1]", "]", &[""]),
+            (r#"[tr"#, "[true]", "ue]", &["", "/0"]),
+            (r#"[true"#, "[true]", "]", &[""]),
+            ("tr", "true", "ue", &[""]),
+            ("true", "true", "", &[]),
+            ("123", "123", "", &[]),
+            ("1.5e", "1.5", "", &[""]),
+            (
+                r#"{"x~/y": [n"#,
+                r#"{"x~/y": [null]}"#,
+                "ull]}",
+                &["", "/x~0~1y", "/x~0~1y/0"],
+            ),
+            (r#"[{"a": 1}] "#, r#"[{"a": 1}]"#, "", &[]),
+        ] {
+            let partial = complete_partial(input).unwrap();
+            assert_eq!(partial.value, parse(expect).unwrap(), "{}", input);
+            assert_eq!(partial.suffix, suffix, "{}", input);
+            let pointers: Vec<&str> = partial.incomplete.iter().map(|p| p.as_str()).collect();
+            assert_eq!(pointers, incomplete, "{}", input);
+        }
+        let partial = complete_partial(r#"{"a": 1, "b"#).unwrap();
+        assert_eq!(partial.kept, 7);
+
+        for (input, kind, offset) in [
+            ("[1 x", ErrorKind::UnexpectedToken, 3),
+            (r#"{"a": 1 "b"#, ErrorKind::MissingComma, 8),
+            ("[\"a\nb", ErrorKind::ControlCharacter, 3),
+            ("[trux", ErrorKind::UnexpectedToken, 1),
+            ("", ErrorKind::UnexpectedEof, 0),
+        ] {
+            let err = complete_partial(input).unwrap_err();
+            assert_eq!((err.kind(), err.offset()), (kind, offset), "{}", input);
+        }
+    }
+}
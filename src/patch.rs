@@ -0,0 +1,194 @@
+//! JSON Patch (RFC 6902) documents.
+
+use crate::{map::Map, InvalidPointer, JsonPointer, JsonValue};
+
+/// One operation of a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Add {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    Remove {
+        path: JsonPointer,
+    },
+    Replace {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Test {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+}
+
+impl Operation {
+    /// The `op` member naming the operation.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Add { .. } => "add",
+            Operation::Remove { .. } => "remove",
+            Operation::Replace { .. } => "replace",
+            Operation::Move { .. } => "move",
+            Operation::Copy { .. } => "copy",
+            Operation::Test { .. } => "test",
+        }
+    }
+
+    /// The operation as a patch document member.
+    pub fn to_value(&self) -> JsonValue {
+        let mut object = Map::new();
+        object.insert("op".to_owned(), self.name().into());
+        let (from, path, value) = match self {
+            Operation::Add { path, value }
+            | Operation::Replace { path, value }
+            | Operation::Test { path, value } => (None, path, Some(value)),
+            Operation::Remove { path } => (None, path, None),
+            Operation::Move { from, path } | Operation::Copy { from, path } => {
+                (Some(from), path, None)
+            }
+        };
+        if let Some(from) = from {
+            object.insert("from".to_owned(), from.as_str().into());
+        }
+        object.insert("path".to_owned(), path.as_str().into());
+        if let Some(value) = value {
+            object.insert("value".to_owned(), value.clone());
+        }
+        JsonValue::Object(object)
+    }
+}
+
+/// A list of operations, built with their pointers checked as they are
+/// added, e.g. `Patch::new().add("/tags/-", "new")?.replace("/name", "x")?`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Patch {
+    operations: Vec<Operation>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn add(self, path: &str, value: impl Into<JsonValue>) -> Result<Self, InvalidPointer> {
+        let path = pointer(path)?;
+        Ok(self.push(Operation::Add {
+            path,
+            value: value.into(),
+        }))
+    }
+
+    pub fn remove(self, path: &str) -> Result<Self, InvalidPointer> {
+        let path = pointer(path)?;
+        Ok(self.push(Operation::Remove { path }))
+    }
+
+    pub fn replace(self, path: &str, value: impl Into<JsonValue>) -> Result<Self, InvalidPointer> {
+        let path = pointer(path)?;
+        Ok(self.push(Operation::Replace {
+            path,
+            value: value.into(),
+        }))
+    }
+
+    /// Move the value at `from` to `path`, which must not be inside `from`.
+    pub fn move_from(self, from: &str, path: &str) -> Result<Self, InvalidPointer> {
+        let (from, path) = (pointer(from)?, pointer(path)?);
+        if path != from && path.is_within(&from) {
+            return Err(InvalidPointer::new(path.as_str()));
+        }
+        Ok(self.push(Operation::Move { from, path }))
+    }
+
+    pub fn copy_from(self, from: &str, path: &str) -> Result<Self, InvalidPointer> {
+        let (from, path) = (pointer(from)?, pointer(path)?);
+        Ok(self.push(Operation::Copy { from, path }))
+    }
+
+    pub fn test(self, path: &str, value: impl Into<JsonValue>) -> Result<Self, InvalidPointer> {
+        let path = pointer(path)?;
+        Ok(self.push(Operation::Test {
+            path,
+            value: value.into(),
+        }))
+    }
+
+    /// The patch document: an array of operation objects.
+    pub fn to_value(&self) -> JsonValue {
+        JsonValue::Array(self.operations.iter().map(Operation::to_value).collect())
+    }
+
+    fn push(mut self, operation: Operation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+}
+
+impl From<Patch> for Vec<Operation> {
+    fn from(patch: Patch) -> Self {
+        patch.operations
+    }
+}
+
+fn pointer(pointer: &str) -> Result<JsonPointer, InvalidPointer> {
+    JsonPointer::parse(pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::Patch;
+
+    #[test]
+    fn test_patch() {
+        let patch = Patch::new()
+            .add("/tags/-", "new")
+            .and_then(|patch| patch.replace("/name", "x"))
+            .and_then(|patch| patch.test("/version", 3))
+            .and_then(|patch| patch.move_from("/a~1b", "/c"))
+            .and_then(|patch| patch.remove(""))
+            .unwrap();
+        let expect = parse(
+            r#"[
+                {"op": "add", "path": "/tags/-", "value": "new"},
+                {"op": "replace", "path": "/name", "value": "x"},
+                {"op": "test", "path": "/version", "value": 3},
+                {"op": "move", "from": "/a~1b", "path": "/c"},
+                {"op": "remove", "path": ""}
+            ]"#,
+        );
+        assert_eq!(patch.to_value(), expect.unwrap());
+    }
+
+    #[test]
+    fn test_invalid_pointer() {
+        let err = Patch::new().add("name", 1).unwrap_err();
+        assert_eq!(err.pointer(), "name");
+        assert_eq!(err.to_string(), r#"invalid JSON Pointer "name""#);
+        assert!(Patch::new().remove("/a~2").is_err());
+        assert!(Patch::new().move_from("/a", "/a/b").is_err());
+        assert!(Patch::new().move_from("/a", "/ab").is_ok());
+    }
+}
@@ -0,0 +1,575 @@
+//! Per-record processing of newline-delimited JSON.
+//!
+//! A [`Pipeline`] is a list of operations applied in turn to each record of an
+//! NDJSON stream, in the spirit of `jq` one-liners: extract a pointer,
+//! filter, project fields, transform, and [`Pipeline::run_to`] writes the
+//! results back out as NDJSON. [`JsonValue::from_reader_many`] reads
+//! records in batches instead, [`parse_lines`] iterates over the records of
+//! NDJSON text, and with the `rayon` feature, `par_parse_ndjson` parses a
+//! whole NDJSON input in parallel.
+
+use std::{
+    fmt,
+    io::{BufRead, Write},
+    str::SplitInclusive,
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    decode_utf8, parse_with, JsonValue, ParseError, ParserOptions, Position, WriteOptions,
+};
+
+type Filter = Box<dyn Fn(&JsonValue) -> bool>;
+type Transform = Box<dyn Fn(JsonValue) -> Option<JsonValue>>;
+
+enum Op {
+    Pointer(String),
+    Filter(Filter),
+    Project(Vec<String>),
+    Map(Transform),
+}
+
+/// Operations applied to each record of an NDJSON stream.
+///
+/// Records are read and processed one line at a time, so memory use doesn't
+/// grow with the stream. Blank lines are skipped.
+#[derive(Default)]
+pub struct Pipeline {
+    options: ParserOptions,
+    ops: Vec<Op>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options each record is parsed with.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Replace the record by its value at `pointer`, dropping records that
+    /// have none; see [`JsonValue::pointer`].
+    pub fn pointer(mut self, pointer: &str) -> Self {
+        self.ops.push(Op::Pointer(pointer.to_owned()));
+        self
+    }
+
+    /// Drop records for which `predicate` is false.
+    pub fn filter(mut self, predicate: impl Fn(&JsonValue) -> bool + 'static) -> Self {
+        self.ops.push(Op::Filter(Box::new(predicate)));
+        self
+    }
+
+    /// Keep only the members `fields` of object records, dropping other
+    /// records.
+    pub fn project<I>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.ops
+            .push(Op::Project(fields.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Replace each record by `f(record)`, dropping it on `None`.
+    pub fn map(mut self, f: impl Fn(JsonValue) -> Option<JsonValue> + 'static) -> Self {
+        self.ops.push(Op::Map(Box::new(f)));
+        self
+    }
+
+    /// Run the operations on one record, `None` if it was dropped.
+    pub fn apply(&self, record: JsonValue) -> Option<JsonValue> {
+        self.ops.iter().try_fold(record, |record, op| match op {
+            Op::Pointer(pointer) => record.pointer(pointer).cloned(),
+            Op::Filter(predicate) => Some(record).filter(|record| predicate(record)),
+            Op::Project(fields) => match record {
+                JsonValue::Object(map) => Some(JsonValue::Object(
+                    map.into_iter()
+                        .filter(|(key, _)| fields.contains(key))
+                        .collect(),
+                )),
+                _ => None,
+            },
+            Op::Map(f) => f(record),
+        })
+    }
+
+    /// Parse the records of `reader` and run the operations on them.
+    ///
+    /// Errors are positioned in the whole stream; the iterator goes on with
+    /// the next line after a parse error and ends after an I/O error.
+    pub fn run<R: BufRead>(&self, reader: R) -> Records<'_, R> {
+        Records {
+            pipeline: self,
+            reader,
+            line: String::new(),
+            line_number: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Parse the records of `reader`, run the operations on them and write
+    /// the results to `writer` as NDJSON, each written as `options` say on
+    /// a line of its own; returns the number of records written.
+    ///
+    /// Stops at the first parse or I/O error, once the records before it are
+    /// written. Records are written one at a time, so `writer` should be
+    /// buffered.
+    ///
+    /// ```
+    /// use nom_json_parser::{Pipeline, WriteOptions};
+    ///
+    /// let input = "{\"id\": 1, \"ok\": true}\n{\"id\": 2, \"ok\": false}\n";
+    /// let mut out = Vec::new();
+    /// let pipeline = Pipeline::new()
+    ///     .filter(|record| record.pointer("/ok") == Some(&true.into()))
+    ///     .pointer("/id");
+    /// let written = pipeline.run_to(input.as_bytes(), &mut out, &WriteOptions::compact())?;
+    /// assert_eq!((written, out.as_slice()), (1, &b"1\n"[..]));
+    /// # Ok::<(), nom_json_parser::ParseError>(())
+    /// ```
+    pub fn run_to<R: BufRead, W: Write>(
+        &self,
+        reader: R,
+        mut writer: W,
+        options: &WriteOptions,
+    ) -> Result<usize, ParseError> {
+        let mut written = 0;
+        for record in self.run(reader) {
+            let mut line = record?.to_string_with(options);
+            line.push('\n');
+            writer.write_all(line.as_bytes()).map_err(ParseError::io)?;
+            written += 1;
+        }
+        writer.flush().map_err(ParseError::io)?;
+        Ok(written)
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("options", &self.options)
+            .field("ops", &self.ops.len())
+            .finish()
+    }
+}
+
+/// Iterator over the processed records of an NDJSON stream, see
+/// [`Pipeline::run`].
+pub struct Records<'p, R> {
+    pipeline: &'p Pipeline,
+    reader: R,
+    line: String,
+    line_number: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for Records<'_, R> {
+    type Item = Result<JsonValue, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line.clear();
+            let len = match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(len) => len,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(ParseError::io(err)));
+                }
+            };
+            let (line_number, offset) = (self.line_number + 1, self.offset);
+            self.line_number = line_number;
+            self.offset += len;
+            if self.line.trim().is_empty() {
+                continue;
+            }
+            match parse_with(&self.line, &self.pipeline.options) {
+                Ok(record) => match self.pipeline.apply(record) {
+                    Some(record) => return Some(Ok(record)),
+                    None => continue,
+                },
+                Err(err) => return Some(Err(err.starting_at(line_start(line_number, offset)))),
+            }
+        }
+        None
+    }
+}
+
+/// RS, the record separator of JSON text sequences (RFC 7464)
+const RECORD_SEPARATOR: u8 = 0x1E;
+
+impl JsonValue {
+    /// Batches of up to `batch_size` records read from an NDJSON stream, or a
+    /// JSON text sequence (RFC 7464) if the stream starts with an RS
+    /// character; see [`Batches`].
+    ///
+    /// # Panics
+    ///
+    /// If `batch_size` is 0.
+    pub fn from_reader_many<R: BufRead>(reader: R, batch_size: usize) -> Batches<R> {
+        JsonValue::from_reader_many_with(reader, batch_size, &ParserOptions::default())
+    }
+
+    pub fn from_reader_many_with<R: BufRead>(
+        reader: R,
+        batch_size: usize,
+        options: &ParserOptions,
+    ) -> Batches<R> {
+        assert!(batch_size > 0, "batch size must be non-zero");
+        Batches {
+            reader,
+            options: options.clone(),
+            batch_size,
+            separator: None,
+            record: Vec::new(),
+            start: Position {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
+            error: None,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over batches of records, see [`JsonValue::from_reader_many`].
+///
+/// Every batch but the last is full. A parse error is yielded on its own,
+/// after the batch of the records before it, and reading goes on with the
+/// next record; an I/O error ends the iteration. Errors are positioned in the
+/// whole stream, and blank records are skipped.
+pub struct Batches<R> {
+    reader: R,
+    options: ParserOptions,
+    batch_size: usize,
+    /// `\n` or RS, once the start of the stream has been seen
+    separator: Option<u8>,
+    record: Vec<u8>,
+    /// position of the next record
+    start: Position,
+    /// error to yield after the current batch
+    error: Option<ParseError>,
+    done: bool,
+}
+
+impl<R: BufRead> Batches<R> {
+    /// The next non-blank record, parsed.
+    fn next_record(&mut self) -> Option<Result<JsonValue, ParseError>> {
+        while !self.done {
+            let separator = match self.separator {
+                Some(separator) => separator,
+                None => {
+                    let separator = match self.reader.fill_buf() {
+                        Ok([RECORD_SEPARATOR, ..]) => RECORD_SEPARATOR,
+                        Ok(_) => b'\n',
+                        Err(err) => {
+                            self.done = true;
+                            return Some(Err(ParseError::io(err)));
+                        }
+                    };
+                    *self.separator.insert(separator)
+                }
+            };
+            self.record.clear();
+            let len = match self.reader.read_until(separator, &mut self.record) {
+                Ok(0) => return None,
+                Ok(len) => len,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(ParseError::io(err)));
+                }
+            };
+            let start = self.start;
+            self.advance(len);
+            let record = match self.record.strip_suffix(&[RECORD_SEPARATOR]) {
+                Some(record) if separator == RECORD_SEPARATOR => record,
+                _ => &self.record,
+            };
+            if record.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let rst = decode_utf8(record, &self.options)
+                .and_then(|record| parse_with(&record, &self.options));
+            return Some(rst.map_err(|err| err.starting_at(start)));
+        }
+        None
+    }
+}
+
+impl<R> Batches<R> {
+    /// Move `start` past the `len` bytes of `record`.
+    fn advance(&mut self, len: usize) {
+        // columns count chars, that is bytes other than UTF-8 continuations
+        let chars = |bytes: &[u8]| bytes.iter().filter(|&&b| b & 0xC0 != 0x80).count();
+        match self.record.iter().rposition(|&b| b == b'\n') {
+            Some(i) => {
+                self.start.line += self.record.iter().filter(|&&b| b == b'\n').count();
+                self.start.column = chars(&self.record[i + 1..]) + 1;
+            }
+            None => self.start.column += chars(&self.record),
+        }
+        self.start.offset += len;
+    }
+}
+
+impl<R: BufRead> Iterator for Batches<R> {
+    type Item = Result<Vec<JsonValue>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        let mut batch = Vec::with_capacity(self.batch_size);
+        while batch.len() < self.batch_size {
+            match self.next_record() {
+                Some(Ok(record)) => batch.push(record),
+                Some(Err(err)) if batch.is_empty() => return Some(Err(err)),
+                Some(Err(err)) => {
+                    self.error = Some(err);
+                    break;
+                }
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            None
+        } else {
+            Some(Ok(batch))
+        }
+    }
+}
+
+/// Position of the start of the line `line_number`, at byte `offset`.
+fn line_start(line_number: usize, offset: usize) -> Position {
+    Position {
+        line: line_number,
+        column: 1,
+        offset,
+    }
+}
+
+/// The records of NDJSON `input`, parsed one line at a time, skipping blank
+/// lines; see [`Lines`].
+///
+/// The records of a reader are parsed by a [`Pipeline`] without operations:
+///
+/// ```
+/// use nom_json_parser::Pipeline;
+///
+/// let pipeline = Pipeline::new();
+/// let records: Vec<_> = pipeline.run("[1]\n[2]\n".as_bytes()).collect();
+/// assert_eq!(records.len(), 2);
+/// ```
+pub fn parse_lines(input: &str) -> Lines<'_> {
+    parse_lines_with(input, &ParserOptions::default())
+}
+
+pub fn parse_lines_with<'a>(input: &'a str, options: &ParserOptions) -> Lines<'a> {
+    Lines {
+        lines: input.split_inclusive('\n'),
+        options: options.clone(),
+        line_number: 0,
+        offset: 0,
+    }
+}
+
+/// Iterator over the records of NDJSON text, see [`parse_lines`].
+///
+/// Errors are positioned in the whole input, and the iterator goes on with
+/// the next line after one.
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    lines: SplitInclusive<'a, char>,
+    options: ParserOptions,
+    line_number: usize,
+    offset: usize,
+}
+
+impl Iterator for Lines<'_> {
+    type Item = Result<JsonValue, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in self.lines.by_ref() {
+            let (line_number, offset) = (self.line_number + 1, self.offset);
+            self.line_number = line_number;
+            self.offset += line.len();
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                parse_with(line, &self.options)
+                    .map_err(|err| err.starting_at(line_start(line_number, offset))),
+            );
+        }
+        None
+    }
+}
+
+/// Parse the records of NDJSON `input` in parallel, in input order, skipping
+/// blank lines.
+///
+/// Errors are positioned in the whole input.
+#[cfg(feature = "rayon")]
+pub fn par_parse_ndjson(input: &str) -> Vec<Result<JsonValue, ParseError>> {
+    par_parse_ndjson_with(input, &ParserOptions::default())
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_parse_ndjson_with(
+    input: &str,
+    options: &ParserOptions,
+) -> Vec<Result<JsonValue, ParseError>> {
+    let mut offset = 0;
+    let lines: Vec<_> = input
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            offset += line.len();
+            (i + 1, offset - line.len(), line)
+        })
+        .filter(|(_, _, line)| !line.trim().is_empty())
+        .collect();
+    lines
+        .into_par_iter()
+        .map(|(line_number, offset, line)| {
+            parse_with(line, options)
+                .map_err(|err| err.starting_at(line_start(line_number, offset)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{map::Map, JsonValue, WriteOptions};
+
+    use super::{parse_lines, Pipeline};
+
+    #[test]
+    fn test_pipeline() {
+        let input = concat!(
+            "{\"id\": 1, \"tags\": [\"a\"], \"x\": true}\n",
+            "\n",
+            "{\"id\": 2, \"tags\": []}\r\n",
+            "[1]\n",
+            "{\"id\": 3, \"tags\": [\"b\", \"c\"]}",
+        );
+        let pipeline = Pipeline::new()
+            .filter(|record| record.pointer("/tags/0").is_some())
+            .project(["id", "missing"])
+            .map(|record| record.pointer("/id").cloned());
+        let ids: Vec<_> = pipeline
+            .run(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            ids,
+            [JsonValue::Number(1u8.into()), JsonValue::Number(3u8.into())]
+        );
+
+        let record = Map::from([("a".to_owned(), JsonValue::Null)]);
+        let pipeline = Pipeline::new().pointer("/a");
+        assert_eq!(
+            pipeline.apply(JsonValue::Object(record)),
+            Some(JsonValue::Null)
+        );
+    }
+
+    #[test]
+    fn test_run_to() {
+        let input = "{\"a\": [1, {\"b\": null}]}\n\n{\"a\": \"x\"}\r\n{}\n";
+        let mut out = Vec::new();
+        let pipeline = Pipeline::new().pointer("/a");
+        let written = pipeline
+            .run_to(input.as_bytes(), &mut out, &WriteOptions::relaxed())
+            .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(out, b"[1, {\"b\": null}]\n\"x\"\n");
+
+        // the records before the error are written
+        let mut out = Vec::new();
+        let err = Pipeline::new()
+            .run_to(
+                "[1]\n[2,]\n[3]\n".as_bytes(),
+                &mut out,
+                &WriteOptions::compact(),
+            )
+            .unwrap_err();
+        assert_eq!((err.line(), out.as_slice()), (2, &b"[1]\n"[..]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = "{}\n{\"a\": }\n{}\n";
+        let records: Vec<_> = Pipeline::new().run(input.as_bytes()).collect();
+        assert_eq!(records.len(), 3);
+        let err = records[1].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.column(), err.offset()), (2, 7, 9));
+        assert!(records[2].is_ok());
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let input = "{\"a\": 1}\r\n\n  \n[x]\n[é, 2]\n[3]";
+        let records: Vec<_> = parse_lines(input).collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records[0].as_ref().unwrap(),
+            &crate::parse(r#"{"a": 1}"#).unwrap()
+        );
+        let err = records[1].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.column(), err.offset()), (4, 2, 15));
+        let err = records[2].as_ref().unwrap_err();
+        assert_eq!(err.line(), 5);
+        assert_eq!(records[3].as_ref().unwrap(), &crate::parse("[3]").unwrap());
+        assert_eq!(parse_lines("").count(), 0);
+    }
+
+    #[test]
+    fn test_from_reader_many() {
+        let input = "[1]\n[2]\n\n[3]\n{\"a\": }\n[4]\n[5]\n";
+        let batches: Vec<_> = JsonValue::from_reader_many(input.as_bytes(), 2).collect();
+        let lens: Vec<_> = batches
+            .iter()
+            .map(|batch| batch.as_ref().map(Vec::len).map_err(|err| err.line()))
+            .collect();
+        assert_eq!(lens, [Ok(2), Ok(1), Err(5), Ok(2)]);
+        assert_eq!(
+            batches[3].as_ref().unwrap()[1],
+            crate::parse("[5]").unwrap()
+        );
+
+        // JSON text sequence, records spanning lines
+        let input = "\x1E[1]\n\x1E{\n  \"a\": [2]\n}\n\x1E\n\x1E[\n  x]\n";
+        let batches: Vec<_> = JsonValue::from_reader_many(input.as_bytes(), 10).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches[0].as_ref().unwrap()[1],
+            crate::parse(r#"{"a": [2]}"#).unwrap()
+        );
+        let err = batches[1].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.column(), err.offset()), (6, 3, 25));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_parse_ndjson() {
+        let input = "[1]\n\n{\"a\": }\n[2]";
+        let records = super::par_parse_ndjson(input);
+        assert_eq!(records.len(), 3);
+        assert!(matches!(&records[0], Ok(JsonValue::Array(items)) if items.len() == 1));
+        let err = records[1].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.offset()), (3, 11));
+        assert!(records[2].is_ok());
+    }
+}
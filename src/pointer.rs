@@ -0,0 +1,457 @@
+//! JSON Pointers (RFC 6901).
+
+use std::{borrow::Borrow, error::Error, fmt, str::FromStr};
+
+use crate::{
+    map::{Key, MapKind, Object},
+    JsonValue,
+};
+
+/// A JSON Pointer (RFC 6901): `""` for a whole document, or `/`-separated
+/// reference tokens with `~` escaped as `~0` and `/` as `~1`, e.g. `/a~1b/0`
+/// for the first element of the member `a/b`.
+///
+/// Every pointer the crate returns is a `JsonPointer`; the methods looking
+/// values up, like [`JsonValue::pointer`], take its text, see
+/// [`as_str`](JsonPointer::as_str).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JsonPointer(String);
+
+impl JsonPointer {
+    /// The pointer to the whole document, `""`.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(pointer: &str) -> Result<Self, InvalidPointer> {
+        let valid = (pointer.is_empty() || pointer.starts_with('/'))
+            && pointer
+                .split('~')
+                .skip(1)
+                .all(|rest| rest.starts_with(['0', '1']));
+        if valid {
+            Ok(JsonPointer(pointer.to_owned()))
+        } else {
+            Err(InvalidPointer::new(pointer))
+        }
+    }
+
+    /// The pointer made of `tokens`, unescaped.
+    pub fn from_tokens<I>(tokens: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut pointer = JsonPointer::root();
+        for token in tokens {
+            pointer.push(token.as_ref());
+        }
+        pointer
+    }
+
+    /// The escaped text of the pointer.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The reference tokens, unescaped.
+    pub fn tokens(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.split('/').skip(1).map(unescape)
+    }
+
+    /// Append `token`, escaping it.
+    pub fn push(&mut self, token: &str) {
+        self.0.push('/');
+        for ch in token.chars() {
+            match ch {
+                '~' => self.0.push_str("~0"),
+                '/' => self.0.push_str("~1"),
+                ch => self.0.push(ch),
+            }
+        }
+    }
+
+    /// [`push`](JsonPointer::push) the token of the array index `i`.
+    pub(crate) fn push_index(&mut self, i: usize) {
+        use fmt::Write;
+
+        let _ = write!(self.0, "/{}", i);
+    }
+
+    /// Remove the last token and return it unescaped, `None` for the root.
+    pub fn pop(&mut self) -> Option<String> {
+        let i = self.0.rfind('/')?;
+        let token = unescape(&self.0[i + 1..]);
+        self.0.truncate(i);
+        Some(token)
+    }
+
+    /// The last token, unescaped, `None` for the root.
+    pub fn last(&self) -> Option<String> {
+        self.0.rsplit_once('/').map(|(_, token)| unescape(token))
+    }
+
+    /// The pointer without its last token, `None` for the root.
+    pub fn parent(&self) -> Option<JsonPointer> {
+        let (parent, _) = self.0.rsplit_once('/')?;
+        Some(JsonPointer(parent.to_owned()))
+    }
+
+    /// The pointer with `token` appended.
+    pub fn child(&self, token: &str) -> JsonPointer {
+        let mut child = self.clone();
+        child.push(token);
+        child
+    }
+
+    /// Whether `self` is `other` or points inside it.
+    pub fn is_within(&self, other: &JsonPointer) -> bool {
+        self.0.starts_with(&other.0)
+            && (self.0.len() == other.0.len() || self.0[other.0.len()..].starts_with('/'))
+    }
+
+    /// Length of the text, to `truncate` back to after pushing tokens.
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Parse a dot path like `a.b[0]`: member names separated by `.`, array
+    /// indexes in brackets, and names that aren't plain, such as ones holding
+    /// a `.`, as bracketed JSON strings like `["a.b"]`. `""` is the root.
+    pub fn from_dot_path(path: &str) -> Result<Self, InvalidPath> {
+        let err = |rest: &str| InvalidPath {
+            path: path.to_owned(),
+            offset: path.len() - rest.len(),
+        };
+        let mut pointer = JsonPointer::root();
+        let mut rest = path;
+        while !rest.is_empty() {
+            if let Some(bracketed) = rest.strip_prefix('[') {
+                let (token, after) = bracket_token(bracketed).ok_or_else(|| err(rest))?;
+                pointer.push(&token);
+                rest = after;
+            } else {
+                let name = match rest.strip_prefix('.') {
+                    Some(name) if !pointer.is_root() => name,
+                    None if pointer.is_root() => rest,
+                    _ => return Err(err(rest)),
+                };
+                let end = name.find(['.', '[', ']']).unwrap_or(name.len());
+                if end == 0 {
+                    return Err(err(name));
+                }
+                pointer.push(&name[..end]);
+                rest = &name[end..];
+            }
+        }
+        Ok(pointer)
+    }
+
+    /// The pointer as a dot path, see [`from_dot_path`](JsonPointer::from_dot_path):
+    /// array indexes are written `[0]` and names that aren't plain `["a.b"]`.
+    pub fn to_dot_path(&self) -> String {
+        let mut path = String::with_capacity(self.0.len());
+        for token in self.tokens() {
+            if array_index(&token).is_some() {
+                path.push_str(&format!("[{}]", token));
+            } else if plain_name(&token) {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&token);
+            } else {
+                path.push_str(&format!("[{}]", quote(&token)));
+            }
+        }
+        path
+    }
+
+    /// Parse either syntax: a JSON Pointer if `s` is empty or starts with
+    /// `/`, a dot path otherwise.
+    pub fn parse_any(s: &str) -> Result<Self, InvalidPath> {
+        if s.is_empty() || s.starts_with('/') {
+            JsonPointer::parse(s).map_err(|_| InvalidPath {
+                path: s.to_owned(),
+                offset: 0,
+            })
+        } else {
+            JsonPointer::from_dot_path(s)
+        }
+    }
+}
+
+pub(crate) fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// an array index token: digits without leading zeros
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.starts_with('0') && token.len() > 1 || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// a member name written as is in a dot path
+fn plain_name(token: &str) -> bool {
+    !token.is_empty() && !token.contains(['.', '[', ']', '"', '\\'])
+}
+
+/// `token` as a JSON string, only `"` and `\` escaped
+fn quote(token: &str) -> String {
+    format!("\"{}\"", token.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// the token of a bracket segment, starting after `[`, and what follows `]`
+fn bracket_token(s: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut token = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => return Some((token, quoted[i + 1..].strip_prefix(']')?)),
+                '\\' => token.push(chars.next().filter(|(_, ch)| matches!(ch, '"' | '\\'))?.1),
+                ch => token.push(ch),
+            }
+        }
+        None
+    } else {
+        let (index, rest) = s.split_once(']')?;
+        array_index(index)?;
+        Some((index.to_owned(), rest))
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = InvalidPointer;
+
+    fn from_str(s: &str) -> Result<Self, InvalidPointer> {
+        JsonPointer::parse(s)
+    }
+}
+
+impl AsRef<str> for JsonPointer {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<JsonPointer> for String {
+    fn from(pointer: JsonPointer) -> Self {
+        pointer.0
+    }
+}
+
+impl PartialEq<str> for JsonPointer {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for JsonPointer {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Error returned for a malformed JSON Pointer, or by a
+/// [`Patch`](crate::Patch) operation moving a value into itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPointer {
+    pointer: String,
+}
+
+impl InvalidPointer {
+    pub(crate) fn new(pointer: &str) -> Self {
+        InvalidPointer {
+            pointer: pointer.to_owned(),
+        }
+    }
+
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl fmt::Display for InvalidPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSON Pointer {:?}", self.pointer)
+    }
+}
+
+impl Error for InvalidPointer {}
+
+/// Error returned for a malformed dot path, see
+/// [`JsonPointer::from_dot_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPath {
+    path: String,
+    offset: usize,
+}
+
+impl InvalidPath {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Byte offset of the segment that couldn't be parsed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path {:?} at offset {}", self.path, self.offset)
+    }
+}
+
+impl Error for InvalidPath {}
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// The value at the JSON Pointer (RFC 6901) `pointer`, `""` being the
+    /// value itself.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<S, M>> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape)
+            .try_fold(self, |value, token| match value {
+                JsonValue::Object(map) => map.get(token.as_str()),
+                JsonValue::Array(items) => items.get(array_index(&token)?),
+                _ => None,
+            })
+    }
+
+    /// Like [`pointer`](JsonValue::pointer), mutably.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue<S, M>> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape)
+            .try_fold(self, |value, token| match value {
+                JsonValue::Object(map) => map.get_mut(token.as_str()),
+                JsonValue::Array(items) => items.get_mut(array_index(&token)?),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::JsonValue;
+
+    use super::JsonPointer;
+
+    #[test]
+    fn test_pointer() {
+        let value = crate::parse(r#"{"a": [{"b/c": 1, "d~": 2}], "": 3}"#).unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/a/0/b~1c"),
+            Some(&JsonValue::Number(1u8.into()))
+        );
+        assert_eq!(
+            value.pointer("/a/0/d~0"),
+            Some(&JsonValue::Number(2u8.into()))
+        );
+        assert_eq!(value.pointer("/"), Some(&JsonValue::Number(3u8.into())));
+        assert_eq!(value.pointer("/a/00"), None);
+        assert_eq!(value.pointer("/a/1"), None);
+        assert_eq!(value.pointer("a"), None);
+
+        let mut value = value;
+        *value.pointer_mut("/a/0/b~1c").unwrap() = JsonValue::Null;
+        assert_eq!(value.pointer("/a/0/b~1c"), Some(&JsonValue::Null));
+        assert_eq!(value.pointer_mut("/a/0/b~1c/0"), None);
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        let mut pointer = JsonPointer::parse("/a~1b/0").unwrap();
+        assert_eq!(pointer.tokens().collect::<Vec<_>>(), ["a/b", "0"]);
+        assert_eq!(pointer, JsonPointer::from_tokens(["a/b", "0"]));
+        assert_eq!(pointer.last().as_deref(), Some("0"));
+        assert_eq!(pointer.parent().unwrap(), "/a~1b");
+        pointer.push("c~");
+        assert_eq!(pointer.to_string(), "/a~1b/0/c~0");
+        assert!(pointer.is_within(&"/a~1b".parse().unwrap()));
+        assert!(!pointer.is_within(&"/a".parse().unwrap()));
+        assert!(pointer.is_within(&JsonPointer::root()));
+        assert_eq!(pointer.pop().as_deref(), Some("c~"));
+        assert_eq!(pointer.pop().as_deref(), Some("0"));
+        assert_eq!(pointer.pop().as_deref(), Some("a/b"));
+        assert!(pointer.is_root());
+        assert_eq!((pointer.pop(), pointer.parent()), (None, None));
+        pointer.push_index(12);
+        assert_eq!(pointer, "/12");
+
+        assert_eq!(JsonPointer::parse("a").unwrap_err().pointer(), "a");
+        assert!(JsonPointer::parse("/a~2").is_err());
+        assert_eq!(JsonPointer::parse("").unwrap(), JsonPointer::root());
+    }
+
+    #[test]
+    fn test_dot_path() {
+        let cases = [
+            ("", ""),
+            ("a.b[0]", "/a/b/0"),
+            ("[1][2].c", "/1/2/c"),
+            (r#"a["b.c"].d~/e"#, "/a/b.c/d~0~1e"),
+            (r#"["q\"\\"]"#, r#"/q"\"#),
+            (r#"[""]"#, "/"),
+        ];
+        for (path, pointer) in cases {
+            let parsed = JsonPointer::from_dot_path(path).unwrap();
+            assert_eq!(parsed, pointer, "{}", path);
+            assert_eq!(parsed.to_dot_path(), path);
+        }
+        // names that look like indexes or need no brackets
+        let pointer = JsonPointer::from_dot_path(r#"a.0.01["b"]"#).unwrap();
+        assert_eq!(
+            (pointer.as_str(), pointer.to_dot_path()),
+            ("/a/0/01/b", "a[0].01.b".to_owned())
+        );
+
+        for (path, offset) in [
+            (".a", 0),
+            ("a..b", 2),
+            ("a.", 2),
+            ("a[01]", 1),
+            ("a[\"b]", 1),
+            ("a]", 1),
+        ] {
+            let err = JsonPointer::from_dot_path(path).unwrap_err();
+            assert_eq!((err.path(), err.offset()), (path, offset));
+        }
+        assert_eq!(
+            JsonPointer::from_dot_path("a..b").unwrap_err().to_string(),
+            r#"invalid path "a..b" at offset 2"#
+        );
+
+        assert_eq!(JsonPointer::parse_any("/a~1b/0").unwrap(), "/a~1b/0");
+        assert_eq!(JsonPointer::parse_any("a[0]").unwrap(), "/a/0");
+        assert_eq!(JsonPointer::parse_any("").unwrap(), "");
+        assert!(JsonPointer::parse_any("/a~2").is_err());
+    }
+}
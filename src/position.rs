@@ -0,0 +1,91 @@
+//! Mapping byte offsets to lines and columns.
+
+use std::fmt;
+
+/// A location in the input text.
+///
+/// `line` and `column` are 1-based, `column` counts `char`s from the start of
+/// the line and `offset` is the 0-based byte offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Line start offsets of a text, for repeated offset → [`Position`] lookups.
+///
+/// Lines end at `\n`, so `\r\n` endings are handled too.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { text, line_starts }
+    }
+
+    /// Number of lines; an empty text has one.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Position of the byte `offset`, clamped to the end of the text and
+    /// rounded down to a `char` boundary.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        Position {
+            line,
+            column: self.text[start..offset].chars().count() + 1,
+            offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineIndex, Position};
+
+    #[test]
+    fn test_position() {
+        let index = LineIndex::new("{\n  \"é\": 1,\r\n  \"b\"\n}");
+        assert_eq!(index.line_count(), 4);
+        let pos = |line, column, offset| Position {
+            line,
+            column,
+            offset,
+        };
+        assert_eq!(index.position(0), pos(1, 1, 0));
+        assert_eq!(index.position(1), pos(1, 2, 1));
+        assert_eq!(index.position(2), pos(2, 1, 2));
+        // after the two-byte `é`
+        assert_eq!(index.position(7), pos(2, 5, 7));
+        // inside `é`
+        assert_eq!(index.position(6), pos(2, 4, 5));
+        assert_eq!(index.position(14), pos(3, 1, 14));
+        assert_eq!(index.position(100), pos(4, 2, 21));
+    }
+
+    #[test]
+    fn test_empty() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.position(0).to_string(), "line 1, column 1");
+    }
+}
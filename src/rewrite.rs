@@ -0,0 +1,447 @@
+//! Applying a [`Patch`] to a document as it's copied, without building it.
+
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    pointer::array_index,
+    write::{write_flat, write_string},
+    Event, JsonPointer, JsonValue, Operation, ParseError, Patch, ReaderEvents, WriteError,
+    WriteOptions,
+};
+
+/// Error returned by [`Patch::rewrite`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RewriteError {
+    /// The input isn't valid JSON, or couldn't be read.
+    Parse(ParseError),
+    /// The operation at this index of the patch is a move, copy or test, or
+    /// removes the whole document.
+    Unsupported(usize),
+    /// The value an operation removes or replaces, or the array or object
+    /// it adds to, isn't in the document.
+    Missing(JsonPointer),
+    Write(WriteError),
+    /// The output couldn't be written.
+    Io(io::Error),
+}
+
+impl fmt::Display for RewriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewriteError::Parse(err) => err.fmt(f),
+            RewriteError::Unsupported(i) => write!(f, "operation {} can't be streamed", i),
+            RewriteError::Missing(pointer) => write!(f, "no value at {:?}", pointer.as_str()),
+            RewriteError::Write(err) => err.fmt(f),
+            RewriteError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for RewriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RewriteError::Parse(err) => Some(err),
+            RewriteError::Write(err) => Some(err),
+            RewriteError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for RewriteError {
+    fn from(err: ParseError) -> Self {
+        RewriteError::Parse(err)
+    }
+}
+
+impl From<WriteError> for RewriteError {
+    fn from(err: WriteError) -> Self {
+        RewriteError::Write(err)
+    }
+}
+
+impl Patch {
+    /// Copy the document of `events` to `writer` with the operations of
+    /// the patch applied, returning the number of bytes written.
+    ///
+    /// The input is read as it's rewritten, so documents larger than memory
+    /// are rewritten in the memory of their longest string and deepest
+    /// path. The operations
+    /// are add, remove and replace, with their pointers into the input
+    /// document rather than into the result of the operations before them;
+    /// values added at the same place are written in the order of the
+    /// patch. The output is written on one line, with the separators and
+    /// escapes of `options`, in writes of about 8 KiB.
+    ///
+    /// A missing value is only known once the input is read, so on an
+    /// error, part of the output may have been written.
+    ///
+    /// ```
+    /// use nom_json_parser::{Patch, ReaderEvents, WriteOptions};
+    ///
+    /// let patch = Patch::new()
+    ///     .replace("/name", "new")?
+    ///     .remove("/tags/0")?
+    ///     .add("/tags/-", "z")?;
+    /// let input = r#"{"name": "old", "tags": ["x", "y"], "size": 3}"#;
+    /// let mut out = Vec::new();
+    /// let events = ReaderEvents::new(input.as_bytes());
+    /// patch.rewrite(events, &mut out, &WriteOptions::compact())?;
+    /// assert_eq!(out, br#"{"name":"new","tags":["y","z"],"size":3}"#);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rewrite<R: BufRead, W: Write>(
+        &self,
+        events: ReaderEvents<R>,
+        writer: W,
+        options: &WriteOptions,
+    ) -> Result<u64, RewriteError> {
+        if let Some(i) = self.operations().iter().position(|operation| {
+            !matches!(
+                operation,
+                Operation::Add { .. } | Operation::Replace { .. } | Operation::Remove { .. }
+            ) || matches!(operation, Operation::Remove { path } if path.is_root())
+        }) {
+            return Err(RewriteError::Unsupported(i));
+        }
+        let mut rewriter = Rewriter {
+            events,
+            operations: self.operations(),
+            applied: vec![false; self.len()],
+            writer,
+            options,
+            out: String::new(),
+            written: 0,
+            pointer: JsonPointer::root(),
+            open: Vec::new(),
+            key: None,
+        };
+        rewriter.run()?;
+        match rewriter.applied.iter().position(|applied| !applied) {
+            Some(i) => Err(RewriteError::Missing(path(&self.operations()[i]).clone())),
+            None => Ok(rewriter.written),
+        }
+    }
+}
+
+/// the pointer an operation is on
+fn path(operation: &Operation) -> &JsonPointer {
+    match operation {
+        Operation::Add { path, .. }
+        | Operation::Remove { path }
+        | Operation::Replace { path, .. }
+        | Operation::Move { path, .. }
+        | Operation::Copy { path, .. }
+        | Operation::Test { path, .. } => path,
+    }
+}
+
+struct Rewriter<'p, R, W> {
+    events: ReaderEvents<R>,
+    operations: &'p [Operation],
+    applied: Vec<bool>,
+    writer: W,
+    options: &'p WriteOptions,
+    /// the output not yet written
+    out: String,
+    written: u64,
+    /// the pointer of the innermost container read
+    pointer: JsonPointer,
+    /// the containers read, innermost last
+    open: Vec<Open>,
+    /// the key of the next member value
+    key: Option<Cow<'static, str>>,
+}
+
+struct Open {
+    /// the index of the next element of the input, `None` for an object
+    index: Option<usize>,
+    /// the length of the pointer without the token of the container
+    len: usize,
+    /// elements or members written
+    written: usize,
+}
+
+impl<R: BufRead, W: Write> Rewriter<'_, R, W> {
+    fn run(&mut self) -> Result<(), RewriteError> {
+        while let Some(event) = self.events.next().transpose()? {
+            match event {
+                Event::Key(key) => {
+                    self.key = Some(key);
+                    continue;
+                }
+                Event::EndObject | Event::EndArray => {
+                    // the elements added at the end, or the members that
+                    // weren't there
+                    let index = self.open.last().and_then(|open| open.index);
+                    self.add(|token| match index {
+                        Some(i) => token == "-" || array_index(token) == Some(i),
+                        None => true,
+                    })?;
+                    let open = self.open.pop().expect("an open array or object");
+                    self.out.push(if index.is_some() { ']' } else { '}' });
+                    self.pointer.truncate(open.len);
+                }
+                event => self.value(event)?,
+            }
+            if self.out.len() >= 8 * 1024 {
+                self.flush()?;
+            }
+        }
+        self.flush()
+    }
+
+    /// a value, or the start of an array or object
+    fn value(&mut self, event: Event<'_>) -> Result<(), RewriteError> {
+        let len = self.pointer.len();
+        let in_object = match self.open.last_mut() {
+            Some(Open { index: Some(i), .. }) => {
+                let i = *i;
+                self.add(|token| array_index(token) == Some(i))?;
+                if let Some(Open { index: Some(i), .. }) = self.open.last_mut() {
+                    *i += 1;
+                }
+                self.pointer.push_index(i);
+                false
+            }
+            Some(Open { index: None, .. }) => {
+                let key = self.key.take().unwrap_or_default();
+                self.pointer.push(&key);
+                true
+            }
+            None => false,
+        };
+        let pointer = &self.pointer;
+        let found = self.operations.iter().enumerate().find(|(i, operation)| {
+            !self.applied[*i]
+                && match operation {
+                    Operation::Add { path, .. } => path == pointer && (in_object || path.is_root()),
+                    operation => path(operation) == pointer,
+                }
+        });
+        let container = matches!(event, Event::StartObject | Event::StartArray);
+        match found {
+            Some((i, operation)) => {
+                self.applied[i] = true;
+                if let Operation::Add { value, .. } | Operation::Replace { value, .. } = operation {
+                    self.separator(in_object.then(|| self.pointer.last()).flatten().as_deref());
+                    write_flat(&mut self.out, value, &self.pointer, self.options)?;
+                }
+                if container {
+                    let depth = self.events.depth();
+                    while self.events.depth() >= depth {
+                        self.events.next().transpose()?;
+                    }
+                }
+            }
+            None => {
+                let key = self.pointer.last();
+                self.separator(if in_object { key.as_deref() } else { None });
+                match event {
+                    Event::StartObject | Event::StartArray => {
+                        let array = event == Event::StartArray;
+                        self.out.push(if array { '[' } else { '{' });
+                        self.open.push(Open {
+                            index: array.then_some(0),
+                            len,
+                            written: 0,
+                        });
+                        return Ok(());
+                    }
+                    Event::String(s) => write_string(&mut self.out, &s, self.options),
+                    Event::Number(n) => {
+                        let value = JsonValue::<String>::Number(n);
+                        write_flat(&mut self.out, &value, &self.pointer, self.options)?;
+                    }
+                    Event::Bool(b) => self.out.push_str(if b { "true" } else { "false" }),
+                    _ => self.out.push_str("null"),
+                }
+            }
+        }
+        self.pointer.truncate(len);
+        Ok(())
+    }
+
+    /// the values added to the innermost container at a token for which
+    /// `at` is true
+    fn add(&mut self, at: impl Fn(&str) -> bool) -> Result<(), RewriteError> {
+        let in_object = matches!(self.open.last(), Some(Open { index: None, .. }));
+        for (i, operation) in self.operations.iter().enumerate() {
+            let Operation::Add { path, value } = operation else {
+                continue;
+            };
+            if self.applied[i] || path.parent().as_ref() != Some(&self.pointer) {
+                continue;
+            }
+            let token = path.last().unwrap_or_default();
+            if at(&token) {
+                self.applied[i] = true;
+                self.separator(in_object.then_some(token.as_str()));
+                write_flat(&mut self.out, value, path, self.options)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// the `,` before an element or member, and the key of a member
+    fn separator(&mut self, key: Option<&str>) {
+        let (comma, colon) = self.options.separators();
+        if let Some(open) = self.open.last_mut() {
+            if open.written > 0 {
+                self.out.push_str(comma);
+            }
+            open.written += 1;
+        }
+        if let Some(key) = key {
+            write_string(&mut self.out, key, self.options);
+            self.out.push_str(colon);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), RewriteError> {
+        let write = |writer: &mut W, out: &str| -> io::Result<()> {
+            writer.write_all(out.as_bytes())?;
+            writer.flush()
+        };
+        write(&mut self.writer, &self.out).map_err(RewriteError::Io)?;
+        self.written += self.out.len() as u64;
+        self.out.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse, ErrorKind, NonFiniteNumbers, ParserOptions, Patch, ReaderEvents, WriteOptions,
+    };
+
+    use std::io;
+
+    use super::RewriteError;
+
+    fn rewritten(patch: &Patch, input: &str) -> Result<String, RewriteError> {
+        let mut out = Vec::new();
+        let events = ReaderEvents::new(input.as_bytes());
+        let written = patch.rewrite(events, &mut out, &WriteOptions::compact())?;
+        assert_eq!(written, out.len() as u64);
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let input = r#"{"a": [1, 2, 3], "b": {"c": null, "d": [{}]}, "e": "é"}"#;
+        let patch = Patch::new()
+            .add("/a/0", 0)
+            .and_then(|patch| patch.add("/a/2", "x"))
+            .and_then(|patch| patch.remove("/a/2"))
+            .and_then(|patch| patch.add("/a/3", 4))
+            .and_then(|patch| patch.add("/a/-", 5))
+            .and_then(|patch| patch.replace("/b/d", parse("[true]").unwrap()))
+            .and_then(|patch| patch.add("/b/c", 1))
+            .and_then(|patch| patch.add("/b/f", "new"))
+            .and_then(|patch| patch.remove("/e"))
+            .unwrap();
+        assert_eq!(
+            rewritten(&patch, input).unwrap(),
+            r#"{"a":[0,1,2,"x",4,5],"b":{"c":1,"d":[true],"f":"new"}}"#
+        );
+
+        // strings and numbers written as `to_string` writes them
+        let input = " [\"a\\/\", -0, 1e2, {\"\": []}] ";
+        let output = r#"["a/",-0.0,100.0,{"":[]}]"#;
+        assert_eq!(rewritten(&Patch::new(), input).unwrap(), output);
+        assert_eq!(parse(input).unwrap().to_string(), output);
+
+        let patch = Patch::new().replace("", "x").unwrap();
+        assert_eq!(rewritten(&patch, input).unwrap(), r#""x""#);
+        let patch = Patch::new()
+            .add("/0", 1)
+            .and_then(|patch| patch.add("/0", 2))
+            .unwrap();
+        assert_eq!(rewritten(&patch, "[]").unwrap(), "[1,2]");
+        let patch = Patch::new()
+            .remove("/0")
+            .and_then(|patch| patch.remove("/1"))
+            .unwrap();
+        assert_eq!(rewritten(&patch, "[1, 2, 3]").unwrap(), "[3]");
+
+        // escaped tokens
+        let patch = Patch::new().add("/a~1b", "~").unwrap();
+        assert_eq!(rewritten(&patch, "{}").unwrap(), r#"{"a/b":"~"}"#);
+
+        // with the separators and escapes of the options
+        let patch = Patch::new().add("/a/-", "<").unwrap();
+        let mut out = Vec::new();
+        let options = WriteOptions::pretty().html_safe(true);
+        let events = ReaderEvents::new(&b"{\"a\": [\n1\n]}"[..]);
+        patch.rewrite(events, &mut out, &options).unwrap();
+        assert_eq!(out, br#"{"a": [1, "\u003c"]}"#);
+    }
+
+    #[test]
+    fn test_errors() {
+        let patch = Patch::new().replace("/a/1", 2).unwrap();
+        assert!(matches!(
+            rewritten(&patch, r#"{"a": [1]}"#),
+            Err(RewriteError::Missing(pointer)) if pointer == "/a/1"
+        ));
+        let patch = Patch::new().add("/a/b", 2).unwrap();
+        assert!(matches!(
+            rewritten(&patch, r#"{"a": [1]}"#),
+            Err(RewriteError::Missing(pointer)) if pointer == "/a/b"
+        ));
+        // nothing is applied within a replaced value
+        let patch = Patch::new()
+            .replace("/a", 1)
+            .and_then(|patch| patch.remove("/a/0"))
+            .unwrap();
+        assert!(matches!(
+            rewritten(&patch, r#"{"a": [1]}"#),
+            Err(RewriteError::Missing(pointer)) if pointer == "/a/0"
+        ));
+
+        for patch in [
+            Patch::new().test("/a", 1),
+            Patch::new().copy_from("/a", "/b"),
+            Patch::new().remove(""),
+        ] {
+            let patch = patch.and_then(|patch| patch.add("/x", 1)).unwrap();
+            assert!(matches!(
+                rewritten(&patch, "{}"),
+                Err(RewriteError::Unsupported(0))
+            ));
+        }
+
+        let err = rewritten(&Patch::new(), "[1 2]").unwrap_err();
+        assert!(
+            matches!(err, RewriteError::Parse(err) if err.kind() == ErrorKind::UnexpectedToken)
+        );
+        // in removed values too
+        let patch = Patch::new().remove("/0").unwrap();
+        let err = rewritten(&patch, "[[1 2]]").unwrap_err();
+        assert!(
+            matches!(err, RewriteError::Parse(err) if err.kind() == ErrorKind::UnexpectedToken)
+        );
+
+        let options = ParserOptions::default().non_finite_numbers(NonFiniteNumbers::Accept);
+        let events = ReaderEvents::new(&b"[1, NaN]"[..]).options(options);
+        let options = WriteOptions::compact().non_finite_numbers(NonFiniteNumbers::Error);
+        let err = Patch::new()
+            .rewrite(events, Vec::new(), &options)
+            .unwrap_err();
+        assert!(matches!(err, RewriteError::Write(err) if err.pointer() == "/1"));
+
+        let events = ReaderEvents::new(&b"[1]"[..]);
+        let err = Patch::new()
+            .rewrite(events, &mut [0u8; 2][..], &WriteOptions::compact())
+            .unwrap_err();
+        assert!(matches!(err, RewriteError::Io(err) if err.kind() == io::ErrorKind::WriteZero));
+    }
+}
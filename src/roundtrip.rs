@@ -0,0 +1,106 @@
+//! Checking that documents survive being parsed and written again.
+
+use crate::{
+    parse_with, EqOptions, JsonPointer, LineIndex, ParseError, ParserOptions, Position,
+    WriteOptions,
+};
+
+/// What [`roundtrip`] found.
+#[derive(Debug)]
+pub struct RoundtripReport {
+    /// The input, parsed and written again.
+    pub output: String,
+    /// The error parsing `output`, which the writer should never cause.
+    pub reparse_error: Option<ParseError>,
+    /// The values that differ between the input and `output`, see
+    /// [`EqOptions::diff`]; e.g. infinities, which are written as `null`.
+    pub differences: Vec<JsonPointer>,
+    /// Where `output` first differs from the input, in the input, if it
+    /// does. Only meaningful for lossless round trips: inputs already in the
+    /// style of the [`WriteOptions`] whose members are written in input order
+    /// and whose numbers keep their text.
+    pub text_difference: Option<Position>,
+}
+
+impl RoundtripReport {
+    /// Whether the output means the same as the input.
+    pub fn is_ok(&self) -> bool {
+        self.reparse_error.is_none() && self.differences.is_empty()
+    }
+
+    /// Whether the output is the input, byte for byte.
+    pub fn is_lossless(&self) -> bool {
+        self.is_ok() && self.text_difference.is_none()
+    }
+}
+
+/// Parse `input` with the default options, write it with `options`, parse
+/// the output and compare the two documents and texts.
+///
+/// Fails only when the input doesn't parse.
+pub fn roundtrip(input: &str, options: &WriteOptions) -> Result<RoundtripReport, ParseError> {
+    roundtrip_with(input, &ParserOptions::default(), options)
+}
+
+/// Like [`roundtrip`], parsing with `parser`.
+pub fn roundtrip_with(
+    input: &str,
+    parser: &ParserOptions,
+    options: &WriteOptions,
+) -> Result<RoundtripReport, ParseError> {
+    let value = parse_with(input, parser)?;
+    let output = value.to_string_with(options);
+    let (reparse_error, differences) = match parse_with(&output, parser) {
+        Ok(reparsed) => (None, EqOptions::new().diff(&value, &reparsed)),
+        Err(err) => (Some(err), Vec::new()),
+    };
+    let text_difference = input
+        .char_indices()
+        .zip(output.chars())
+        .find(|&((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .or_else(|| (input.len() != output.len()).then(|| input.len().min(output.len())))
+        .map(|offset| LineIndex::new(input).position(offset));
+    Ok(RoundtripReport {
+        output,
+        reparse_error,
+        differences,
+        text_difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorKind, ParserOptions, WriteOptions};
+
+    use super::{roundtrip, roundtrip_with};
+
+    #[test]
+    fn test_roundtrip() {
+        let report = roundtrip(r#"[{"a": [1, 2.5]}, "é\n"]"#, &WriteOptions::relaxed()).unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(report.output, r#"[{"a": [1, 2.5]}, "é\n"]"#);
+
+        let report = roundtrip("[\n  1e2,\n  true\n]", &WriteOptions::pretty()).unwrap();
+        assert!(report.is_ok());
+        assert!(!report.is_lossless());
+        let at = report.text_difference.unwrap();
+        assert_eq!((at.line, at.column, at.offset), (2, 4, 5));
+        let options = ParserOptions::default().keep_number_text(true);
+        let report = roundtrip_with("[\n  1e2,\n  true\n]", &options, &WriteOptions::pretty());
+        assert!(report.unwrap().is_lossless());
+
+        // a prefix of the output
+        let report = roundtrip("[1]\n", &WriteOptions::compact()).unwrap();
+        assert_eq!(report.text_difference.unwrap().offset, 3);
+
+        let report = roundtrip("[1, -1e400]", &WriteOptions::compact()).unwrap();
+        assert_eq!(report.output, "[1,null]");
+        let pointers: Vec<&str> = report.differences.iter().map(|p| p.as_str()).collect();
+        assert_eq!(pointers, ["/1"]);
+        assert!(!report.is_ok());
+
+        let err = roundtrip("[1,]", &WriteOptions::compact()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+    }
+}
@@ -0,0 +1,67 @@
+//! Tokens of string values, to feed full-text search indexes.
+
+use std::borrow::Borrow;
+
+use crate::{
+    map::{Key, MapKind},
+    JsonPointer, JsonValue,
+};
+
+/// The `(pointer, token)` pairs of every string value in `value`, in walk
+/// order, as split by `tokenize`; see [`words`] for a default tokenizer.
+pub fn string_tokens<S, M, F, I>(
+    value: &JsonValue<S, M>,
+    mut tokenize: F,
+) -> Vec<(JsonPointer, String)>
+where
+    S: Key + Borrow<str>,
+    M: MapKind,
+    F: FnMut(&str) -> I,
+    I: IntoIterator<Item = String>,
+{
+    let mut tokens = Vec::new();
+    for (pointer, value) in value.walk() {
+        if let JsonValue::String(s) = value {
+            for token in tokenize(s.borrow()) {
+                tokens.push((pointer.clone(), token));
+            }
+        }
+    }
+    tokens
+}
+
+/// Lowercased runs of alphanumeric characters.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::{string_tokens, words};
+
+    #[test]
+    fn test_string_tokens() {
+        let value = parse(r#"{"title": "Hello, World!", "tags": ["Ünïcode", 1, "a-b"]}"#).unwrap();
+        let mut tokens = string_tokens(&value, words);
+        tokens.sort();
+        let pair = |pointer: &str, token: &str| (pointer.parse().unwrap(), token.to_owned());
+        assert_eq!(
+            tokens,
+            [
+                pair("/tags/0", "ünïcode"),
+                pair("/tags/2", "a"),
+                pair("/tags/2", "b"),
+                pair("/title", "hello"),
+                pair("/title", "world"),
+            ]
+        );
+
+        let tokens = string_tokens(&value, |s| Some(s.len().to_string()));
+        assert_eq!(tokens.len(), 3);
+    }
+}
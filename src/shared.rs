@@ -0,0 +1,234 @@
+//! A DOM whose subtrees can be shared between documents and within one.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    map::Map,
+    pointer::{array_index, unescape},
+    JsonValue, Number,
+};
+
+/// A JSON value whose strings, arrays and objects are reference counted,
+/// so cloning one, or any part of it, doesn't copy it.
+///
+/// [`dedupe_subtrees`](SharedValue::dedupe_subtrees) makes identical
+/// subtrees one allocation, which shrinks documents with heavy repetition,
+/// such as machine-generated reports, to the size of their distinct parts.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use nom_json_parser::{parse, JsonValue, SharedValue};
+///
+/// let value = parse(r#"[{"a": [1, 2]}, {"a": [1, 2]}]"#)?;
+/// let mut shared = SharedValue::from(value.clone());
+/// shared.dedupe_subtrees();
+/// let (Some(SharedValue::Object(first)), Some(SharedValue::Object(second))) =
+///     (shared.pointer("/0"), shared.pointer("/1"))
+/// else {
+///     unreachable!();
+/// };
+/// assert!(Arc::ptr_eq(first, second));
+/// assert_eq!(JsonValue::from(&shared), value);
+/// # Ok::<(), nom_json_parser::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    String(Arc<str>),
+    Bool(bool),
+    Null,
+    Number(Number),
+    Object(Arc<Map<Arc<str>, SharedValue>>),
+    Array(Arc<[SharedValue]>),
+}
+
+impl SharedValue {
+    /// The value at the JSON Pointer (RFC 6901) `pointer`, as
+    /// [`JsonValue::pointer`] finds it.
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape)
+            .try_fold(self, |value, token| match value {
+                SharedValue::Object(map) => map.get(token.as_str()),
+                SharedValue::Array(items) => items.get(array_index(&token)?),
+                _ => None,
+            })
+    }
+
+    /// Share every subtree with the first one identical to it: strings,
+    /// keys, arrays and objects with the same content become one
+    /// allocation.
+    ///
+    /// Numbers are identical when they're written the same, so `1` and
+    /// `1.0` stay apart. Subtrees are compared through their children,
+    /// already shared, so this takes time linear in the size of the value.
+    pub fn dedupe_subtrees(&mut self) {
+        Deduper::default().share(self);
+    }
+}
+
+/// a subtree, by its scalars and the addresses of its shared children
+#[derive(PartialEq, Eq, Hash)]
+enum Node {
+    String(Arc<str>),
+    Array(Vec<Id>),
+    /// members in the order of their keys' addresses
+    Object(Vec<(usize, Id)>),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Id {
+    Null,
+    Bool(bool),
+    /// as written
+    Number(String),
+    /// the allocation of a string, array or object
+    Shared(usize),
+}
+
+#[derive(Default)]
+struct Deduper {
+    nodes: HashMap<Node, SharedValue>,
+}
+
+impl Deduper {
+    /// `value`, its children already shared, and its [`Id`]
+    fn share(&mut self, value: &mut SharedValue) -> Id {
+        let node = match value {
+            SharedValue::Null => return Id::Null,
+            SharedValue::Bool(b) => return Id::Bool(*b),
+            SharedValue::Number(n) => return Id::Number(n.to_string()),
+            SharedValue::String(s) => Node::String(s.clone()),
+            SharedValue::Array(items) => {
+                let mut items = items.to_vec();
+                let ids = items.iter_mut().map(|item| self.share(item)).collect();
+                *value = SharedValue::Array(items.into());
+                Node::Array(ids)
+            }
+            SharedValue::Object(map) => {
+                let mut object = Map::new();
+                let mut ids = Vec::with_capacity(map.len());
+                for (key, item) in map.iter() {
+                    let mut key = SharedValue::String(key.clone());
+                    let (Id::Shared(key_id), SharedValue::String(key)) =
+                        (self.share(&mut key), key)
+                    else {
+                        unreachable!("a key is a shared string");
+                    };
+                    let mut item = item.clone();
+                    ids.push((key_id, self.share(&mut item)));
+                    object.insert(key, item);
+                }
+                ids.sort_unstable_by_key(|(key, _)| *key);
+                *value = SharedValue::Object(Arc::new(object));
+                Node::Object(ids)
+            }
+        };
+        let shared = self.nodes.entry(node).or_insert_with(|| value.clone());
+        *value = shared.clone();
+        Id::Shared(address(value))
+    }
+}
+
+fn address(value: &SharedValue) -> usize {
+    match value {
+        SharedValue::String(s) => Arc::as_ptr(s) as *const u8 as usize,
+        SharedValue::Array(items) => Arc::as_ptr(items) as *const u8 as usize,
+        SharedValue::Object(map) => Arc::as_ptr(map) as *const u8 as usize,
+        _ => 0,
+    }
+}
+
+impl From<JsonValue> for SharedValue {
+    fn from(value: JsonValue) -> Self {
+        match value {
+            JsonValue::String(s) => SharedValue::String(s.into()),
+            JsonValue::Bool(b) => SharedValue::Bool(b),
+            JsonValue::Null => SharedValue::Null,
+            JsonValue::Number(n) => SharedValue::Number(n),
+            JsonValue::Object(map) => {
+                let mut object = Map::new();
+                for (key, value) in map {
+                    object.insert(key.into(), SharedValue::from(value));
+                }
+                SharedValue::Object(Arc::new(object))
+            }
+            JsonValue::Array(items) => {
+                SharedValue::Array(items.into_iter().map(SharedValue::from).collect())
+            }
+        }
+    }
+}
+
+impl From<&SharedValue> for JsonValue {
+    fn from(value: &SharedValue) -> Self {
+        match value {
+            SharedValue::String(s) => JsonValue::String(s.to_string()),
+            SharedValue::Bool(b) => JsonValue::Bool(*b),
+            SharedValue::Null => JsonValue::Null,
+            SharedValue::Number(n) => JsonValue::Number(n.clone()),
+            SharedValue::Object(map) => {
+                let mut object = Map::new();
+                for (key, value) in map.iter() {
+                    object.insert(key.to_string(), JsonValue::from(value));
+                }
+                JsonValue::Object(object)
+            }
+            SharedValue::Array(items) => {
+                JsonValue::Array(items.iter().map(JsonValue::from).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{parse, JsonValue};
+
+    use super::SharedValue;
+
+    #[test]
+    fn test_dedupe_subtrees() {
+        let input = r#"{
+            "a": [{"x": [1, "s"], "y": {}}, {"y": {}, "x": [1, "s"]}],
+            "b": [1, "s"],
+            "c": [1.0, "s"],
+            "d": "s"
+        }"#;
+        let value = parse(input).unwrap();
+        let mut shared = SharedValue::from(value.clone());
+        shared.dedupe_subtrees();
+        assert_eq!(JsonValue::from(&shared), value);
+
+        let same = |a: &str, b: &str| match (shared.pointer(a), shared.pointer(b)) {
+            (Some(SharedValue::Array(a)), Some(SharedValue::Array(b))) => Arc::ptr_eq(a, b),
+            (Some(SharedValue::Object(a)), Some(SharedValue::Object(b))) => Arc::ptr_eq(a, b),
+            (Some(SharedValue::String(a)), Some(SharedValue::String(b))) => Arc::ptr_eq(a, b),
+            _ => panic!("no containers or strings at {} and {}", a, b),
+        };
+        assert!(same("/a/0", "/a/1"));
+        assert!(same("/a/0/x", "/b"));
+        assert!(same("/a/0/y", "/a/1/y"));
+        assert!(same("/a/1/x/1", "/d"));
+        // numbers written differently
+        assert!(!same("/b", "/c"));
+        assert!(same("/b/1", "/c/1"));
+
+        // keys too
+        let SharedValue::Object(map) = shared.pointer("/a/1").unwrap() else {
+            unreachable!();
+        };
+        let key = map.keys().find(|key| &***key == "x").unwrap();
+        let SharedValue::Object(map) = shared.pointer("/a/0").unwrap() else {
+            unreachable!();
+        };
+        assert!(map.keys().any(|other| Arc::ptr_eq(key, other)));
+    }
+}
@@ -0,0 +1,132 @@
+//! Counters of the work a parse does, for tracking performance over time.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use crate::{
+    parse_dom_state,
+    parser::{parse_root_dom, ParseState},
+    JsonValue, ParseError, ParserOptions,
+};
+
+/// What [`parse_with_stats`] counted during a parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ParseStats {
+    /// Heap allocations and reallocations on the parsing thread, only
+    /// counted with [`CountingAllocator`] installed.
+    pub allocations: usize,
+    /// Elements of the longest array.
+    pub max_array_len: usize,
+    /// Members of the largest object.
+    pub max_object_len: usize,
+    /// Bytes of the strings and keys decoded into owned `String`s.
+    pub string_bytes: usize,
+}
+
+/// A [`GlobalAlloc`] wrapping [`System`] that counts the allocations of each
+/// thread, for [`ParseStats::allocations`].
+///
+/// Installed in a benchmark or test binary with
+///
+/// ```
+/// use nom_json_parser::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// count an allocation, unless the thread is being torn down
+fn count() {
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+/// allocations of the current thread so far
+fn allocations() -> usize {
+    ALLOCATIONS.try_with(Cell::get).unwrap_or(0)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Like [`parse_with`](crate::parse_with), also counting the work done; see
+/// [`ParseStats`].
+///
+/// The sizes are those of the pass that produced the result, the
+/// allocations those of the whole call.
+pub fn parse_with_stats(
+    s: &str,
+    options: &ParserOptions,
+) -> (Result<JsonValue, ParseError>, ParseStats) {
+    let before = allocations();
+    let st = ParseState::new(options).holding_logs();
+    let (rst, mut stats) = match parse_root_dom::<JsonValue, ()>(s, &st) {
+        Ok(("", value)) => {
+            st.release_logs();
+            (Ok(value), st.stats())
+        }
+        _ => {
+            let st = st.restart();
+            (parse_dom_state(s, &st), st.stats())
+        }
+    };
+    stats.allocations = allocations() - before;
+    (rst, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorKind, ParserOptions};
+
+    use super::{parse_with_stats, CountingAllocator};
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn test_parse_with_stats() {
+        let options = ParserOptions::default();
+        let (value, stats) =
+            parse_with_stats(r#"{"ab": [1, "xyz", [], {"c": "é"}], "d": 2}"#, &options);
+        assert!(value.is_ok());
+        assert_eq!(stats.max_array_len, 4);
+        assert_eq!(stats.max_object_len, 2);
+        // "ab", "xyz", "c", "é" and "d"
+        assert_eq!(stats.string_bytes, 9);
+        assert!(stats.allocations > 0);
+
+        let (value, stats) = parse_with_stats("[1, 2, 3,]", &options);
+        assert_eq!(value.unwrap_err().kind(), ErrorKind::UnexpectedToken);
+        assert_eq!(stats.max_array_len, 0);
+        assert_eq!(stats.string_bytes, 0);
+
+        let options = ParserOptions::default().max_array_len(Some(2));
+        let (_, stats) = parse_with_stats("[1, 2, 3]", &options);
+        assert_eq!(stats.max_array_len, 2);
+    }
+}
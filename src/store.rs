@@ -0,0 +1,173 @@
+//! Many documents sharing their object keys.
+
+use std::{collections::HashSet, fmt, sync::Arc};
+
+use crate::{map::Map, parse_with, JsonValue, ParseError, ParserOptions};
+
+/// A value held by a [`JsonStore`].
+pub type StoredValue = JsonValue<Arc<str>>;
+
+/// Identifier of a document in a [`JsonStore`], in order of insertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocId(usize);
+
+impl DocId {
+    /// Index of the document in insertion order.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for DocId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A collection of documents, queried together.
+///
+/// Object keys are interned: every occurrence of a key, in any document,
+/// shares one allocation, which makes stores of similar documents such as
+/// log records much smaller than the documents themselves.
+#[derive(Debug, Default)]
+pub struct JsonStore {
+    keys: HashSet<Arc<str>>,
+    docs: Vec<StoredValue>,
+}
+
+impl JsonStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of documents.
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// Number of distinct object keys across all documents.
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn insert(&mut self, value: JsonValue) -> DocId {
+        let value = self.intern(value);
+        self.docs.push(value);
+        DocId(self.docs.len() - 1)
+    }
+
+    /// Parse `s` with the default options and insert it.
+    pub fn insert_str(&mut self, s: &str) -> Result<DocId, ParseError> {
+        self.insert_str_with(s, &ParserOptions::default())
+    }
+
+    pub fn insert_str_with(
+        &mut self,
+        s: &str,
+        options: &ParserOptions,
+    ) -> Result<DocId, ParseError> {
+        Ok(self.insert(parse_with(s, options)?))
+    }
+
+    pub fn get(&self, id: DocId) -> Option<&StoredValue> {
+        self.docs.get(id.0)
+    }
+
+    /// Every document, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (DocId, &StoredValue)> {
+        self.docs.iter().enumerate().map(|(i, doc)| (DocId(i), doc))
+    }
+
+    /// The value at `pointer` in every document that has one; see
+    /// [`JsonValue::pointer`].
+    pub fn query<'s>(
+        &'s self,
+        pointer: &'s str,
+    ) -> impl Iterator<Item = (DocId, &'s StoredValue)> + 's {
+        self.iter()
+            .filter_map(move |(id, doc)| Some((id, doc.pointer(pointer)?)))
+    }
+
+    /// The documents whose value at `pointer` satisfies `predicate`.
+    pub fn find<'s>(
+        &'s self,
+        pointer: &'s str,
+        predicate: impl Fn(&StoredValue) -> bool + 's,
+    ) -> impl Iterator<Item = DocId> + 's {
+        self.query(pointer)
+            .filter(move |(_, value)| predicate(value))
+            .map(|(id, _)| id)
+    }
+
+    fn intern(&mut self, value: JsonValue) -> StoredValue {
+        match value {
+            JsonValue::String(s) => JsonValue::String(s.into()),
+            JsonValue::Bool(b) => JsonValue::Bool(b),
+            JsonValue::Null => JsonValue::Null,
+            JsonValue::Number(n) => JsonValue::Number(n),
+            JsonValue::Object(map) => {
+                let mut object = Map::new();
+                for (key, value) in map {
+                    let key = match self.keys.get(key.as_str()) {
+                        Some(key) => key.clone(),
+                        None => {
+                            let key: Arc<str> = key.into();
+                            self.keys.insert(key.clone());
+                            key
+                        }
+                    };
+                    object.insert(key, self.intern(value));
+                }
+                JsonValue::Object(object)
+            }
+            JsonValue::Array(items) => {
+                JsonValue::Array(items.into_iter().map(|item| self.intern(item)).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::JsonValue;
+
+    use super::{DocId, JsonStore};
+
+    #[test]
+    fn test_store() {
+        let mut store = JsonStore::new();
+        let a = store
+            .insert_str(r#"{"level": "info", "msg": "a"}"#)
+            .unwrap();
+        let b = store
+            .insert_str(r#"{"level": "error", "msg": "b", "ctx": {"msg": "c"}}"#)
+            .unwrap();
+        assert!(store.insert_str("{").is_err());
+        assert_eq!((a, b, store.len()), (DocId(0), DocId(1), 2));
+        assert_eq!(store.key_count(), 3);
+
+        let error = JsonValue::String(Arc::from("error"));
+        assert_eq!(
+            store
+                .find("/level", |level| *level == error)
+                .collect::<Vec<_>>(),
+            [b]
+        );
+        assert_eq!(store.query("/ctx/msg").count(), 1);
+        assert_eq!(store.query("").count(), 2);
+
+        // one allocation per key, shared by every document
+        let key = |id, pointer: &str| match store.get(id).and_then(|doc| doc.pointer(pointer)) {
+            Some(JsonValue::Object(map)) => map.keys().find(|key| &***key == "msg").cloned(),
+            _ => None,
+        };
+        let (first, second) = (key(a, "").unwrap(), key(b, "/ctx").unwrap());
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
@@ -0,0 +1,302 @@
+//! Picking the values at some paths out of a document as its events are
+//! read.
+
+use std::borrow::Cow;
+
+use crate::{
+    error::ErrorKind, map::Map, Event, InvalidPointer, JsonEvents, JsonPointer, JsonValue,
+    ParseError,
+};
+
+/// The values of a document at the paths matching some patterns, with
+/// their pointers, in document order. See [`JsonEvents::subscribe`].
+#[derive(Debug)]
+pub struct Subscription<'a> {
+    events: JsonEvents<'a>,
+    /// the escaped tokens of each pattern
+    patterns: Vec<Vec<String>>,
+    /// the pointer of the innermost container read
+    pointer: JsonPointer,
+    /// the containers read, innermost last
+    open: Vec<Open>,
+    /// the key of the next member value
+    key: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug)]
+struct Open {
+    /// the index of the next element, `None` for an object
+    index: Option<usize>,
+    /// the length of the pointer without the token of the container
+    len: usize,
+}
+
+/// how a pointer relates to a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    None,
+    /// the pattern matches some values within
+    Within,
+    Exact,
+}
+
+impl<'a> JsonEvents<'a> {
+    /// Read the document for the values at the pointers matching one of
+    /// `patterns`, where a `*` token matches any member or element.
+    ///
+    /// Only the matching values are built: the containers no pattern goes
+    /// into are skipped over, event by event. A value matching a pattern
+    /// is returned whole, so the values within it are never returned on
+    /// their own. The options of the events apply.
+    ///
+    /// ```
+    /// use nom_json_parser::{JsonEvents, JsonValue};
+    ///
+    /// let input = r#"{"data": [{"events": [1, 2]}, {"events": [3], "x": [4]}]}"#;
+    /// let values: Vec<_> = JsonEvents::new(input)
+    ///     .subscribe(["/data/*/events/*"])?
+    ///     .map(|rst| rst.map(|(pointer, value)| (pointer.to_string(), value)))
+    ///     .collect::<Result<_, _>>()?;
+    /// assert_eq!(
+    ///     values,
+    ///     [
+    ///         ("/data/0/events/0".to_owned(), JsonValue::from(1)),
+    ///         ("/data/0/events/1".to_owned(), JsonValue::from(2)),
+    ///         ("/data/1/events/0".to_owned(), JsonValue::from(3)),
+    ///     ]
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn subscribe<I>(self, patterns: I) -> Result<Subscription<'a>, InvalidPointer>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let pointer = JsonPointer::parse(pattern.as_ref())?;
+                Ok(pointer
+                    .as_str()
+                    .split('/')
+                    .skip(1)
+                    .map(str::to_owned)
+                    .collect())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Subscription {
+            events: self,
+            patterns,
+            pointer: JsonPointer::root(),
+            open: Vec::new(),
+            key: None,
+        })
+    }
+}
+
+impl<'a> Subscription<'a> {
+    fn next_event(&mut self) -> Result<Event<'a>, ParseError> {
+        match self.events.next() {
+            Some(rst) => rst,
+            None => Err(self.events.error(ErrorKind::UnexpectedEof)),
+        }
+    }
+
+    /// how the pointer relates to the patterns
+    fn matches(&self) -> Match {
+        self.patterns
+            .iter()
+            .map(|pattern| {
+                let mut tokens = self.pointer.as_str().split('/').skip(1);
+                for expected in pattern {
+                    match tokens.next() {
+                        Some(token) if expected == "*" || expected == token => {}
+                        Some(_) => return Match::None,
+                        None => return Match::Within,
+                    }
+                }
+                if tokens.next().is_none() {
+                    Match::Exact
+                } else {
+                    Match::None
+                }
+            })
+            .max()
+            .unwrap_or(Match::None)
+    }
+
+    /// the value starting with `event`
+    fn build(&mut self, mut event: Event<'a>) -> Result<JsonValue, ParseError> {
+        // the arrays and objects being built, with the key of the next member
+        let mut stack: Vec<(JsonValue, Option<String>)> = Vec::new();
+        loop {
+            let value = match event {
+                Event::StartObject => {
+                    stack.push((JsonValue::Object(Map::default()), None));
+                    None
+                }
+                Event::StartArray => {
+                    stack.push((JsonValue::Array(Vec::new()), None));
+                    None
+                }
+                Event::Key(key) => {
+                    if let Some((_, next)) = stack.last_mut() {
+                        *next = Some(key.into_owned());
+                    }
+                    None
+                }
+                Event::EndObject | Event::EndArray => stack.pop().map(|(value, _)| value),
+                Event::String(s) => Some(JsonValue::String(s.into_owned())),
+                Event::Number(n) => Some(JsonValue::Number(n)),
+                Event::Bool(b) => Some(JsonValue::Bool(b)),
+                Event::Null => Some(JsonValue::Null),
+            };
+            if let Some(value) = value {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some((JsonValue::Array(items), _)) => items.push(value),
+                    Some((JsonValue::Object(map), key)) => {
+                        map.insert(key.take().unwrap_or_default(), value);
+                    }
+                    Some(_) => unreachable!("only arrays and objects are stacked"),
+                }
+            }
+            event = self.next_event()?;
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<(JsonPointer, JsonValue)>, ParseError> {
+        loop {
+            let Some(event) = self.events.next().transpose()? else {
+                return Ok(None);
+            };
+            let len = self.pointer.len();
+            match (&event, self.open.last_mut()) {
+                (Event::Key(key), _) => {
+                    self.key = Some(key.clone());
+                    continue;
+                }
+                (Event::EndObject | Event::EndArray, _) => {
+                    if let Some(open) = self.open.pop() {
+                        self.pointer.truncate(open.len);
+                    }
+                    continue;
+                }
+                (_, Some(Open { index: Some(i), .. })) => {
+                    self.pointer.push_index(*i);
+                    *i += 1;
+                }
+                (_, Some(Open { index: None, .. })) => {
+                    let key = self.key.take().unwrap_or_default();
+                    self.pointer.push(&key);
+                }
+                (_, None) => {}
+            }
+            let container = match event {
+                Event::StartObject => Some(None),
+                Event::StartArray => Some(Some(0)),
+                _ => None,
+            };
+            match (self.matches(), container) {
+                (Match::Exact, _) => {
+                    let value = self.build(event)?;
+                    let pointer = self.pointer.clone();
+                    self.pointer.truncate(len);
+                    return Ok(Some((pointer, value)));
+                }
+                (Match::Within, Some(index)) => {
+                    self.open.push(Open { index, len });
+                }
+                (Match::None, Some(_)) => {
+                    self.events.skip_container()?;
+                    self.pointer.truncate(len);
+                }
+                (_, None) => self.pointer.truncate(len),
+            }
+        }
+    }
+}
+
+impl Iterator for Subscription<'_> {
+    type Item = Result<(JsonPointer, JsonValue), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorKind, JsonEvents, JsonValue, ParserOptions};
+
+    fn subscribed(input: &str, patterns: &[&str]) -> Result<Vec<(String, JsonValue)>, ErrorKind> {
+        JsonEvents::new(input)
+            .subscribe(patterns)
+            .unwrap()
+            .map(|rst| rst.map(|(pointer, value)| (pointer.to_string(), value)))
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.kind())
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let input = r#"{
+            "data": [
+                {"id": 1, "events": [{"t": "a"}, {"t": "b"}]},
+                {"id": 2, "events": []},
+                {"id": 3, "tags": ["x"], "events": [{"t": "c"}]}
+            ],
+            "a/b": {"~": true}
+        }"#;
+        let value = crate::parse(input).unwrap();
+        let rst = subscribed(input, &["/data/*/events/*"]).unwrap();
+        let pointers: Vec<_> = rst.iter().map(|(pointer, _)| pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            ["/data/0/events/0", "/data/0/events/1", "/data/2/events/0"]
+        );
+        for (pointer, found) in &rst {
+            assert_eq!(value.pointer(pointer), Some(found));
+        }
+
+        // values in document order, the ones within a match not on their own
+        let rst = subscribed(input, &["/data/*/id", "/data/1", "/data/1/id"]).unwrap();
+        let pointers: Vec<_> = rst.iter().map(|(pointer, _)| pointer.as_str()).collect();
+        assert_eq!(pointers, ["/data/0/id", "/data/1", "/data/2/id"]);
+
+        // escaped tokens
+        let rst = subscribed(input, &["/a~1b/~0"]).unwrap();
+        assert_eq!(rst, [("/a~1b/~0".to_owned(), JsonValue::Bool(true))]);
+
+        assert_eq!(subscribed(input, &[""]).unwrap(), [(String::new(), value)]);
+        assert_eq!(subscribed(input, &["/*/*/*/*/*/*"]).unwrap(), []);
+        assert_eq!(subscribed(input, &[]).unwrap(), []);
+        assert_eq!(subscribed("1", &["/*"]).unwrap(), []);
+        assert!(JsonEvents::new("[]").subscribe(["a"]).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        // errors in skipped values too
+        assert_eq!(
+            subscribed(r#"[[1 2], 3]"#, &["/1"]),
+            Err(ErrorKind::UnexpectedToken)
+        );
+        assert_eq!(
+            subscribed(r#"[1, [2"#, &["/1"]),
+            Err(ErrorKind::UnexpectedEof)
+        );
+        assert_eq!(
+            subscribed(r#"[1] 2"#, &["/0"]),
+            Err(ErrorKind::TrailingCharacters)
+        );
+
+        let options = ParserOptions::default().max_depth(Some(2));
+        let rst = JsonEvents::new("[[[1]], 2]")
+            .options(options)
+            .subscribe(["/1"])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(rst.unwrap_err().kind(), ErrorKind::DepthLimitExceeded);
+    }
+}
@@ -0,0 +1,111 @@
+//! The `{"$type": tag, "value": value}` convention for values carrying a
+//! type discriminator, such as the variants of an enum.
+
+use std::borrow::Borrow;
+
+use crate::{
+    map::{Key, MapKind, Object},
+    JsonValue,
+};
+
+/// Key of the tag of a tagged value.
+pub const TAG_KEY: &str = "$type";
+
+/// Key of the content of a tagged value.
+pub const VALUE_KEY: &str = "value";
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// The tag and content of a tagged value: an object with exactly the
+    /// members [`TAG_KEY`], a string, and [`VALUE_KEY`].
+    pub fn as_tagged(&self) -> Option<(&str, &JsonValue<S, M>)> {
+        let JsonValue::Object(map) = self else {
+            return None;
+        };
+        match (map.len(), map.get(TAG_KEY), map.get(VALUE_KEY)) {
+            (2, Some(JsonValue::String(tag)), Some(value)) => Some((tag.borrow(), value)),
+            _ => None,
+        }
+    }
+
+    /// The tag and content of a tagged value, taken out of it, or the value
+    /// itself if it isn't tagged.
+    ///
+    /// Enums are converted from tagged values by matching on the tag:
+    ///
+    /// ```
+    /// use nom_json_parser::{parse, JsonValue};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Shape {
+    ///     Circle(f64),
+    ///     Label(String),
+    /// }
+    ///
+    /// impl TryFrom<JsonValue> for Shape {
+    ///     type Error = JsonValue;
+    ///
+    ///     fn try_from(value: JsonValue) -> Result<Self, JsonValue> {
+    ///         match value.into_tagged()? {
+    ///             (tag, JsonValue::Number(r)) if tag == "circle" => {
+    ///                 Ok(Shape::Circle(r.as_f64().unwrap_or_default()))
+    ///             }
+    ///             (tag, JsonValue::String(s)) if tag == "label" => Ok(Shape::Label(s)),
+    ///             (tag, value) => Err(JsonValue::tagged(tag, value)),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let value = parse(r#"{"$type": "circle", "value": 2}"#).unwrap();
+    /// assert_eq!(Shape::try_from(value), Ok(Shape::Circle(2.0)));
+    /// ```
+    pub fn into_tagged(self) -> Result<(S, JsonValue<S, M>), Self> {
+        if self.as_tagged().is_none() {
+            return Err(self);
+        }
+        let JsonValue::Object(mut map) = self else {
+            return Err(self);
+        };
+        match (map.remove(TAG_KEY), map.remove(VALUE_KEY)) {
+            (Some(JsonValue::String(tag)), Some(value)) => Ok((tag, value)),
+            _ => Err(JsonValue::Object(map)),
+        }
+    }
+}
+
+impl<S: Key + From<String>, M: MapKind> JsonValue<S, M> {
+    /// The tagged value `{"$type": tag, "value": value}`.
+    pub fn tagged(tag: impl Into<String>, value: JsonValue<S, M>) -> Self {
+        let mut map = M::Map::with_capacity(2);
+        map.insert(
+            S::from(TAG_KEY.to_owned()),
+            JsonValue::String(S::from(tag.into())),
+        );
+        map.insert(S::from(VALUE_KEY.to_owned()), value);
+        JsonValue::Object(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn test_tagged() {
+        let value = parse(r#"{"value": [1], "$type": "list"}"#).unwrap();
+        let content = parse("[[1]]").unwrap().pointer("/0").cloned().unwrap();
+        assert_eq!(value.as_tagged(), Some(("list", &content)));
+        assert_eq!(JsonValue::tagged("list", content.clone()), value);
+        assert_eq!(value.into_tagged(), Ok(("list".to_owned(), content)));
+
+        for input in [
+            r#"{"$type": "list"}"#,
+            r#"{"$type": 1, "value": 2}"#,
+            r#"{"$type": "list", "value": 2, "x": 3}"#,
+            "[1, 2]",
+        ] {
+            let value = parse(input).unwrap();
+            assert_eq!(value.as_tagged(), None, "{}", input);
+            assert_eq!(value.clone().into_tagged(), Err(value));
+        }
+    }
+}
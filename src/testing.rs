@@ -0,0 +1,196 @@
+//! Random [`JsonValue`]s for property-based tests.
+//!
+//! Enabled with the `testing` feature: an [`arbitrary::Arbitrary`] impl for
+//! fuzzers and [`proptest`] strategies. Generated values are bounded in depth
+//! and size, and only contain finite numbers, so they always survive a round
+//! trip through JSON text.
+
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::{collection, prelude::*};
+
+use crate::{JsonValue, Map, Number};
+
+/// Nesting depth of values generated by [`Arbitrary`] and [`json_value`].
+pub const MAX_DEPTH: u32 = 8;
+
+/// Elements per array/object generated by [`Arbitrary`] and [`json_value`].
+pub const MAX_LEN: usize = 8;
+
+impl<'a> Arbitrary<'a> for JsonValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_value(u, MAX_DEPTH)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured, depth: u32) -> arbitrary::Result<JsonValue> {
+    let max_kind = if depth == 0 { 3 } else { 5 };
+    Ok(match u.int_in_range(0..=max_kind)? {
+        0 => JsonValue::Null,
+        1 => JsonValue::Bool(u.arbitrary()?),
+        2 => JsonValue::Number(finite(u.arbitrary()?)),
+        3 => JsonValue::String(u.arbitrary()?),
+        4 => {
+            let len = u.arbitrary_len::<JsonValue>()?.min(MAX_LEN);
+            let mut items = Vec::with_capacity(len);
+            for _ in 0..len {
+                items.push(arbitrary_value(u, depth - 1)?);
+            }
+            JsonValue::Array(items)
+        }
+        _ => {
+            let len = u.arbitrary_len::<(String, JsonValue)>()?.min(MAX_LEN);
+            let mut map = Map::new();
+            for _ in 0..len {
+                map.insert(u.arbitrary()?, arbitrary_value(u, depth - 1)?);
+            }
+            JsonValue::Object(map)
+        }
+    })
+}
+
+fn finite(n: f64) -> Number {
+    Number::from_f64(n)
+        .filter(|_| n.is_finite())
+        .unwrap_or(Number::from(0u8))
+}
+
+/// Any value, bounded by [`MAX_DEPTH`] and [`MAX_LEN`].
+pub fn json_value() -> BoxedStrategy<JsonValue> {
+    json_value_with(MAX_DEPTH, MAX_LEN)
+}
+
+/// Any value nested at most `depth` levels with at most `len` elements per
+/// array/object.
+pub fn json_value_with(depth: u32, len: usize) -> BoxedStrategy<JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Bool),
+        any::<f64>().prop_map(|n| JsonValue::Number(finite(n))),
+        any::<String>().prop_map(JsonValue::String),
+    ];
+    leaf.prop_recursive(depth, depth * len as u32, len as u32, move |inner| {
+        prop_oneof![
+            collection::vec(inner.clone(), 0..=len).prop_map(JsonValue::Array),
+            collection::hash_map(any::<String>(), inner, 0..=len)
+                .prop_map(|map| JsonValue::Object(map.into_iter().collect())),
+        ]
+    })
+    .boxed()
+}
+
+/// A value [`parse`](crate::parse) accepts as a document: any value, as
+/// scalar roots are allowed by default, though mostly an array or object,
+/// the only roots [`scalar_roots(false)`](crate::ParserOptions::scalar_roots) accepts.
+pub fn json_document() -> BoxedStrategy<JsonValue> {
+    let len = MAX_LEN;
+    let inner = json_value_with(MAX_DEPTH - 1, len);
+    prop_oneof![
+        1 => json_value_with(0, 0),
+        3 => prop_oneof![
+            collection::vec(inner.clone(), 0..=len).prop_map(JsonValue::Array),
+            collection::hash_map(any::<String>(), inner, 0..=len)
+                .prop_map(|map| JsonValue::Object(map.into_iter().collect())),
+        ],
+    ]
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use proptest::prelude::*;
+
+    use crate::{parse, JsonValue};
+
+    use super::{json_document, MAX_LEN};
+
+    /// reference encoder for the round-trip properties
+    fn encode(value: &JsonValue, out: &mut String) {
+        match value {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Number(n) => out.push_str(&n.to_string()),
+            JsonValue::String(s) => encode_str(s, out),
+            JsonValue::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    encode(item, out);
+                }
+                out.push(']');
+            }
+            JsonValue::Object(map) => {
+                out.push('{');
+                for (i, (key, val)) in map.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    encode_str(key, out);
+                    out.push(':');
+                    encode(val, out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    fn encode_str(s: &str, out: &mut String) {
+        out.push('"');
+        for ch in s.chars() {
+            match ch {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                ch if ch.is_control() => out.push_str(&format!("\\u{:04x}", ch as u32)),
+                ch => out.push(ch),
+            }
+        }
+        out.push('"');
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(value in json_document()) {
+            let mut text = String::new();
+            encode(&value, &mut text);
+            prop_assert_eq!(parse(&text).ok(), Some(value));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_no_panic(text in r#"[\[\]{}":,.0-9eE+\-\\u/bfnrtalsxé😀 \n]{0,48}"#) {
+            crate::fuzzing::entry_points(text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_len() {
+        // all 0xff: the longest arrays and objects `arbitrary_len` allows
+        let data = [0xff; 4096];
+        let mut u = Unstructured::new(&data);
+        let mut value = JsonValue::arbitrary(&mut u).unwrap();
+        while let JsonValue::Array(items) = value {
+            assert!(items.len() <= MAX_LEN);
+            let Some(last) = items.into_iter().last() else {
+                break;
+            };
+            value = last;
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_roundtrip() {
+        let data: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let value = JsonValue::Array(vec![JsonValue::arbitrary(&mut u).unwrap()]);
+            let mut text = String::new();
+            encode(&value, &mut text);
+            assert_eq!(parse(&text).ok(), Some(value));
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Iterating over every value of a document, and rebuilding it.
+
+use std::{borrow::Borrow, collections::VecDeque, error::Error, fmt};
+
+use crate::{
+    map::{DefaultMapKind, Key, MapKind, Object},
+    JsonPointer, JsonValue,
+};
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// Every value with its JSON Pointer, depth first, parents before their
+    /// children.
+    pub fn walk(&self) -> Walk<'_, S, M> {
+        Walk::new(self, Order::DepthFirst, None)
+    }
+
+    /// Every value with its JSON Pointer, breadth first: the value itself,
+    /// then its children, then their children, and so on.
+    pub fn walk_bfs(&self) -> Walk<'_, S, M> {
+        Walk::new(self, Order::BreadthFirst, None)
+    }
+
+    /// Like [`walk`](JsonValue::walk), skipping values nested deeper than
+    /// `max_depth`; the value itself is at depth 0.
+    pub fn walk_max_depth(&self, max_depth: usize) -> Walk<'_, S, M> {
+        Walk::new(self, Order::DepthFirst, Some(max_depth))
+    }
+
+    /// The value at `pointer`, borrowed with its pointer; see
+    /// [`pointer`](JsonValue::pointer).
+    pub fn node(&self, pointer: &str) -> Option<NodeRef<'_, S, M>> {
+        Some(NodeRef {
+            value: self.pointer(pointer)?,
+            pointer: JsonPointer::parse(pointer).ok()?,
+        })
+    }
+
+    /// Every value satisfying `predicate`, borrowed with its pointer, in
+    /// [`walk`](JsonValue::walk) order.
+    ///
+    /// To change them, pass their pointers to
+    /// [`pointer_mut`](JsonValue::pointer_mut) once done with the borrows.
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<NodeRef<'_, S, M>>
+    where
+        F: FnMut(&JsonValue<S, M>) -> bool,
+    {
+        self.walk()
+            .filter(|(_, value)| predicate(value))
+            .map(|(pointer, value)| NodeRef { pointer, value })
+            .collect()
+    }
+
+    /// A copy of the value with `f(pointer, value)` applied to every value,
+    /// children before their parents, which get the transformed children.
+    ///
+    /// The first error stops the copy and is returned with the JSON Pointer of
+    /// the value it is about.
+    pub fn map_tree<F, E>(&self, mut f: F) -> Result<JsonValue<S, M>, MapTreeError<E>>
+    where
+        S: Clone,
+        F: FnMut(&JsonPointer, JsonValue<S, M>) -> Result<JsonValue<S, M>, E>,
+    {
+        map_tree_at(self, &mut JsonPointer::root(), &mut f)
+    }
+}
+
+fn map_tree_at<S, M, F, E>(
+    value: &JsonValue<S, M>,
+    pointer: &mut JsonPointer,
+    f: &mut F,
+) -> Result<JsonValue<S, M>, MapTreeError<E>>
+where
+    S: Key + Clone + Borrow<str>,
+    M: MapKind,
+    F: FnMut(&JsonPointer, JsonValue<S, M>) -> Result<JsonValue<S, M>, E>,
+{
+    let len = pointer.len();
+    let copy = match value {
+        JsonValue::Object(map) => {
+            let mut object = M::Map::with_capacity(map.len());
+            for (key, value) in map.iter() {
+                pointer.push(key.borrow());
+                let value = map_tree_at(value, pointer, f)?;
+                pointer.truncate(len);
+                object.insert(key.clone(), value);
+            }
+            JsonValue::Object(object)
+        }
+        JsonValue::Array(items) => {
+            let mut copy = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                pointer.push(&i.to_string());
+                copy.push(map_tree_at(item, pointer, f)?);
+                pointer.truncate(len);
+            }
+            JsonValue::Array(copy)
+        }
+        value => value.clone(),
+    };
+    f(pointer, copy).map_err(|error| MapTreeError {
+        pointer: pointer.clone(),
+        error,
+    })
+}
+
+/// A value borrowed from a document, with its JSON Pointer in that
+/// document; see [`JsonValue::node`] and [`JsonValue::find_all`].
+pub struct NodeRef<'v, S: Key = String, M: MapKind = DefaultMapKind> {
+    pointer: JsonPointer,
+    value: &'v JsonValue<S, M>,
+}
+
+impl<'v, S: Key + Borrow<str>, M: MapKind> NodeRef<'v, S, M> {
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    /// The value, borrowed for as long as the document.
+    pub fn value(&self) -> &'v JsonValue<S, M> {
+        self.value
+    }
+
+    pub fn into_pointer(self) -> JsonPointer {
+        self.pointer
+    }
+
+    /// The member `token` of an object or the element at index `token` of an
+    /// array.
+    pub fn child(&self, token: &str) -> Option<NodeRef<'v, S, M>> {
+        let pointer = self.pointer.child(token);
+        let value = self
+            .value
+            .pointer(&pointer.as_str()[self.pointer.len()..])?;
+        Some(NodeRef { pointer, value })
+    }
+}
+
+impl<S: Key, M: MapKind> Clone for NodeRef<'_, S, M> {
+    fn clone(&self) -> Self {
+        NodeRef {
+            pointer: self.pointer.clone(),
+            value: self.value,
+        }
+    }
+}
+
+impl<S: Key + fmt::Debug, M: MapKind> fmt::Debug for NodeRef<'_, S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("pointer", &self.pointer)
+            .field("value", self.value)
+            .finish()
+    }
+}
+
+/// Error returned by [`JsonValue::map_tree`]: the error of the
+/// transformation and where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapTreeError<E> {
+    pointer: JsonPointer,
+    error: E,
+}
+
+impl<E> MapTreeError<E> {
+    /// JSON Pointer of the value the transformation failed on.
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for MapTreeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {:?}", self.error, self.pointer.as_str())
+    }
+}
+
+impl<E: Error + 'static> Error for MapTreeError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    DepthFirst,
+    BreadthFirst,
+}
+
+/// Iterator over `(pointer, value)` pairs, see [`JsonValue::walk`].
+pub struct Walk<'v, S: Key, M: MapKind> {
+    /// `(pointer, depth, value)` still to visit
+    pending: VecDeque<(JsonPointer, usize, &'v JsonValue<S, M>)>,
+    order: Order,
+    max_depth: Option<usize>,
+}
+
+impl<'v, S: Key + Borrow<str>, M: MapKind> Walk<'v, S, M> {
+    fn new(value: &'v JsonValue<S, M>, order: Order, max_depth: Option<usize>) -> Self {
+        Walk {
+            pending: VecDeque::from([(JsonPointer::root(), 0, value)]),
+            order,
+            max_depth,
+        }
+    }
+}
+
+impl<'v, S: Key + Borrow<str>, M: MapKind> Iterator for Walk<'v, S, M> {
+    type Item = (JsonPointer, &'v JsonValue<S, M>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pointer, depth, value) = match self.order {
+            Order::DepthFirst => self.pending.pop_back()?,
+            Order::BreadthFirst => self.pending.pop_front()?,
+        };
+        if self.max_depth.map_or(true, |max| depth < max) {
+            let child = |token: &str, child| (pointer.child(token), depth + 1, child);
+            let children: Vec<_> = match value {
+                JsonValue::Object(map) => map
+                    .iter()
+                    .map(|(key, value)| child(key.borrow(), value))
+                    .collect(),
+                JsonValue::Array(items) => items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| child(&i.to_string(), item))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            match self.order {
+                // the stack pops the first child next
+                Order::DepthFirst => self.pending.extend(children.into_iter().rev()),
+                Order::BreadthFirst => self.pending.extend(children),
+            }
+        }
+        Some((pointer, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonPointer, JsonValue};
+
+    fn pointers<'v>(walk: impl Iterator<Item = (JsonPointer, &'v JsonValue)>) -> Vec<JsonPointer> {
+        walk.map(|(pointer, _)| pointer).collect()
+    }
+
+    #[test]
+    fn test_map_tree() {
+        // numeric strings to numbers, failing on other strings
+        let coerce = |_: &JsonPointer, value: JsonValue| match value {
+            JsonValue::String(s) => match s.parse::<i64>() {
+                Ok(n) => Ok(JsonValue::from(n)),
+                Err(_) => Err(format!("not a number: {}", s)),
+            },
+            value => Ok(value),
+        };
+        let value = parse(r#"[{"a": "1"}, ["2", 3]]"#).unwrap();
+        assert_eq!(
+            value.map_tree(coerce),
+            Ok(parse(r#"[{"a": 1}, [2, 3]]"#).unwrap())
+        );
+
+        let value = parse(r#"[{"a": "1"}, ["2", "x/y", "z"]]"#).unwrap();
+        let err = value.map_tree(coerce).unwrap_err();
+        assert_eq!(
+            (err.pointer().as_str(), err.error().as_str()),
+            ("/1/1", "not a number: x/y")
+        );
+        assert_eq!(err.to_string(), r#"not a number: x/y at "/1/1""#);
+
+        // parents see their transformed children
+        let mut seen = Vec::new();
+        let lens = value.map_tree(|pointer, value| {
+            seen.push(pointer.to_string());
+            Ok::<_, ()>(match value {
+                JsonValue::Array(items) => JsonValue::from(items.len() as u64),
+                value => value,
+            })
+        });
+        assert_eq!(lens, Ok(JsonValue::from(2u64)));
+        assert_eq!(seen, ["/0/a", "/0", "/1/0", "/1/1", "/1/2", "/1", ""]);
+    }
+
+    #[test]
+    fn test_find_all() {
+        let value = parse(r#"{"a": [1, "x", {"b": 2}], "c": "y"}"#).unwrap();
+        let mut strings: Vec<_> = value
+            .find_all(|value| matches!(value, JsonValue::String(_)))
+            .into_iter()
+            .map(|node| (node.pointer().to_string(), node.value()))
+            .collect();
+        strings.sort_by(|a, b| a.0.cmp(&b.0));
+        let (x, y) = (JsonValue::from("x"), JsonValue::from("y"));
+        assert_eq!(strings, [("/a/1".to_owned(), &x), ("/c".to_owned(), &y)]);
+
+        // nodes borrow the document, not a copy of it
+        let node = value.node("/a").unwrap();
+        assert!(std::ptr::eq(node.value(), value.pointer("/a").unwrap()));
+        let child = node.child("2").and_then(|node| node.child("b")).unwrap();
+        assert_eq!(child.pointer(), "/a/2/b");
+        assert!(std::ptr::eq(
+            child.value(),
+            value.pointer("/a/2/b").unwrap()
+        ));
+        assert!(node.child("3").is_none());
+        assert!(value.node("a").is_none());
+        assert_eq!(value.node("").unwrap().into_pointer(), JsonPointer::root());
+    }
+
+    #[test]
+    fn test_walk() {
+        let value = parse(r#"[[1, [2]], {"a/b": 3}, 4]"#).unwrap();
+        assert_eq!(
+            pointers(value.walk()),
+            ["", "/0", "/0/0", "/0/1", "/0/1/0", "/1", "/1/a~1b", "/2"]
+        );
+        assert_eq!(
+            pointers(value.walk_bfs()),
+            ["", "/0", "/1", "/2", "/0/0", "/0/1", "/1/a~1b", "/0/1/0"]
+        );
+        assert_eq!(pointers(value.walk_max_depth(1)), ["", "/0", "/1", "/2"]);
+        assert_eq!(pointers(value.walk_max_depth(0)), [""]);
+        assert_eq!(
+            value
+                .walk()
+                .find(|(pointer, _)| *pointer == "/1/a~1b")
+                .map(|(_, v)| v),
+            value.pointer("/1/a~1b")
+        );
+    }
+}
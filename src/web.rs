@@ -0,0 +1,251 @@
+//! [axum](https://docs.rs/axum) support: [`Json`] extracts request bodies
+//! with this crate's parser, its limits and diagnostics, and writes response
+//! bodies.
+
+use std::fmt;
+
+use axum_core::{
+    body::Body,
+    extract::{rejection::BytesRejection, FromRequest, Request},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    body::check_media_type, from_http_body_with, map::Map, JsonValue, ParseError, ParserOptions,
+};
+
+/// A JSON request or response body.
+///
+/// As an extractor, it parses the body of requests with a JSON content type
+/// as [`from_http_body`] does, and converts the document to `T`. The parser
+/// options are the [`ParserOptions`] in the request extensions, e.g. added
+/// with axum's `Extension` layer, or [`ParserOptions::untrusted`] without
+/// one; the size of the body is bounded by axum's `DefaultBodyLimit`.
+///
+/// As a response, it writes `T` as compact JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T = JsonValue>(pub T);
+
+impl<S, T> FromRequest<S> for Json<T>
+where
+    S: Send + Sync,
+    T: TryFrom<JsonValue>,
+    T::Error: fmt::Display,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = match req.headers().get(header::CONTENT_TYPE) {
+            Some(value) => value.to_str().unwrap_or_default().to_owned(),
+            None => return Err(JsonRejection::UnsupportedMediaType),
+        };
+        if check_media_type(&content_type).is_err() {
+            return Err(JsonRejection::UnsupportedMediaType);
+        }
+        let options = req
+            .extensions()
+            .get::<ParserOptions>()
+            .cloned()
+            .unwrap_or_else(ParserOptions::untrusted);
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(JsonRejection::Body)?;
+        let value = from_http_body_with(&content_type, &body, &options).map_err(|error| {
+            JsonRejection::Parse {
+                diagnostic: error.to_json(&String::from_utf8_lossy(&body)),
+                error: Box::new(error),
+            }
+        })?;
+        T::try_from(value)
+            .map(Json)
+            .map_err(|err| JsonRejection::Convert(err.to_string()))
+    }
+}
+
+impl<T: Into<JsonValue>> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        let body = self.0.into().to_string();
+        json_response(StatusCode::OK, body)
+    }
+}
+
+fn json_response(status: StatusCode, body: String) -> Response {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// Why [`Json`] rejected a request.
+///
+/// As a response: `415 Unsupported Media Type` for a body that isn't JSON in
+/// UTF-8, `400 Bad Request` with the diagnostic of [`ParseError::to_json`]
+/// for one that doesn't parse, `422 Unprocessable Entity` for a document `T`
+/// can't be built from, and axum's response for a body that can't be read.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsonRejection {
+    UnsupportedMediaType,
+    Body(BytesRejection),
+    /// The error, boxed as it's large, and its [`ParseError::to_json`]
+    /// diagnostic.
+    Parse {
+        error: Box<ParseError>,
+        diagnostic: JsonValue,
+    },
+    /// The message of the conversion error.
+    Convert(String),
+}
+
+impl IntoResponse for JsonRejection {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            JsonRejection::Body(rejection) => return rejection.into_response(),
+            JsonRejection::Parse { diagnostic, .. } => {
+                let body = diagnostic.to_string();
+                return json_response(StatusCode::BAD_REQUEST, body);
+            }
+            JsonRejection::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "expected a JSON content type".to_owned(),
+            ),
+            JsonRejection::Convert(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+        };
+        let message: JsonValue =
+            JsonValue::Object(Map::from([("message".to_owned(), message.into())]));
+        json_response(status, message.to_string())
+    }
+}
+
+impl fmt::Display for JsonRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonRejection::UnsupportedMediaType => f.write_str("expected a JSON content type"),
+            JsonRejection::Body(rejection) => write!(f, "{}", rejection),
+            JsonRejection::Parse { error, .. } => write!(f, "{}", error),
+            JsonRejection::Convert(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for JsonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonRejection::Body(rejection) => Some(rejection),
+            JsonRejection::Parse { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_core::{
+        body::Body,
+        extract::{FromRequest, Request},
+        response::IntoResponse,
+    };
+    use bytes::Bytes;
+    use http::StatusCode;
+
+    use crate::{parse, DuplicateKeys, ErrorKind, JsonValue, ParserOptions};
+
+    use super::{Json, JsonRejection};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn request(content_type: &str, body: &str) -> Request {
+        Request::builder()
+            .header("content-type", content_type)
+            .body(Body::from(body.to_owned()))
+            .unwrap()
+    }
+
+    fn extract(req: Request) -> Result<Json, JsonRejection> {
+        block_on(Json::from_request(req, &()))
+    }
+
+    fn body(rejection: JsonRejection) -> (StatusCode, JsonValue) {
+        let response = rejection.into_response();
+        let status = response.status();
+        let body = block_on(Bytes::from_request(Request::new(response.into_body()), &()));
+        (status, crate::parse_bytes(&body.unwrap()).unwrap())
+    }
+
+    #[test]
+    fn test_extract() {
+        let req = request("application/json; charset=utf-8", r#"{"a": [1]}"#);
+        assert_eq!(extract(req).unwrap().0, parse(r#"{"a": [1]}"#).unwrap());
+        let req = request("application/vnd.api+json", "[]");
+        assert!(extract(req).is_ok());
+
+        let req = request("application/json", "\u{feff}[]");
+        assert!(extract(req).is_ok());
+
+        let rejection = extract(request("text/plain", "[]")).unwrap_err();
+        assert!(matches!(rejection, JsonRejection::UnsupportedMediaType));
+        assert_eq!(body(rejection).0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let rejection = extract(request("application/json; charset=latin1", "[]")).unwrap_err();
+        assert!(matches!(rejection, JsonRejection::UnsupportedMediaType));
+
+        // `untrusted` options by default
+        let rejection = extract(request("application/json", r#"{"a": 1, "a": 2}"#)).unwrap_err();
+        let JsonRejection::Parse { error, .. } = &rejection else {
+            panic!("{:?}", rejection);
+        };
+        assert_eq!(error.kind(), ErrorKind::DuplicateKey);
+        let (status, diagnostic) = body(rejection);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(diagnostic.pointer("/code"), Some(&"E0009".into()));
+
+        let mut req = request("application/json", r#"{"a": 1, "a": 2}"#);
+        let options = ParserOptions::untrusted().duplicate_keys(DuplicateKeys::LastWins);
+        req.extensions_mut().insert(options);
+        assert!(extract(req).is_ok());
+    }
+
+    #[test]
+    fn test_convert() {
+        struct Name(String);
+
+        impl TryFrom<JsonValue> for Name {
+            type Error = &'static str;
+
+            fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+                match value.pointer("/name") {
+                    Some(JsonValue::String(name)) => Ok(Name(name.clone())),
+                    _ => Err("missing name"),
+                }
+            }
+        }
+
+        let req = request("application/json", r#"{"name": "x"}"#);
+        let name = block_on(Json::<Name>::from_request(req, &())).ok().unwrap();
+        assert_eq!(name.0 .0, "x");
+        let req = request("application/json", "[]");
+        let rejection = block_on(Json::<Name>::from_request(req, &()))
+            .err()
+            .unwrap();
+        let (status, body) = body(rejection);
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body, parse(r#"{"message": "missing name"}"#).unwrap());
+    }
+
+    #[test]
+    fn test_response() {
+        let response = Json(parse(r#"["a\n", {"b": null}]"#).unwrap()).into_response();
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = block_on(Bytes::from_request(Request::new(response.into_body()), &()));
+        assert_eq!(body.unwrap(), r#"["a\n",{"b":null}]"#);
+    }
+}
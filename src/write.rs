@@ -0,0 +1,711 @@
+//! Writing values as JSON text.
+
+use std::{
+    borrow::Borrow,
+    fmt::{self, Write},
+};
+
+use crate::{
+    map::{Key, MapKind, Object},
+    parser::grow_stack,
+    JsonPointer, JsonValue, NonFiniteNumbers,
+};
+
+/// Options for [`JsonValue::to_string_with`].
+///
+/// Numbers JSON can't represent, NaN and the infinities, are written as
+/// `null` unless [`non_finite_numbers`](WriteOptions::non_finite_numbers)
+/// says otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    indent: Option<String>,
+    width: Option<usize>,
+    spaces: bool,
+    js_safe: bool,
+    html_safe: bool,
+    unicode_escapes: bool,
+    escape_slash: bool,
+    non_finite_numbers: NonFiniteNumbers,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: None,
+            width: None,
+            spaces: false,
+            js_safe: false,
+            html_safe: false,
+            unicode_escapes: false,
+            escape_slash: false,
+            non_finite_numbers: NonFiniteNumbers::Null,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Without whitespace: `{"a":[1,2]}`.
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    /// On one line, with a space after each `,` and `:`: `{"a": [1, 2]}`.
+    pub fn relaxed() -> Self {
+        Self::compact().spaces(true)
+    }
+
+    /// Every non-empty array and object broken over lines, indented by two
+    /// spaces.
+    pub fn pretty() -> Self {
+        Self::compact().indent("  ")
+    }
+
+    /// Break arrays and objects over lines, with `indent` before their
+    /// elements and members for each level of nesting.
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = Some(indent.into());
+        self
+    }
+
+    /// Write a space after each `,` and `:` between the elements and members
+    /// on one line; always the case with an indent.
+    pub fn spaces(mut self, spaces: bool) -> Self {
+        self.spaces = spaces;
+        self
+    }
+
+    /// With an indent, keep the arrays and objects that fit on one line of
+    /// `width` chars there, e.g. `{"a": [1, 2], "b": null}`, and break the
+    /// others, like `prettier` does; `None` breaks every one.
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Escape U+2028 and U+2029, which end lines in older JavaScript, and the
+    /// `/` of `</`, so the output can be inlined in an HTML `<script>` without
+    /// ending it early.
+    pub fn js_safe(mut self, js_safe: bool) -> Self {
+        self.js_safe = js_safe;
+        self
+    }
+
+    /// Escape `<`, `>` and `&` as `\u003c`, `\u003e` and `\u0026`, so no
+    /// markup or entity can appear in the output when it ends up in HTML.
+    pub fn html_safe(mut self, html_safe: bool) -> Self {
+        self.html_safe = html_safe;
+        self
+    }
+
+    /// Escape every control character as `\u000a` and the like, rather than
+    /// `\n`, `\r`, `\t`, `\b` and `\f` with the short escapes they have.
+    pub fn unicode_escapes(mut self, unicode_escapes: bool) -> Self {
+        self.unicode_escapes = unicode_escapes;
+        self
+    }
+
+    /// Escape every `/` as `\/`, as some consumers expect.
+    pub fn escape_slash(mut self, escape_slash: bool) -> Self {
+        self.escape_slash = escape_slash;
+        self
+    }
+
+    /// How NaN and the infinities are written: as `null`, the default, as
+    /// the `NaN`, `Infinity` and `-Infinity` tokens Python's `json` module
+    /// reads with [`NonFiniteNumbers::Accept`], or not at all with
+    /// [`NonFiniteNumbers::Error`], for which
+    /// [`try_to_string_with`](JsonValue::try_to_string_with) fails.
+    pub fn non_finite_numbers(mut self, policy: NonFiniteNumbers) -> Self {
+        self.non_finite_numbers = policy;
+        self
+    }
+}
+
+impl WriteOptions {
+    /// the `,` and `:` between elements and members on one line
+    pub(crate) fn separators(&self) -> (&'static str, &'static str) {
+        if self.spaces || self.indent.is_some() {
+            (", ", ": ")
+        } else {
+            (",", ":")
+        }
+    }
+}
+
+impl<S: Key + Borrow<str>, M: MapKind> JsonValue<S, M> {
+    /// The JSON text of the value, with every non-empty array and object
+    /// broken over lines and `indent` before their elements and members for
+    /// each level of nesting.
+    pub fn to_string_pretty(&self, indent: &str) -> String {
+        self.to_string_with(&WriteOptions::compact().indent(indent))
+    }
+
+    /// The JSON text of the value, written as `options` say; with
+    /// [`NonFiniteNumbers::Error`], NaN and the infinities are written as
+    /// `null`, see [`try_to_string_with`](JsonValue::try_to_string_with).
+    pub fn to_string_with(&self, options: &WriteOptions) -> String {
+        let mut out = String::new();
+        Writer {
+            out: &mut out,
+            options,
+            depth: 0,
+        }
+        .value(self, 0);
+        out
+    }
+
+    /// Like [`to_string_with`](JsonValue::to_string_with), failing on the
+    /// first NaN or infinity with [`NonFiniteNumbers::Error`].
+    pub fn try_to_string_with(&self, options: &WriteOptions) -> Result<String, WriteError> {
+        check_finite(self, &JsonPointer::root(), options)?;
+        Ok(self.to_string_with(options))
+    }
+}
+
+/// `value`, at `pointer` in its document, on one line with the separators
+/// of `options`; fails like [`JsonValue::try_to_string_with`]
+pub(crate) fn write_flat<S: Key + Borrow<str>, M: MapKind>(
+    out: &mut String,
+    value: &JsonValue<S, M>,
+    pointer: &JsonPointer,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    check_finite(value, pointer, options)?;
+    Writer {
+        out,
+        options,
+        depth: 0,
+    }
+    .flat(value);
+    Ok(())
+}
+
+/// Error of [`JsonValue::try_to_string_with`]: the number at `pointer` is NaN
+/// or infinite, which JSON can't represent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteError {
+    pointer: JsonPointer,
+}
+
+impl WriteError {
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("can't write a non-finite number")?;
+        if !self.pointer.is_root() {
+            write!(f, " at {:?}", self.pointer.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// whether `value` is NaN or infinite
+fn is_non_finite<S: Key, M: MapKind>(value: &JsonValue<S, M>) -> bool {
+    matches!(value, JsonValue::Number(n) if n.as_f64().is_some_and(|n| !n.is_finite()))
+}
+
+/// the error of the first NaN or infinity of `value`, at `pointer` in its
+/// document, if `options` fail on them
+fn check_finite<S: Key + Borrow<str>, M: MapKind>(
+    value: &JsonValue<S, M>,
+    pointer: &JsonPointer,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    if options.non_finite_numbers == NonFiniteNumbers::Error && has_non_finite(value) {
+        if let Some((within, _)) = value.walk().find(|(_, value)| is_non_finite(value)) {
+            let mut pointer = pointer.clone();
+            for token in within.tokens() {
+                pointer.push(&token);
+            }
+            return Err(WriteError { pointer });
+        }
+    }
+    Ok(())
+}
+
+/// whether `value` is, or has at any depth, a NaN or infinity
+fn has_non_finite<S: Key, M: MapKind>(value: &JsonValue<S, M>) -> bool {
+    match value {
+        JsonValue::Array(items) => items.iter().any(has_non_finite),
+        JsonValue::Object(map) => map.iter().any(|(_, value)| has_non_finite(value)),
+        value => is_non_finite(value),
+    }
+}
+
+/// The JSON text of the value without whitespace, see
+/// [`WriteOptions::compact`], or with `{:#}` as [`WriteOptions::pretty`]
+/// writes it.
+impl<S: Key + Borrow<str>, M: MapKind> fmt::Display for JsonValue<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = if f.alternate() {
+            WriteOptions::pretty()
+        } else {
+            WriteOptions::compact()
+        };
+        f.write_str(&self.to_string_with(&options))
+    }
+}
+
+struct Writer<'w> {
+    out: &'w mut String,
+    options: &'w WriteOptions,
+    /// nesting level of the line being written
+    depth: usize,
+}
+
+impl Writer<'_> {
+    /// `value`, followed on its line by `trailing` chars, e.g. a `,`
+    fn value<S: Key + Borrow<str>, M: MapKind>(
+        &mut self,
+        value: &JsonValue<S, M>,
+        trailing: usize,
+    ) {
+        grow_stack(|| self.value_inner(value, trailing))
+    }
+
+    fn value_inner<S: Key + Borrow<str>, M: MapKind>(
+        &mut self,
+        value: &JsonValue<S, M>,
+        trailing: usize,
+    ) {
+        let broken = match value {
+            JsonValue::Array(items) => !items.is_empty() && !self.fits(value, trailing),
+            JsonValue::Object(map) => !map.is_empty() && !self.fits(value, trailing),
+            _ => false,
+        };
+        match value {
+            JsonValue::Array(items) if broken => {
+                self.out.push('[');
+                self.depth += 1;
+                for (i, item) in items.iter().enumerate() {
+                    self.line();
+                    let last = i + 1 == items.len();
+                    self.value(item, usize::from(!last));
+                    if !last {
+                        self.out.push(',');
+                    }
+                }
+                self.depth -= 1;
+                self.line();
+                self.out.push(']');
+            }
+            JsonValue::Object(map) if broken => {
+                self.out.push('{');
+                self.depth += 1;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    self.line();
+                    write_string(self.out, key.borrow(), self.options);
+                    self.out.push_str(": ");
+                    let last = i + 1 == map.len();
+                    self.value(value, usize::from(!last));
+                    if !last {
+                        self.out.push(',');
+                    }
+                }
+                self.depth -= 1;
+                self.line();
+                self.out.push('}');
+            }
+            _ => self.flat(value),
+        }
+    }
+
+    /// `value` on one line
+    fn flat<S: Key + Borrow<str>, M: MapKind>(&mut self, value: &JsonValue<S, M>) {
+        grow_stack(|| self.flat_inner(value))
+    }
+
+    fn flat_inner<S: Key + Borrow<str>, M: MapKind>(&mut self, value: &JsonValue<S, M>) {
+        let (comma, colon) = self.options.separators();
+        match value {
+            JsonValue::Array(items) => {
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(comma);
+                    }
+                    self.flat(item);
+                }
+                self.out.push(']');
+            }
+            JsonValue::Object(map) => {
+                self.out.push('{');
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(comma);
+                    }
+                    write_string(self.out, key.borrow(), self.options);
+                    self.out.push_str(colon);
+                    self.flat(value);
+                }
+                self.out.push('}');
+            }
+            scalar => write_scalar(self.out, scalar, self.options),
+        }
+    }
+
+    /// whether `value` is written on one line, followed by `trailing` chars
+    fn fits<S: Key + Borrow<str>, M: MapKind>(
+        &self,
+        value: &JsonValue<S, M>,
+        trailing: usize,
+    ) -> bool {
+        if self.options.indent.is_none() {
+            return true;
+        }
+        let Some(width) = self.options.width else {
+            return false;
+        };
+        let line = self.out.rfind('\n').map_or(0, |i| i + 1);
+        let column = self.out[line..].chars().count();
+        width
+            .checked_sub(column + trailing)
+            .is_some_and(|budget| flat_width(value, budget, self.options).is_some())
+    }
+
+    /// a line break and the indent of `depth`
+    fn line(&mut self) {
+        if let Some(indent) = &self.options.indent {
+            self.out.push('\n');
+            for _ in 0..self.depth {
+                self.out.push_str(indent);
+            }
+        }
+    }
+}
+
+/// Chars `value` takes on one line, with a space after each separator;
+/// `None` once that's more than `budget`.
+fn flat_width<S: Key + Borrow<str>, M: MapKind>(
+    value: &JsonValue<S, M>,
+    budget: usize,
+    options: &WriteOptions,
+) -> Option<usize> {
+    let mut width = 2;
+    match value {
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                width += 2 * usize::from(i > 0);
+                width += flat_width(item, budget.checked_sub(width)?, options)?;
+            }
+        }
+        JsonValue::Object(map) => {
+            for (i, (key, value)) in map.iter().enumerate() {
+                width += 2 * usize::from(i > 0) + string_width(key.borrow(), options) + 2;
+                width += flat_width(value, budget.checked_sub(width)?, options)?;
+            }
+        }
+        JsonValue::String(s) => width = string_width(s.borrow(), options),
+        scalar => {
+            let mut text = String::new();
+            write_scalar(&mut text, scalar, options);
+            width = text.len();
+        }
+    }
+    (width <= budget).then_some(width)
+}
+
+/// Chars of `s` written by [`write_string`].
+fn string_width(s: &str, options: &WriteOptions) -> usize {
+    let mut prev = None;
+    let escaped: usize = s
+        .chars()
+        .map(|ch| {
+            let width = escape(ch, prev, options).map_or(1, |escape| escape.width());
+            prev = Some(ch);
+            width
+        })
+        .sum();
+    escaped + 2
+}
+
+/// a value other than an array or object; numbers JSON can't represent are
+/// written as `null`
+fn write_scalar<S: Key + Borrow<str>, M: MapKind>(
+    out: &mut String,
+    value: &JsonValue<S, M>,
+    options: &WriteOptions,
+) {
+    match value {
+        JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(n) => match n.as_f64() {
+            Some(n) if !n.is_finite() => out.push_str(match options.non_finite_numbers {
+                NonFiniteNumbers::Accept if n.is_nan() => "NaN",
+                NonFiniteNumbers::Accept if n > 0.0 => "Infinity",
+                NonFiniteNumbers::Accept => "-Infinity",
+                _ => "null",
+            }),
+            _ => {
+                let _ = write!(out, "{}", n);
+            }
+        },
+        JsonValue::String(s) => write_string(out, s.borrow(), options),
+        _ => out.push_str("null"),
+    }
+}
+
+/// how a char of a string is escaped
+enum Escape {
+    Short(&'static str),
+    /// `\uXXXX`
+    Unicode(char),
+}
+
+impl Escape {
+    fn width(&self) -> usize {
+        match self {
+            Escape::Short(escape) => escape.len(),
+            Escape::Unicode(_) => 6,
+        }
+    }
+}
+
+/// the escape of `ch`, following `prev`, if it has one: `"`, `\` and
+/// control characters always do, others according to the options
+fn escape(ch: char, prev: Option<char>, options: &WriteOptions) -> Option<Escape> {
+    Some(match ch {
+        '"' => Escape::Short("\\\""),
+        '\\' => Escape::Short("\\\\"),
+        ch if ch < ' ' && options.unicode_escapes => Escape::Unicode(ch),
+        '\n' => Escape::Short("\\n"),
+        '\r' => Escape::Short("\\r"),
+        '\t' => Escape::Short("\\t"),
+        '\u{8}' => Escape::Short("\\b"),
+        '\u{c}' => Escape::Short("\\f"),
+        ch if ch < ' ' => Escape::Unicode(ch),
+        '<' | '>' | '&' if options.html_safe => Escape::Unicode(ch),
+        '\u{2028}' | '\u{2029}' if options.js_safe => Escape::Unicode(ch),
+        '/' if options.escape_slash => Escape::Short("\\/"),
+        // no `</` is left once `<` is escaped
+        '/' if options.js_safe && !options.html_safe && prev == Some('<') => Escape::Short("\\/"),
+        _ => return None,
+    })
+}
+
+/// `s` quoted and escaped
+pub(crate) fn write_string(out: &mut String, s: &str, options: &WriteOptions) {
+    out.push('"');
+    let mut prev = None;
+    for ch in s.chars() {
+        match escape(ch, prev, options) {
+            Some(Escape::Short(escape)) => out.push_str(escape),
+            Some(Escape::Unicode(ch)) => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            None => out.push(ch),
+        }
+        prev = Some(ch);
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_generic, BTreeMapKind, JsonValue, NonFiniteNumbers, ParserOptions};
+
+    use super::WriteOptions;
+
+    fn parse(input: &str) -> JsonValue<String, BTreeMapKind> {
+        parse_generic(input, &ParserOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_compact() {
+        let value = parse(r#"{"b": [1, 2.5, -1e400], "a": {"x": "q\"\n\u0001é"}, "c": []}"#);
+        assert_eq!(
+            value.to_string_with(&WriteOptions::compact()),
+            r#"{"a":{"x":"q\"\n\u0001é"},"b":[1,2.5,null],"c":[]}"#
+        );
+        assert_eq!(
+            value.to_string_with(&WriteOptions::relaxed()),
+            r#"{"a": {"x": "q\"\n\u0001é"}, "b": [1, 2.5, null], "c": []}"#
+        );
+    }
+
+    #[test]
+    fn test_to_string() {
+        let mut value = parse(r#"[{"a": "x"}, 1]"#);
+        if let JsonValue::Array(items) = &mut value {
+            items[1] = JsonValue::String("tab\t \"quoted\" \\ \u{1f}".to_owned());
+            items.push(JsonValue::Null);
+        }
+        let text = value.to_string();
+        assert_eq!(text, r#"[{"a":"x"},"tab\t \"quoted\" \\ \u001f",null]"#);
+        assert_eq!(parse(&text), value);
+        assert_eq!(
+            value.to_string_pretty("    "),
+            "[\n    {\n        \"a\": \"x\"\n    },\n    \"tab\\t \\\"quoted\\\" \\\\ \\u001f\",\n    null\n]"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let value = parse(r#"{"a": [1, "x"]}"#);
+        assert_eq!(format!("{}", value), r#"{"a":[1,"x"]}"#);
+        assert_eq!(
+            format!("{:#}", value),
+            "{\n  \"a\": [\n    1,\n    \"x\"\n  ]\n}"
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_member_order() {
+        let input = "{\n  \"b\": 1,\n  \"a\": {\n    \"d\": 2,\n    \"c\": 3\n  }\n}";
+        let value = crate::parse(input).unwrap();
+        assert_eq!(value.to_string(), r#"{"b":1,"a":{"d":2,"c":3}}"#);
+        assert_eq!(format!("{:#}", value), input);
+    }
+
+    #[test]
+    fn test_js_safe() {
+        let value = parse(r#"["</script><!-- a/b \u2028\u2029"]"#);
+        assert_eq!(
+            value.to_string_with(&WriteOptions::compact().js_safe(true)),
+            r#"["<\/script><!-- a/b \u2028\u2029"]"#
+        );
+        assert_eq!(
+            value.to_string(),
+            "[\"</script><!-- a/b \u{2028}\u{2029}\"]"
+        );
+        // escapes count towards the width
+        let options = WriteOptions::pretty().width(Some(18)).js_safe(true);
+        assert_eq!(
+            parse(r#"["\u2028", "</"]"#).to_string_with(&options),
+            "[\"\\u2028\", \"<\\/\"]"
+        );
+        assert_eq!(
+            parse(r#"["\u2028", "</", 1]"#).to_string_with(&options),
+            "[\n  \"\\u2028\",\n  \"<\\/\",\n  1\n]"
+        );
+    }
+
+    #[test]
+    fn test_html_safe() {
+        let value = parse(r#"{"<a href='x'>": "&amp; </b>"}"#);
+        let options = WriteOptions::compact().html_safe(true);
+        assert_eq!(
+            value.to_string_with(&options),
+            r#"{"\u003ca href='x'\u003e":"\u0026amp; \u003c/b\u003e"}"#
+        );
+        assert_eq!(
+            crate::parse(&value.to_string_with(&options)).unwrap(),
+            crate::parse(r#"{"<a href='x'>": "&amp; </b>"}"#).unwrap()
+        );
+        let options = options.js_safe(true);
+        assert_eq!(
+            parse(r#"["</"]"#).to_string_with(&options),
+            r#"["\u003c/"]"#
+        );
+    }
+
+    #[test]
+    fn test_escape_style() {
+        let value = parse(r#"["a/b\n\t\u0001", "</"]"#);
+        assert_eq!(value.to_string(), r#"["a/b\n\t\u0001","</"]"#);
+        let options = WriteOptions::compact().unicode_escapes(true);
+        assert_eq!(
+            value.to_string_with(&options),
+            r#"["a/b\u000a\u0009\u0001","</"]"#
+        );
+        let options = options.escape_slash(true);
+        let text = value.to_string_with(&options);
+        assert_eq!(text, r#"["a\/b\u000a\u0009\u0001","<\/"]"#);
+        assert_eq!(parse(&text), value);
+        // escapes count towards the width
+        let options = WriteOptions::pretty().width(Some(14)).escape_slash(true);
+        assert_eq!(
+            parse(r#"["/", "//"]"#).to_string_with(&options),
+            "[\"\\/\", \"\\/\\/\"]"
+        );
+        assert_eq!(
+            parse(r#"["/", "///"]"#).to_string_with(&options),
+            "[\n  \"\\/\",\n  \"\\/\\/\\/\"\n]"
+        );
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let input = r#"[NaN, Infinity, {"a": -Infinity}, 1.5]"#;
+        let options = ParserOptions::default().non_finite_numbers(NonFiniteNumbers::Accept);
+        let value: JsonValue<String, BTreeMapKind> = parse_generic(input, &options).unwrap();
+        assert_eq!(value.to_string(), r#"[null,null,{"a":null},1.5]"#);
+
+        let write = WriteOptions::compact().non_finite_numbers(NonFiniteNumbers::Accept);
+        let text = value.to_string_with(&write);
+        assert_eq!(text, r#"[NaN,Infinity,{"a":-Infinity},1.5]"#);
+        let reparsed: JsonValue<String, BTreeMapKind> = parse_generic(&text, &options).unwrap();
+        assert_eq!(reparsed.to_string_with(&write), text);
+
+        let write = WriteOptions::compact().non_finite_numbers(NonFiniteNumbers::Error);
+        assert_eq!(value.to_string_with(&write), value.to_string());
+        let err = value.try_to_string_with(&write).unwrap_err();
+        assert_eq!(err.pointer().as_str(), "/0");
+        assert_eq!(err.to_string(), "can't write a non-finite number at \"/0\"");
+        let value = parse(r#"[{"a": [1e400]}]"#);
+        let err = value.try_to_string_with(&write).unwrap_err();
+        assert_eq!(err.pointer().as_str(), "/0/a/0");
+        let value = parse(r#"[{"a": [1e300]}]"#);
+        assert_eq!(value.try_to_string_with(&write).unwrap(), value.to_string());
+    }
+
+    #[test]
+    fn test_pretty() {
+        let value = parse(r#"{"b": [1, {}], "a": {"x": null}, "c": []}"#);
+        assert_eq!(
+            value.to_string_with(&WriteOptions::pretty()),
+            "{\n  \"a\": {\n    \"x\": null\n  },\n  \"b\": [\n    1,\n    {}\n  ],\n  \"c\": []\n}"
+        );
+        assert_eq!(
+            parse("[[1]]").to_string_with(&WriteOptions::compact().indent("\t")),
+            "[\n\t[\n\t\t1\n\t]\n]"
+        );
+    }
+
+    #[test]
+    fn test_width() {
+        let value = parse(
+            r#"{"name": "é", "tags": ["a", "b"], "points": [[1, 2], [3, 4], [5, 6], [7, 8]],
+                "nested": {"deep": {"deeper": [true, false, null]}}}"#,
+        );
+        let options = WriteOptions::pretty().width(Some(40));
+        assert_eq!(
+            value.to_string_with(&options),
+            r#"{
+  "name": "é",
+  "nested": {
+    "deep": {
+      "deeper": [true, false, null]
+    }
+  },
+  "points": [
+    [1, 2],
+    [3, 4],
+    [5, 6],
+    [7, 8]
+  ],
+  "tags": ["a", "b"]
+}"#
+        );
+        // a line of exactly the width fits, trailing `,` included
+        let value = parse(r#"[[1, 2], "x"]"#);
+        assert_eq!(
+            value.to_string_with(&options.clone().width(Some(13))),
+            r#"[[1, 2], "x"]"#
+        );
+        assert_eq!(
+            value.to_string_with(&options.clone().width(Some(9))),
+            "[\n  [1, 2],\n  \"x\"\n]"
+        );
+        assert_eq!(
+            value.to_string_with(&options.width(Some(8))),
+            "[\n  [\n    1,\n    2\n  ],\n  \"x\"\n]"
+        );
+    }
+}